@@ -9,6 +9,10 @@ use crate::{V8Type, V8TypeGarbageCollected};
 pub enum IntoV8Error {
 	ContextNotInitialized,
 	NewInstanceFailed,
+	/// The isolate was created without a cpp heap, so the value can not be garbage collected.
+	CppHeapNotAttached,
+	/// The value refers to something that no longer exists.
+	Expired,
 	Serde(#[from] serde_v8::Error),
 }
 
@@ -19,12 +23,12 @@ impl fmt::Display for IntoV8Error {
 }
 
 pub trait IntoV8 {
-	fn into_v8<'s>(scope: &'s mut v8::HandleScope<'s>, value: Self) -> v8::Local<'s, v8::Value>;
+	fn into_v8<'s>(scope: &mut v8::HandleScope<'s>, value: Self) -> v8::Local<'s, v8::Value>;
 }
 
 pub trait TryIntoV8 {
 	fn try_into_v8<'s>(
-		scope: &'s mut v8::HandleScope<'s>,
+		scope: &mut v8::HandleScope<'s>,
 		value: Self,
 	) -> Result<v8::Local<'s, v8::Value>, IntoV8Error>;
 }
@@ -33,7 +37,7 @@ impl<T> IntoV8 for T
 where
 	T: TryIntoV8,
 {
-	fn into_v8<'s>(scope: &'s mut v8::HandleScope<'s>, value: Self) -> v8::Local<'s, v8::Value> {
+	fn into_v8<'s>(scope: &mut v8::HandleScope<'s>, value: Self) -> v8::Local<'s, v8::Value> {
 		<T as TryIntoV8>::try_into_v8(scope, value).expect("into_v8")
 	}
 }
@@ -62,7 +66,7 @@ where
 	T: V8Type + Serialize,
 {
 	fn try_into_v8<'s>(
-		scope: &'s mut v8::HandleScope<'s>,
+		scope: &mut v8::HandleScope<'s>,
 		value: Self,
 	) -> Result<v8::Local<'s, v8::Value>, IntoV8Error> {
 		Ok(to_v8(scope, value)?)
@@ -14,8 +14,8 @@ use scoped_thread_local::scoped_thread_local;
 pub use self::{
 	class::{Class, ClassContext},
 	export::{Export, ExportInitFn},
-	from_v8::{FromV8, TryFromV8},
-	into_v8::{IntoV8, TryIntoV8},
+	from_v8::{FromV8, TryFromV8, TryFromV8Error},
+	into_v8::{IntoV8, IntoV8Error, TryIntoV8},
 	module::Module,
 	tags::Tags,
 	v8type::{V8Type, V8TypeGarbageCollected, V8TypeInfo},
@@ -1,17 +1,113 @@
 use proc_macro2::TokenStream;
-use quote::quote;
-use syn::ItemStruct;
+use quote::{quote, quote_spanned};
+use syn::{meta::ParseNestedMeta, spanned::Spanned, Error, Ident, ItemStruct, LitStr, Path, Type};
+
+/// The options of `#[component(...)]`.
+#[derive(Default)]
+struct Options {
+	value: Option<Type>,
+	name: Option<LitStr>,
+	serialize: Option<Path>,
+	default: Option<Path>,
+	v8: Option<Path>,
+}
+
+impl Options {
+	fn parse(item_struct: &ItemStruct) -> syn::Result<Self> {
+		let mut options = Self::default();
+
+		for attr in item_struct
+			.attrs
+			.iter()
+			.filter(|v| v.path().is_ident("component"))
+		{
+			attr.parse_nested_meta(|meta| options.parse_meta(meta))?;
+		}
+
+		Ok(options)
+	}
+
+	fn parse_meta(&mut self, meta: ParseNestedMeta) -> syn::Result<()> {
+		let duplicate = || Error::new_spanned(&meta.path, "duplicate component option");
+
+		if meta.path.is_ident("value") {
+			if self.value.is_some() {
+				return Err(duplicate());
+			}
+
+			self.value = Some(meta.value()?.parse()?);
+		} else if meta.path.is_ident("name") {
+			if self.name.is_some() {
+				return Err(duplicate());
+			}
+
+			self.name = Some(meta.value()?.parse()?);
+		} else if meta.path.is_ident("serialize") {
+			if self.serialize.is_some() {
+				return Err(duplicate());
+			}
+
+			self.serialize = Some(meta.path);
+		} else if meta.path.is_ident("default") {
+			if self.default.is_some() {
+				return Err(duplicate());
+			}
+
+			self.default = Some(meta.path);
+		} else if meta.path.is_ident("v8") {
+			if self.v8.is_some() {
+				return Err(duplicate());
+			}
+
+			self.v8 = Some(meta.path);
+		} else {
+			return Err(meta.error("expected `value`, `name`, `serialize`, `default` or `v8`"));
+		}
+
+		Ok(())
+	}
+}
 
 pub fn derive(tokens: TokenStream) -> syn::Result<TokenStream> {
 	let item_struct: ItemStruct = syn::parse2(tokens)?;
 	let ident = &item_struct.ident;
-	let name = ident.to_string();
+	let options = Options::parse(&item_struct)?;
+	let name = options
+		.name
+		.unwrap_or_else(|| LitStr::new(&ident.to_string(), ident.span()));
+	let value = options
+		.value
+		.map(|value| quote! { #value })
+		.unwrap_or_else(|| quote! { Self });
+	// the component named with the span of the option, so unmet bounds point at the option
+	let spanned = |path: Path, method| {
+		let ident = Ident::new(&ident.to_string(), path.span());
+		let method = Ident::new(method, path.span());
+
+		quote_spanned! { path.span()=> .#method::<#ident>() }
+	};
+	let serialize = options.serialize.map(|path| spanned(path, "serialized"));
+	let default = options.default.map(|path| spanned(path, "defaulted"));
+	// needs the `v8` feature of torque-ecs
+	let v8 = options.v8.map(|path| spanned(path, "scripted"));
 
 	Ok(quote! {
-		impl ::torque_ecs::Component for  #ident {
+		impl ::torque_ecs::Component for #ident {
 			const NAME: &str = #name;
 
-			type Value = Self;
+			type Value = #value;
+		}
+
+		::torque_ecs::inventory::submit! {
+			::torque_ecs::registry::ComponentType::new::<#ident>(|| {
+				#[allow(unused_imports)]
+				use ::torque_ecs::registry::{DebugOpaque as _, DebugViaValue as _};
+
+				(&&::torque_ecs::registry::DebugProbe::<#ident>(::std::marker::PhantomData)).debug_fn()
+			})
+			#serialize
+			#default
+			#v8
 		}
 	})
 }
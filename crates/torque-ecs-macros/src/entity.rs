@@ -1,47 +1,155 @@
 use proc_macro2::TokenStream;
-use quote::{format_ident, quote};
-use syn::{parse_quote, ItemStruct, Type};
+use quote::quote;
+use syn::{
+	parse::{Parse, ParseStream},
+	parse_quote,
+	punctuated::Punctuated,
+	Error, Expr, ItemStruct, LitStr, Token, Type,
+};
+
+/// A component of `#[require(...)]`, set to its default value unless given one.
+struct Require {
+	component: Type,
+	value: Option<Expr>,
+}
+
+impl Parse for Require {
+	fn parse(input: ParseStream) -> syn::Result<Self> {
+		let component = input.parse()?;
+		let value = if input.parse::<Option<Token![=]>>()?.is_some() {
+			Some(input.parse()?)
+		} else {
+			None
+		};
+
+		Ok(Self { component, value })
+	}
+}
 
 pub fn derive(tokens: TokenStream) -> syn::Result<TokenStream> {
 	let item_struct: ItemStruct = syn::parse2(tokens)?;
 	let ident = &item_struct.ident;
-	let name = ident.to_string();
-	let base_type = if let Some(extends) = item_struct
+	let name = name(&item_struct)?.unwrap_or_else(|| LitStr::new(&ident.to_string(), ident.span()));
+	let base_type = base_type(&item_struct)?.unwrap_or_else(|| parse_quote! { () });
+	let mut requires = Vec::new();
+
+	for attr in item_struct
 		.attrs
 		.iter()
-		.find(|v| v.path().is_ident("extends"))
+		.filter(|v| v.path().is_ident("require"))
 	{
-		extends.parse_args::<Type>()?
-	} else {
-		parse_quote! { () }
-	};
+		requires.extend(attr.parse_args_with(Punctuated::<Require, Token![,]>::parse_terminated)?);
+	}
 
-	let type_id_ident = format_ident!("{}_TYPE_ID", ident.to_string().to_uppercase());
-	let type_ids_ident = format_ident!("{}_TYPE_IDS", ident.to_string().to_uppercase());
+	let init = requires.iter().map(|Require { component, value }| {
+		let value = value
+			.as_ref()
+			.map(|value| quote! { #value })
+			.unwrap_or_else(|| quote! { ::std::default::Default::default() });
 
-	Ok(quote! {
-		static #type_id_ident: ::std::sync::LazyLock<::std::any::TypeId> = ::std::sync::LazyLock::new(|| ::std::any::TypeId::of::<#ident>());
-		static #type_ids_ident: ::std::sync::LazyLock<Vec<::std::any::TypeId>> = ::std::sync::LazyLock::new(|| {
-			let mut type_ids = vec![*#type_id_ident];
-
-			type_ids.extend(<#base_type as ::torque_ecs::Entity>::type_ids());
-
-			type_ids
-		});
+		quote! {
+			system.try_entity_set::<#component>(entity_id, #value)?;
+		}
+	});
 
+	Ok(quote! {
 		impl ::torque_ecs::Entity for #ident {
 			const NAME: &str = #name;
+			const DEPTH: usize = <#base_type as ::torque_ecs::Entity>::DEPTH + 1;
 			type Base = #base_type;
 
 			fn type_id() -> ::std::any::TypeId {
-				*#type_id_ident
+				::std::any::TypeId::of::<#ident>()
 			}
 
 			fn type_ids() -> &'static [::std::any::TypeId] {
-				&*#type_ids_ident
+				static TYPE_IDS: ::std::sync::LazyLock<Vec<::std::any::TypeId>> = ::std::sync::LazyLock::new(|| {
+					let mut type_ids = vec![::std::any::TypeId::of::<#ident>()];
+
+					type_ids.extend(<#base_type as ::torque_ecs::Entity>::type_ids());
+
+					type_ids
+				});
+
+				&*TYPE_IDS
+			}
+
+			fn init(system: &::torque_ecs::System, entity_id: ::torque_ecs::EntityId) -> Result<(), ::torque_ecs::SystemError> {
+				<#base_type as ::torque_ecs::Entity>::init(system, entity_id)?;
+				#(#init)*
+
+				Ok(())
 			}
 		}
 
 		impl ::torque_ecs::Extends<#base_type> for #ident {}
+
+		// fails to compile if the extends chain is cyclic
+		const _: usize = <#ident as ::torque_ecs::Entity>::DEPTH;
+
+		::torque_ecs::inventory::submit! {
+			::torque_ecs::registry::EntityType::of::<#ident>()
+		}
 	})
 }
+
+/// The name given by `#[entity(name = "...")]`, if any, for types sharing their identifier with
+/// another entity type, e.g. in another module.
+fn name(item_struct: &ItemStruct) -> syn::Result<Option<LitStr>> {
+	let mut name = None;
+
+	for attr in item_struct
+		.attrs
+		.iter()
+		.filter(|v| v.path().is_ident("entity"))
+	{
+		attr.parse_nested_meta(|meta| {
+			if !meta.path.is_ident("name") {
+				return Err(meta.error("expected `name`"));
+			}
+
+			if name.is_some() {
+				return Err(Error::new_spanned(&meta.path, "duplicate entity option"));
+			}
+
+			name = Some(meta.value()?.parse()?);
+
+			Ok(())
+		})?;
+	}
+
+	Ok(name)
+}
+
+/// The type of the single `#[extends(...)]` attribute, if any.
+fn base_type(item_struct: &ItemStruct) -> syn::Result<Option<Type>> {
+	let mut base_type = None::<Type>;
+
+	for attr in item_struct
+		.attrs
+		.iter()
+		.filter(|v| v.path().is_ident("extends"))
+	{
+		for ty in attr.parse_args_with(Punctuated::<Type, Token![,]>::parse_terminated)? {
+			if let Some(base_type) = &base_type {
+				let message = if quote!(#base_type).to_string() == quote!(#ty).to_string() {
+					"duplicate `extends`"
+				} else {
+					"an entity can only extend a single type"
+				};
+
+				return Err(Error::new_spanned(ty, message));
+			}
+
+			if matches!(&ty, Type::Path(path) if path.qself.is_none()
+				&& (path.path.is_ident("Self") || path.path.is_ident(&item_struct.ident)))
+			{
+				return Err(Error::new_spanned(ty, "an entity can not extend itself"));
+			}
+
+			base_type = Some(ty);
+		}
+	}
+
+	Ok(base_type)
+}
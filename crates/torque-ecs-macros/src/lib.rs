@@ -3,7 +3,7 @@ mod entity;
 
 use proc_macro::TokenStream;
 
-#[proc_macro_derive(Entity, attributes(extends))]
+#[proc_macro_derive(Entity, attributes(entity, extends, require))]
 pub fn derive_entity(tokens: TokenStream) -> TokenStream {
 	match entity::derive(tokens.into()) {
 		Ok(tokens) => tokens,
@@ -12,7 +12,7 @@ pub fn derive_entity(tokens: TokenStream) -> TokenStream {
 	.into()
 }
 
-#[proc_macro_derive(Component)]
+#[proc_macro_derive(Component, attributes(component))]
 pub fn derive_component(tokens: TokenStream) -> TokenStream {
 	match component::derive(tokens.into()) {
 		Ok(tokens) => tokens,
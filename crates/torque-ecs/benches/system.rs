@@ -0,0 +1,133 @@
+use criterion::{
+	black_box, criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion, Throughput,
+};
+use torque_ecs::{Component, Entity, EntityMethods, EntityRef, System};
+
+#[derive(Entity)]
+struct Base;
+
+#[derive(Entity)]
+#[extends(Base)]
+struct Derived;
+
+#[derive(Component)]
+#[component(value = usize)]
+struct Counter;
+
+const SIZES: [usize; 3] = [10_000, 100_000, 1_000_000];
+
+fn create(count: usize) -> (System, Vec<EntityRef<Derived>>) {
+	let system = System::default();
+	let entities = (0..count).map(|_| system.create::<Derived>()).collect();
+
+	(system, entities)
+}
+
+fn create_set(count: usize) -> (System, Vec<EntityRef<Derived>>) {
+	let (system, entities) = create(count);
+
+	for (i, entity) in entities.iter().enumerate() {
+		entity.set::<Counter>(i);
+	}
+
+	(system, entities)
+}
+
+fn benches(c: &mut Criterion) {
+	let mut group = c.benchmark_group("system");
+
+	group.sample_size(10);
+
+	for size in SIZES {
+		group.throughput(Throughput::Elements(size as u64));
+
+		group.bench_with_input(BenchmarkId::new("create", size), &size, |b, &size| {
+			b.iter_batched(
+				System::default,
+				|system| {
+					let entities = (0..size)
+						.map(|_| system.create::<Derived>())
+						.collect::<Vec<_>>();
+
+					(system, entities)
+				},
+				BatchSize::PerIteration,
+			);
+		});
+
+		group.bench_with_input(BenchmarkId::new("set", size), &size, |b, &size| {
+			b.iter_batched(
+				|| create(size),
+				|(system, entities)| {
+					for (i, entity) in entities.iter().enumerate() {
+						entity.set::<Counter>(i);
+					}
+
+					(system, entities)
+				},
+				BatchSize::PerIteration,
+			);
+		});
+
+		group.bench_with_input(BenchmarkId::new("with", size), &size, |b, &size| {
+			let (_system, entities) = create_set(size);
+
+			b.iter(|| {
+				entities
+					.iter()
+					.map(|entity| entity.with::<Counter, _>(|v| *v))
+					.sum::<usize>()
+			});
+		});
+
+		group.bench_with_input(BenchmarkId::new("with_by_id", size), &size, |b, &size| {
+			let (system, entities) = create_set(size);
+			let component_id = system.component_id::<Counter>();
+
+			b.iter(|| {
+				entities
+					.iter()
+					.map(|entity| entity.with_by_id::<Counter, _>(component_id, |v| *v))
+					.sum::<usize>()
+			});
+		});
+
+		group.bench_with_input(BenchmarkId::new("get_ref", size), &size, |b, &size| {
+			let (_system, entities) = create_set(size);
+
+			b.iter(|| {
+				entities
+					.iter()
+					.map(|entity| *entity.get_ref::<Counter>())
+					.sum::<usize>()
+			});
+		});
+
+		group.bench_with_input(BenchmarkId::new("cast", size), &size, |b, &size| {
+			let (_system, entities) = create(size);
+
+			b.iter(|| {
+				for entity in &entities {
+					black_box(entity.upcast::<Base>().downcast::<Derived>());
+				}
+			});
+		});
+
+		group.bench_with_input(BenchmarkId::new("drop", size), &size, |b, &size| {
+			b.iter_batched(
+				|| create_set(size),
+				|(system, entities)| {
+					drop(entities);
+
+					system
+				},
+				BatchSize::PerIteration,
+			);
+		});
+	}
+
+	group.finish();
+}
+
+criterion_group!(system, benches);
+criterion_main!(system);
@@ -0,0 +1,255 @@
+use std::{
+	cell::RefCell,
+	mem,
+	sync::atomic::{AtomicU32, AtomicU64, Ordering},
+};
+
+use parking_lot_core::{DEFAULT_PARK_TOKEN, DEFAULT_UNPARK_TOKEN};
+
+thread_local! {
+	/// The borrows this thread holds, by address of the column.
+	static HELD: RefCell<Vec<(usize, BorrowKind)>> = const { RefCell::new(Vec::new()) };
+}
+
+// the state of a column packs the borrows of its rows and of the column as a whole
+const ROW_READ: u64 = 1;
+const ROW_WRITE: u64 = 1 << 20;
+const COLUMN_READ: u64 = 1 << 40;
+const COUNT_MASK: u64 = (1 << 20) - 1;
+const COLUMN_WRITE: u64 = 1 << 62;
+/// Set while a thread waits for a borrow of the column or one of its rows to be released.
+const PARKED: u64 = 1 << 63;
+
+const ROW_EXCLUSIVE: u32 = u32::MAX;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum BorrowKind {
+	/// Every row shared, taken by queries reading the component.
+	ColumnRead,
+	/// Every row exclusively, taken by queries writing the component.
+	ColumnWrite,
+	RowRead(usize),
+	RowWrite(usize),
+}
+
+impl BorrowKind {
+	fn conflicts(self, other: Self) -> bool {
+		use BorrowKind::*;
+
+		match (self, other) {
+			(ColumnWrite, _) | (_, ColumnWrite) => true,
+			(ColumnRead, RowWrite(_)) | (RowWrite(_), ColumnRead) => true,
+			(RowRead(a), RowWrite(b)) | (RowWrite(a), RowRead(b)) | (RowWrite(a), RowWrite(b)) => a == b,
+			_ => false,
+		}
+	}
+}
+
+/// The borrows of the values of a column. A thread may hold any number of borrows that do not
+/// conflict with each other, any conflicting borrow by the same thread fails, and conflicting
+/// borrows by other threads are waited for. Waiting threads park on the column, so releasing a
+/// borrow only wakes those waiting on the same column.
+///
+/// Rows are only added, moved or dropped with exclusive access to the storage, while nothing is
+/// borrowed.
+#[derive(Default)]
+pub(crate) struct Borrows {
+	state: AtomicU64,
+	rows: Vec<AtomicU32>,
+}
+
+impl Borrows {
+	#[inline]
+	pub fn push(&mut self) {
+		self.rows.push(AtomicU32::new(0));
+	}
+
+	#[inline]
+	pub fn swap_remove(&mut self, row: usize) {
+		debug_assert_eq!(*self.rows[row].get_mut(), 0);
+
+		self.rows.swap_remove(row);
+	}
+
+	/// Returns `None` if this thread already holds a conflicting borrow.
+	pub fn borrow(&self, kind: BorrowKind) -> Option<CellBorrow<'_>> {
+		loop {
+			if self.try_acquire(kind) {
+				HELD.with_borrow_mut(|held| held.push((self.address(), kind)));
+
+				return Some(CellBorrow {
+					borrows: self,
+					kind,
+				});
+			}
+
+			let address = self.address();
+			let reentrant = HELD.with_borrow(|held| {
+				held
+					.iter()
+					.any(|(v, held_kind)| *v == address && held_kind.conflicts(kind))
+			});
+
+			if reentrant {
+				return None;
+			}
+
+			// acquires the release of a row freed just before, so it is seen when validating
+			self.state.fetch_or(PARKED, Ordering::AcqRel);
+
+			// SAFETY: the key is the address of this column, which no other primitive parks on, and
+			// the callbacks neither panic nor park
+			unsafe {
+				parking_lot_core::park(
+					address,
+					|| self.state.load(Ordering::Relaxed) & PARKED != 0 && !self.can_acquire(kind),
+					|| {},
+					|_, _| {},
+					DEFAULT_PARK_TOKEN,
+					None,
+				);
+			}
+		}
+	}
+
+	#[inline]
+	fn address(&self) -> usize {
+		&self.state as *const AtomicU64 as usize
+	}
+
+	fn column_allows(state: u64, kind: BorrowKind) -> bool {
+		let count = |unit: u64| (state / unit) & COUNT_MASK;
+
+		match kind {
+			BorrowKind::ColumnRead => state & COLUMN_WRITE == 0 && count(ROW_WRITE) == 0,
+			BorrowKind::ColumnWrite => state & !PARKED == 0,
+			BorrowKind::RowRead(_) => state & COLUMN_WRITE == 0,
+			BorrowKind::RowWrite(_) => state & COLUMN_WRITE == 0 && count(COLUMN_READ) == 0,
+		}
+	}
+
+	fn can_acquire(&self, kind: BorrowKind) -> bool {
+		let row_allows = match kind {
+			BorrowKind::RowRead(row) => self.rows[row].load(Ordering::Relaxed) != ROW_EXCLUSIVE,
+			BorrowKind::RowWrite(row) => self.rows[row].load(Ordering::Relaxed) == 0,
+			_ => true,
+		};
+
+		row_allows && Self::column_allows(self.state.load(Ordering::Relaxed), kind)
+	}
+
+	fn unit(kind: BorrowKind) -> u64 {
+		match kind {
+			BorrowKind::ColumnRead => COLUMN_READ,
+			BorrowKind::ColumnWrite => COLUMN_WRITE,
+			BorrowKind::RowRead(_) => ROW_READ,
+			BorrowKind::RowWrite(_) => ROW_WRITE,
+		}
+	}
+
+	fn try_acquire(&self, kind: BorrowKind) -> bool {
+		let unit = Self::unit(kind);
+		let acquired = self
+			.state
+			.fetch_update(Ordering::Acquire, Ordering::Relaxed, |state| {
+				if !Self::column_allows(state, kind) {
+					return None;
+				}
+
+				assert!(
+					unit == COLUMN_WRITE || (state / unit) & COUNT_MASK < COUNT_MASK,
+					"too many borrows of a column"
+				);
+
+				Some(state + unit)
+			})
+			.is_ok();
+
+		if !acquired {
+			return false;
+		}
+
+		let row_acquired = match kind {
+			BorrowKind::RowRead(row) => self.rows[row]
+				.fetch_update(Ordering::Acquire, Ordering::Relaxed, |v| {
+					(v < ROW_EXCLUSIVE - 1).then(|| v + 1)
+				})
+				.is_ok(),
+			BorrowKind::RowWrite(row) => self.rows[row]
+				.compare_exchange(0, ROW_EXCLUSIVE, Ordering::Acquire, Ordering::Relaxed)
+				.is_ok(),
+			_ => true,
+		};
+
+		if !row_acquired {
+			self.release_column(unit);
+		}
+
+		row_acquired
+	}
+
+	fn release(&self, kind: BorrowKind) {
+		match kind {
+			BorrowKind::RowRead(row) => {
+				self.rows[row].fetch_sub(1, Ordering::Release);
+			}
+			BorrowKind::RowWrite(row) => self.rows[row].store(0, Ordering::Release),
+			_ => {}
+		}
+
+		self.release_column(Self::unit(kind));
+	}
+
+	fn release_column(&self, unit: u64) {
+		if self.state.fetch_sub(unit, Ordering::Release) & PARKED != 0 {
+			self.state.fetch_and(!PARKED, Ordering::Relaxed);
+
+			// SAFETY: the key is the address of this column, see borrow
+			unsafe { parking_lot_core::unpark_all(self.address(), DEFAULT_UNPARK_TOKEN) };
+		}
+	}
+}
+
+pub(crate) struct CellBorrow<'b> {
+	borrows: &'b Borrows,
+	kind: BorrowKind,
+}
+
+impl CellBorrow<'_> {
+	/// Detaches the borrow from the reference to its column, so it can be held along with the guard
+	/// of the storage.
+	///
+	/// # Safety
+	///
+	/// The column must not be moved or dropped for `'s`, e.g. by holding the storage locked and
+	/// dropping the borrow before the lock.
+	pub unsafe fn detach<'s>(self) -> CellBorrow<'s> {
+		let borrows = self.borrows as *const Borrows;
+		let kind = self.kind;
+
+		mem::forget(self);
+
+		CellBorrow {
+			// SAFETY: the caller keeps the column in place for 's
+			borrows: unsafe { &*borrows },
+			kind,
+		}
+	}
+}
+
+impl Drop for CellBorrow<'_> {
+	fn drop(&mut self) {
+		let entry = (self.borrows.address(), self.kind);
+
+		HELD.with_borrow_mut(|held| {
+			let index = held
+				.iter()
+				.rposition(|v| *v == entry)
+				.expect("borrow should be registered");
+
+			held.swap_remove(index);
+		});
+
+		self.borrows.release(self.kind);
+	}
+}
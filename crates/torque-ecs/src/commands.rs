@@ -0,0 +1,123 @@
+#[cfg(test)]
+mod tests;
+
+use std::mem;
+
+use crate::{Component, Entity, EntityId, EntityMethods, EntityRef, System, SystemError};
+
+pub(crate) type Command = Box<dyn FnOnce(&System) -> Result<(), SystemError> + Send>;
+
+/// Structural changes recorded now and applied later, for when they can not be made on the spot:
+/// from within a closure accessing the system, or while iterating a query, any of them would
+/// return [`SystemError::ReentrantLock`].
+///
+/// Commands are applied in the order they were recorded, either by [`apply`](Self::apply) or, if
+/// the buffer is dropped without being applied, by the next
+/// [`System::apply_deferred`](crate::System). A [`Schedule`](crate::Schedule) applies deferred
+/// commands after every stage.
+pub struct Commands {
+	system: System,
+	queue: Vec<Command>,
+}
+
+impl Commands {
+	pub(crate) fn new(system: System) -> Self {
+		Self {
+			system,
+			queue: Vec::new(),
+		}
+	}
+
+	/// Reserves a new entity, which is spawned along with its required components when the
+	/// command is applied. Until then the reference is valid but accessing components through it
+	/// returns [`SystemError::EntityNotFound`]. If every reference is dropped before then, the
+	/// entity is not spawned at all.
+	pub fn spawn<E>(&mut self) -> EntityRef<E>
+	where
+		E: Entity + 'static,
+	{
+		let entity = self.system.reserve::<E>();
+		let entity_id = entity.id();
+
+		self.push(move |system| {
+			if !system.spawn_reserved(entity_id, E::NAME, E::type_ids())? {
+				return Ok(());
+			}
+
+			// held so the entity can not be disposed before its components are set
+			match system.get_any(entity_id) {
+				Some(_entity) => E::init(system, entity_id),
+				None => Ok(()),
+			}
+		});
+
+		entity
+	}
+
+	/// Despawns the entity, see [`System::despawn`](crate::System). Despawning an entity that is
+	/// already gone does nothing.
+	pub fn despawn(&mut self, entity_id: EntityId) -> &mut Self {
+		self.push(move |system| {
+			system.despawn(entity_id);
+
+			Ok(())
+		})
+	}
+
+	pub fn set<C>(&mut self, entity_id: EntityId, value: C::Value) -> &mut Self
+	where
+		C: Component + 'static,
+	{
+		self.push(move |system| system.try_entity_set::<C>(entity_id, value))
+	}
+
+	/// Removes the component, dropping its value. Removing a component the entity does not carry
+	/// does nothing.
+	pub fn remove<C>(&mut self, entity_id: EntityId) -> &mut Self
+	where
+		C: Component + 'static,
+	{
+		self.push(move |system| system.try_entity_remove::<C>(entity_id).map(drop))
+	}
+
+	/// Records an arbitrary command.
+	pub fn push(
+		&mut self,
+		command: impl FnOnce(&System) -> Result<(), SystemError> + Send + 'static,
+	) -> &mut Self {
+		self.queue.push(Box::new(command));
+
+		self
+	}
+
+	#[inline]
+	pub fn len(&self) -> usize {
+		self.queue.len()
+	}
+
+	#[inline]
+	pub fn is_empty(&self) -> bool {
+		self.queue.is_empty()
+	}
+
+	pub fn apply(self) {
+		self.try_apply().unwrap()
+	}
+
+	/// Applies the commands now. Stops at the first command that fails, dropping the rest.
+	pub fn try_apply(mut self) -> Result<(), SystemError> {
+		let queue = mem::take(&mut self.queue);
+
+		queue
+			.into_iter()
+			.try_for_each(|command| command(&self.system))
+	}
+}
+
+impl Drop for Commands {
+	fn drop(&mut self) {
+		if !self.queue.is_empty() {
+			self.system.defer(self.queue.drain(..));
+		}
+	}
+}
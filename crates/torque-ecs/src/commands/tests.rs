@@ -0,0 +1,140 @@
+use test_log::test;
+
+use crate::{Component, Entity, EntityMethods, EntityRef, Schedule, System, SystemError, Task};
+
+#[derive(Clone, Entity)]
+struct CommandsEntity;
+
+struct Count;
+
+impl Component for Count {
+	const NAME: &str = "Count";
+
+	type Value = usize;
+}
+
+struct Label;
+
+impl Component for Label {
+	const NAME: &str = "Label";
+
+	type Value = &'static str;
+}
+
+#[test]
+fn spawn_set_remove_despawn() {
+	let system = System::default();
+	let mut commands = system.commands();
+
+	let entity = commands.spawn::<CommandsEntity>();
+
+	commands
+		.set::<Count>(entity.id(), 1)
+		.set::<Label>(entity.id(), "a")
+		.remove::<Label>(entity.id());
+
+	assert!(matches!(
+		entity.try_with::<Count, _>(|_| ()),
+		Err(SystemError::EntityNotFound(_))
+	));
+
+	commands.apply();
+
+	assert_eq!(entity.get::<Count>(), 1);
+	assert!(matches!(
+		entity.try_with::<Label, _>(|_| ()),
+		Err(SystemError::ComponentNotFound(..))
+	));
+
+	let mut commands = system.commands();
+
+	commands.despawn(entity.id());
+	commands.apply();
+
+	assert!(!system.contains(entity.id()));
+}
+
+#[test]
+fn structural_changes_from_within_closure() {
+	let system = System::default();
+	let parent = system.create::<CommandsEntity>();
+	let mut child = None;
+
+	parent.set::<Count>(1);
+	parent.with::<Count, _>(|count| {
+		let mut commands = system.commands();
+
+		assert!(matches!(
+			system.try_create::<CommandsEntity>(),
+			Err(SystemError::ReentrantLock)
+		));
+
+		let entity = commands.spawn::<CommandsEntity>();
+
+		commands.set::<Count>(entity.id(), *count + 1);
+		child = Some(entity);
+	});
+
+	let child = child.unwrap();
+
+	assert!(child.try_with::<Count, _>(|_| ()).is_err());
+
+	system.apply_deferred().unwrap();
+
+	assert_eq!(child.get::<Count>(), 2);
+}
+
+#[test]
+fn unreferenced_spawn_is_skipped() {
+	let system = System::default();
+	let mut commands = system.commands();
+
+	let entity_id = commands.spawn::<CommandsEntity>().id();
+
+	commands.set::<Count>(entity_id, 0);
+
+	assert!(matches!(
+		commands.try_apply(),
+		Err(SystemError::EntityNotFound(_))
+	));
+	assert!(!system.contains(entity_id));
+}
+
+#[test]
+fn schedule_applies_after_stage() {
+	let system = System::default();
+	let mut schedule = Schedule::new();
+
+	system.insert_resource(Vec::<EntityRef<CommandsEntity>>::new());
+
+	schedule
+		.add_stage("Spawn")
+		.add_stage("Count")
+		.add_task(
+			"Spawn",
+			Task::new("spawn", |system| {
+				let mut commands = system.commands();
+				let entity = commands.spawn::<CommandsEntity>();
+
+				commands.set::<Count>(entity.id(), 0);
+				system
+					.resource_mut::<Vec<EntityRef<CommandsEntity>>>()
+					.push(entity);
+			}),
+		)
+		.add_task(
+			"Count",
+			Task::query::<&mut Count, ()>("count", |mut query| {
+				for (_, count) in &mut query {
+					*count += 1;
+				}
+			}),
+		);
+
+	schedule.run(&system);
+
+	let mut query = system.query::<&Count>();
+	let counts = query.iter().map(|(_, count)| *count).collect::<Vec<_>>();
+
+	assert_eq!(counts, [1]);
+}
@@ -1,5 +1,5 @@
 pub trait Component {
 	const NAME: &str;
 
-	type Value;
+	type Value: Send + Sync;
 }
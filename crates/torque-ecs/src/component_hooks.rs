@@ -0,0 +1,20 @@
+use std::sync::Arc;
+
+use crate::{EntityId, System};
+
+/// Called with the system and the entity whose component is being added, replaced or removed.
+/// No locks are held while a hook runs, so it may freely access the system.
+pub type ComponentHook = Arc<dyn Fn(&System, EntityId) + Send + Sync>;
+
+#[derive(Default)]
+pub(crate) struct ComponentHooks {
+	pub on_add: Vec<ComponentHook>,
+	pub on_replace: Vec<ComponentHook>,
+	pub on_remove: Vec<ComponentHook>,
+}
+
+pub(crate) fn call_hooks(hooks: Vec<ComponentHook>, system: &System, entity_id: EntityId) {
+	for hook in hooks {
+		hook(system, entity_id);
+	}
+}
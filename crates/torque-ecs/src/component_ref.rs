@@ -0,0 +1,103 @@
+use std::{
+	fmt::{self, Debug},
+	ops::{Deref, DerefMut},
+};
+
+use crate::{cell_borrow::CellBorrow, storage_guard::StorageRead};
+
+/// Shared access to the value of a component, see [`System::entity_get_ref`](crate::System). Like
+/// the closure of [`System::entity_with`](crate::System), holding it keeps the system locked
+/// shared, so entities can not be created, disposed or gain components on this thread until it is
+/// dropped.
+pub struct Ref<'s, T> {
+	// dropped in order, releasing the borrow before the storage
+	_borrow: CellBorrow<'s>,
+	_storage: StorageRead<'s>,
+	value: *const T,
+}
+
+impl<'s, T> Ref<'s, T> {
+	/// # Safety
+	///
+	/// `value` must point to the value `borrow` borrows shared, in the storage held by `storage`.
+	pub(crate) unsafe fn new(
+		storage: StorageRead<'s>,
+		borrow: CellBorrow<'s>,
+		value: *const T,
+	) -> Self {
+		Self {
+			_borrow: borrow,
+			_storage: storage,
+			value,
+		}
+	}
+}
+
+impl<T> Deref for Ref<'_, T> {
+	type Target = T;
+
+	fn deref(&self) -> &Self::Target {
+		// SAFETY: the value is borrowed shared for as long as self lives
+		unsafe { &*self.value }
+	}
+}
+
+impl<T> Debug for Ref<'_, T>
+where
+	T: Debug,
+{
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		(**self).fmt(f)
+	}
+}
+
+/// Exclusive access to the value of a component, see
+/// [`System::entity_get_mut`](crate::System). The value is marked changed when this is created.
+pub struct RefMut<'s, T> {
+	_borrow: CellBorrow<'s>,
+	_storage: StorageRead<'s>,
+	value: *mut T,
+}
+
+impl<'s, T> RefMut<'s, T> {
+	/// # Safety
+	///
+	/// `value` must point to the value `borrow` borrows exclusively, in the storage held by
+	/// `storage`.
+	pub(crate) unsafe fn new(
+		storage: StorageRead<'s>,
+		borrow: CellBorrow<'s>,
+		value: *mut T,
+	) -> Self {
+		Self {
+			_borrow: borrow,
+			_storage: storage,
+			value,
+		}
+	}
+}
+
+impl<T> Deref for RefMut<'_, T> {
+	type Target = T;
+
+	fn deref(&self) -> &Self::Target {
+		// SAFETY: the value is borrowed exclusively for as long as self lives
+		unsafe { &*self.value }
+	}
+}
+
+impl<T> DerefMut for RefMut<'_, T> {
+	fn deref_mut(&mut self) -> &mut Self::Target {
+		// SAFETY: the value is borrowed exclusively for as long as self lives
+		unsafe { &mut *self.value }
+	}
+}
+
+impl<T> Debug for RefMut<'_, T>
+where
+	T: Debug,
+{
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		(**self).fmt(f)
+	}
+}
@@ -1,17 +1,32 @@
 use std::any::TypeId;
 
-use crate::{Component, EntityId, EntityRef, Extends, System, SystemError, WeakEntityRef};
+use crate::{
+	Component, ComponentId, EntityId, EntityRef, Event, Extends, ObserverId, Ref, RefMut, System,
+	SystemError, Trigger, WeakEntityRef,
+};
 
 pub trait Entity {
+	/// The name types are registered and looked up as, the identifier of the type unless given by
+	/// `#[entity(name = "...")]`. Types sharing a name can not be looked up by it.
 	const NAME: &'static str;
-	type Base;
+	/// The number of types in the [`Extends`] chain, including this one. Evaluating it fails to
+	/// compile for a type that ends up extending itself.
+	const DEPTH: usize;
+	type Base: Entity;
 
 	fn type_id() -> TypeId;
 	fn type_ids() -> &'static [TypeId];
+
+	/// Sets the components required by this type and the types it extends, base types first, on
+	/// a newly created entity.
+	fn init(_system: &System, _entity_id: EntityId) -> Result<(), SystemError> {
+		Ok(())
+	}
 }
 
 impl Entity for () {
 	const NAME: &'static str = "()";
+	const DEPTH: usize = 0;
 
 	type Base = Self;
 
@@ -60,6 +75,85 @@ where
 		self
 	}
 
+	#[inline]
+	fn has<C>(&self) -> bool
+	where
+		C: Component + 'static,
+	{
+		self.system().entity_has::<C>(self.id())
+	}
+
+	#[inline]
+	fn try_has<C>(&self) -> Result<bool, SystemError>
+	where
+		C: Component + 'static,
+	{
+		self.system().try_entity_has::<C>(self.id())
+	}
+
+	/// Removes the component, returning its value, or `None` if the entity did not carry it.
+	#[inline]
+	fn remove<C>(&self) -> Option<C::Value>
+	where
+		C: Component + 'static,
+	{
+		self.system().entity_remove::<C>(self.id())
+	}
+
+	#[inline]
+	fn try_remove<C>(&self) -> Result<Option<C::Value>, SystemError>
+	where
+		C: Component + 'static,
+	{
+		self.system().try_entity_remove::<C>(self.id())
+	}
+
+	/// Observes events targeted at the entity or bubbling up from its descendants, see
+	/// [`System::observe_entity`](crate::System).
+	#[inline]
+	fn observe<Ev>(
+		&self,
+		run: impl Fn(&System, &mut Trigger<'_, Ev>) + Send + Sync + 'static,
+	) -> ObserverId
+	where
+		Ev: Event,
+	{
+		self.system().observe_entity(self.id(), run)
+	}
+
+	/// Sends the event to the entity, see [`System::trigger_at`](crate::System).
+	#[inline]
+	fn trigger<Ev>(&self, event: Ev)
+	where
+		Ev: Event,
+	{
+		self.system().trigger_at(self.id(), event)
+	}
+
+	/// Takes the value of the component, leaving the default in its place.
+	#[inline]
+	fn take<C>(&self) -> C::Value
+	where
+		C: Component + 'static,
+		C::Value: Default,
+	{
+		self.with_mut::<C, _>(std::mem::take)
+	}
+
+	#[inline]
+	fn try_take<C>(&self) -> Result<C::Value, SystemError>
+	where
+		C: Component + 'static,
+		C::Value: Default,
+	{
+		self.try_with_mut::<C, _>(std::mem::take)
+	}
+
+	#[inline]
+	fn component_names(&self) -> impl Iterator<Item = &'static str> {
+		self.system().entity_component_names(self.id())
+	}
+
 	#[inline]
 	fn with<C, R>(&self, f: impl FnOnce(&C::Value) -> R) -> R
 	where
@@ -132,6 +226,59 @@ where
 		self.system().try_entity_with_mut::<C, _>(self.id(), f)
 	}
 
+	#[inline]
+	fn get_ref<C>(&self) -> Ref<'_, C::Value>
+	where
+		C: Component + 'static,
+	{
+		self.system().entity_get_ref::<C>(self.id())
+	}
+
+	#[inline]
+	fn try_get_ref<C>(&self) -> Result<Ref<'_, C::Value>, SystemError>
+	where
+		C: Component + 'static,
+	{
+		self.system().try_entity_get_ref::<C>(self.id())
+	}
+
+	#[inline]
+	fn get_mut<C>(&self) -> RefMut<'_, C::Value>
+	where
+		C: Component + 'static,
+	{
+		self.system().entity_get_mut::<C>(self.id())
+	}
+
+	#[inline]
+	fn try_get_mut<C>(&self) -> Result<RefMut<'_, C::Value>, SystemError>
+	where
+		C: Component + 'static,
+	{
+		self.system().try_entity_get_mut::<C>(self.id())
+	}
+
+	/// Like [`with`](Self::with), see [`System::component_id`](crate::System).
+	#[inline]
+	fn with_by_id<C, R>(&self, component_id: ComponentId, f: impl FnOnce(&C::Value) -> R) -> R
+	where
+		C: Component + 'static,
+	{
+		self
+			.system()
+			.entity_with_by_id::<C, _>(self.id(), component_id, f)
+	}
+
+	#[inline]
+	fn with_mut_by_id<C, R>(&self, component_id: ComponentId, f: impl FnOnce(&mut C::Value) -> R) -> R
+	where
+		C: Component + 'static,
+	{
+		self
+			.system()
+			.entity_with_mut_by_id::<C, _>(self.id(), component_id, f)
+	}
+
 	#[inline]
 	fn downgrade(self) -> WeakEntityRef<E> {
 		WeakEntityRef::new(self.system().clone(), self.id())
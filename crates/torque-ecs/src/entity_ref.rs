@@ -1,3 +1,6 @@
+#[cfg(test)]
+mod tests;
+
 use std::marker::PhantomData;
 
 use crate::{Entity, EntityId, EntityMethods, System};
@@ -16,9 +19,8 @@ impl<E> EntityRef<E>
 where
 	E: Entity + 'static,
 {
-	pub(crate) fn new(system: System, id: EntityId) -> Self {
-		system.increment_ref(id);
-
+	/// Wraps a reference that was already counted for the entity.
+	pub(crate) fn from_counted(system: System, id: EntityId) -> Self {
 		Self {
 			system,
 			id,
@@ -69,9 +71,10 @@ impl<E> v8::cppgc::GarbageCollected for EntityRef<E>
 where
 	E: Entity + 'static,
 {
+	// the entity is kept alive by the reference count, there are no traced members
 	fn trace(&self, _visitor: &v8::cppgc::Visitor) {}
 
 	fn get_name(&self) -> Option<&'static std::ffi::CStr> {
-		None
+		Some(c"EntityRef")
 	}
 }
@@ -0,0 +1,161 @@
+use std::{
+	collections::VecDeque,
+	sync::{
+		atomic::{AtomicUsize, Ordering},
+		Arc,
+	},
+	thread,
+};
+
+use test_log::test;
+
+use crate::{Component, Entity, EntityMethods, EntityRef, System, SystemError};
+
+#[derive(Entity)]
+struct RefEntity;
+
+struct Counter;
+
+impl Component for Counter {
+	const NAME: &str = "Counter";
+
+	type Value = usize;
+}
+
+struct Holds;
+
+impl Component for Holds {
+	const NAME: &str = "Holds";
+
+	type Value = VecDeque<EntityRef<RefEntity>>;
+}
+
+#[test]
+fn clone_and_drop() {
+	let system = System::default();
+
+	let entity = system.create::<RefEntity>();
+	let entity_id = entity.id;
+	let clone = entity.clone();
+
+	drop(entity);
+
+	assert!(system.contains(entity_id));
+
+	drop(clone);
+
+	assert!(!system.contains(entity_id));
+}
+
+#[test]
+fn upgrade_after_last_drop() {
+	let system = System::default();
+
+	let entity = system.create::<RefEntity>();
+	let weak = entity.clone().downgrade();
+
+	assert!(weak.upgrade().is_some());
+
+	drop(entity);
+
+	assert!(weak.upgrade().is_none());
+}
+
+#[test]
+fn despawn_invalidates_refs() {
+	let system = System::default();
+
+	let entity = system.create::<RefEntity>();
+	let weak = entity.clone().downgrade();
+
+	entity.set::<Counter>(0);
+
+	assert!(system.despawn(entity.id));
+	assert!(!system.despawn(entity.id));
+	assert!(weak.upgrade().is_none());
+	assert!(matches!(
+		entity.try_with::<Counter, _>(|v| *v),
+		Err(SystemError::EntityNotFound(_))
+	));
+
+	let clone = entity.clone();
+
+	drop(entity);
+	drop(clone);
+}
+
+#[test]
+fn dropping_holder_disposes_held() {
+	let system = System::default();
+
+	let holder = system.create::<RefEntity>();
+	let held = system.create::<RefEntity>();
+	let held_id = held.id;
+
+	holder.set::<Holds>(VecDeque::from([held]));
+
+	assert!(system.contains(held_id));
+
+	// replacing the value drops the held entity while the storage is locked
+	holder.set::<Holds>(VecDeque::new());
+
+	assert!(!system.contains(held_id));
+
+	let held = system.create::<RefEntity>();
+	let held_id = held.id;
+
+	holder.set::<Holds>(VecDeque::from([held]));
+	drop(holder);
+
+	assert!(!system.contains(held_id));
+}
+
+#[test]
+fn concurrent_drops() {
+	let system = System::default();
+	let removed = Arc::new(AtomicUsize::new(0));
+
+	{
+		let removed = removed.clone();
+
+		system.on_remove::<Counter>(move |_, _| {
+			removed.fetch_add(1, Ordering::Relaxed);
+		});
+	}
+
+	for _ in 0..16 {
+		let entity = system.create::<RefEntity>();
+		let entity_id = entity.id;
+
+		entity.set::<Counter>(0);
+
+		let weak = entity.clone().downgrade();
+		let threads = (0..8)
+			.map(|_| {
+				let clones = (0..100).map(|_| entity.clone()).collect::<Vec<_>>();
+				let weak = weak.clone();
+
+				thread::spawn(move || {
+					for clone in clones {
+						if let Some(upgraded) = weak.upgrade() {
+							upgraded.with_mut::<Counter, _>(|v| *v += 1);
+						}
+
+						drop(clone);
+					}
+				})
+			})
+			.collect::<Vec<_>>();
+
+		drop(entity);
+
+		for thread in threads {
+			thread.join().unwrap();
+		}
+
+		assert!(!system.contains(entity_id));
+		assert!(weak.upgrade().is_none());
+	}
+
+	assert_eq!(removed.load(Ordering::Relaxed), 16);
+}
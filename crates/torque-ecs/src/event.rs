@@ -0,0 +1,353 @@
+#[cfg(test)]
+mod tests;
+
+use std::{
+	any::{Any, TypeId},
+	sync::Arc,
+};
+
+use fnv::FnvHashMap;
+use slotmap::{new_key_type, SlotMap};
+
+use crate::{system::Inner, EntityId, System, SystemError};
+
+/// Something that happened, passed to observers by [`System::trigger`](crate::System) or, targeted
+/// at an entity, by [`System::trigger_at`](crate::System).
+pub trait Event: Send + Sync + 'static {
+	/// Whether the event, when targeted at an entity, propagates through the ancestors of the
+	/// entity, see [`Phase`]. If not, only the observers of the target and the global observers
+	/// see it.
+	const PROPAGATES: bool = true;
+}
+
+/// How an observer is reached by an event targeted at an entity. Like DOM events, the event
+/// travels from the root down to the target and back up again.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Phase {
+	/// At an ancestor of the target, root first, for observers added with
+	/// [`observe_capture`](crate::System).
+	Capture,
+	/// At the target, for capture observers and then the others.
+	Target,
+	/// At an ancestor of the target, parent first, for observers added with
+	/// [`observe_entity`](crate::System).
+	Bubble,
+	/// At the observers of the event type, after the observers of entities.
+	Global,
+}
+
+new_key_type! {
+	/// Identifies an observer to [`System::unobserve`](crate::System).
+	pub struct ObserverId;
+}
+
+/// An event on its way to observers, along with where it is.
+pub struct Trigger<'e, E> {
+	event: &'e E,
+	target: Option<EntityId>,
+	current_target: Option<EntityId>,
+	phase: Phase,
+	propagating: bool,
+}
+
+impl<E> Trigger<'_, E> {
+	#[inline]
+	pub fn event(&self) -> &E {
+		self.event
+	}
+
+	/// The entity the event was sent to, `None` for a global event.
+	#[inline]
+	pub fn target(&self) -> Option<EntityId> {
+		self.target
+	}
+
+	/// The entity whose observer is running, `None` for a global observer.
+	#[inline]
+	pub fn current_target(&self) -> Option<EntityId> {
+		self.current_target
+	}
+
+	#[inline]
+	pub fn phase(&self) -> Phase {
+		self.phase
+	}
+
+	/// Keeps the event from reaching further entities. The remaining observers of the current
+	/// entity and the global observers still run.
+	#[inline]
+	pub fn stop_propagation(&mut self) {
+		self.propagating = false;
+	}
+
+	#[inline]
+	pub fn is_propagating(&self) -> bool {
+		self.propagating
+	}
+}
+
+type ObserverFn<E> = Box<dyn Fn(&System, &mut Trigger<'_, E>) + Send + Sync>;
+
+struct Observer {
+	target: Option<EntityId>,
+	type_id: TypeId,
+	capture: bool,
+	/// An [`ObserverFn`] of the event type.
+	run: Arc<dyn Any + Send + Sync>,
+}
+
+/// The observers of a system, by event type and by entity.
+#[derive(Default)]
+pub(crate) struct Observers {
+	observers: SlotMap<ObserverId, Observer>,
+	global: FnvHashMap<TypeId, Vec<ObserverId>>,
+	entities: FnvHashMap<EntityId, Vec<ObserverId>>,
+}
+
+impl Observers {
+	fn insert<E>(
+		&mut self,
+		target: Option<EntityId>,
+		capture: bool,
+		run: impl Fn(&System, &mut Trigger<'_, E>) + Send + Sync + 'static,
+	) -> ObserverId
+	where
+		E: Event,
+	{
+		let observer_id = self.observers.insert(Observer {
+			target,
+			type_id: TypeId::of::<E>(),
+			capture,
+			run: Arc::new(Box::new(run) as ObserverFn<E>),
+		});
+
+		match target {
+			Some(entity_id) => self.entities.entry(entity_id).or_default(),
+			None => self.global.entry(TypeId::of::<E>()).or_default(),
+		}
+		.push(observer_id);
+
+		observer_id
+	}
+
+	fn remove(&mut self, observer_id: ObserverId) -> bool {
+		let Some(observer) = self.observers.remove(observer_id) else {
+			return false;
+		};
+
+		let ids = match observer.target {
+			Some(entity_id) => self.entities.get_mut(&entity_id),
+			None => self.global.get_mut(&observer.type_id),
+		};
+
+		if let Some(ids) = ids {
+			ids.retain(|&v| v != observer_id);
+		}
+
+		true
+	}
+
+	/// Removes the observers of a disposed entity.
+	pub(crate) fn remove_entity(&mut self, entity_id: EntityId) {
+		for observer_id in self.entities.remove(&entity_id).unwrap_or_default() {
+			self.observers.remove(observer_id);
+		}
+	}
+
+	/// The observers of `E` on the entity, or the global ones if `None`, that `filter` accepts
+	/// given whether they capture.
+	fn of<E>(
+		&self,
+		target: Option<EntityId>,
+		filter: impl Fn(bool) -> bool,
+	) -> Vec<Arc<dyn Any + Send + Sync>>
+	where
+		E: Event,
+	{
+		let ids = match target {
+			Some(entity_id) => self.entities.get(&entity_id),
+			None => self.global.get(&TypeId::of::<E>()),
+		};
+
+		ids
+			.into_iter()
+			.flatten()
+			.map(|&observer_id| &self.observers[observer_id])
+			.filter(|v| v.type_id == TypeId::of::<E>() && filter(v.capture))
+			.map(|v| v.run.clone())
+			.collect()
+	}
+}
+
+impl Inner {
+	/// Runs `run` for every event of type `E`, whether global or targeted at an entity.
+	pub fn observe<E>(
+		self: &Arc<Self>,
+		run: impl Fn(&System, &mut Trigger<'_, E>) + Send + Sync + 'static,
+	) -> ObserverId
+	where
+		E: Event,
+	{
+		self.observers.write().insert(None, false, run)
+	}
+
+	pub fn observe_entity<E>(
+		self: &Arc<Self>,
+		entity_id: EntityId,
+		run: impl Fn(&System, &mut Trigger<'_, E>) + Send + Sync + 'static,
+	) -> ObserverId
+	where
+		E: Event,
+	{
+		self.try_observe_entity(entity_id, run).unwrap()
+	}
+
+	/// Runs `run` for every event of type `E` targeted at the entity, or bubbling up from one of
+	/// its descendants. The observer is removed along with the entity.
+	pub fn try_observe_entity<E>(
+		self: &Arc<Self>,
+		entity_id: EntityId,
+		run: impl Fn(&System, &mut Trigger<'_, E>) + Send + Sync + 'static,
+	) -> Result<ObserverId, SystemError>
+	where
+		E: Event,
+	{
+		self.insert_entity_observer(entity_id, false, run)
+	}
+
+	pub fn observe_capture<E>(
+		self: &Arc<Self>,
+		entity_id: EntityId,
+		run: impl Fn(&System, &mut Trigger<'_, E>) + Send + Sync + 'static,
+	) -> ObserverId
+	where
+		E: Event,
+	{
+		self.try_observe_capture(entity_id, run).unwrap()
+	}
+
+	/// Like [`try_observe_entity`](Self::try_observe_entity), for events targeted at the entity
+	/// or on their way down to one of its descendants.
+	pub fn try_observe_capture<E>(
+		self: &Arc<Self>,
+		entity_id: EntityId,
+		run: impl Fn(&System, &mut Trigger<'_, E>) + Send + Sync + 'static,
+	) -> Result<ObserverId, SystemError>
+	where
+		E: Event,
+	{
+		self.insert_entity_observer(entity_id, true, run)
+	}
+
+	/// Removes an observer, returning `false` if it was already gone.
+	pub fn unobserve(self: &Arc<Self>, observer_id: ObserverId) -> bool {
+		self.observers.write().remove(observer_id)
+	}
+
+	/// Passes the event to the global observers of `E`.
+	pub fn trigger<E>(self: &Arc<Self>, event: E)
+	where
+		E: Event,
+	{
+		let mut trigger = Trigger {
+			event: &event,
+			target: None,
+			current_target: None,
+			phase: Phase::Global,
+			propagating: true,
+		};
+
+		self.run_observers(None, &mut trigger, |_| true);
+	}
+
+	/// Passes the event to the observers of the target and, unless propagation is stopped or
+	/// [`Event::PROPAGATES`] is `false`, of its ancestors as described by [`Phase`], then to the
+	/// global observers of `E`.
+	///
+	/// Observers run with no lock held and are looked up as the event reaches them, so they may
+	/// change the hierarchy, add or remove observers and trigger other events.
+	pub fn trigger_at<E>(self: &Arc<Self>, target: EntityId, event: E)
+	where
+		E: Event,
+	{
+		let path = if E::PROPAGATES {
+			self.ancestors(target).collect::<Vec<_>>()
+		} else {
+			Vec::new()
+		};
+		let mut trigger = Trigger {
+			event: &event,
+			target: Some(target),
+			current_target: None,
+			phase: Phase::Capture,
+			propagating: true,
+		};
+
+		let steps = path
+			.iter()
+			.rev()
+			.map(|&entity_id| (entity_id, Phase::Capture))
+			.chain([(target, Phase::Target)])
+			.chain(path.iter().map(|&entity_id| (entity_id, Phase::Bubble)));
+
+		for (entity_id, phase) in steps {
+			if !trigger.propagating {
+				break;
+			}
+
+			trigger.current_target = Some(entity_id);
+			trigger.phase = phase;
+
+			match phase {
+				Phase::Capture => self.run_observers(Some(entity_id), &mut trigger, |v| v),
+				Phase::Bubble => self.run_observers(Some(entity_id), &mut trigger, |v| !v),
+				_ => {
+					self.run_observers(Some(entity_id), &mut trigger, |v| v);
+					self.run_observers(Some(entity_id), &mut trigger, |v| !v);
+				}
+			}
+		}
+
+		trigger.current_target = None;
+		trigger.phase = Phase::Global;
+
+		self.run_observers(None, &mut trigger, |_| true);
+	}
+
+	fn insert_entity_observer<E>(
+		self: &Arc<Self>,
+		entity_id: EntityId,
+		capture: bool,
+		run: impl Fn(&System, &mut Trigger<'_, E>) + Send + Sync + 'static,
+	) -> Result<ObserverId, SystemError>
+	where
+		E: Event,
+	{
+		// held so the entity can not be disposed, leaving the observer behind
+		let _entity = self
+			.get_any(entity_id)
+			.ok_or(SystemError::EntityNotFound(entity_id))?;
+
+		Ok(self.observers.write().insert(Some(entity_id), capture, run))
+	}
+
+	fn run_observers<E>(
+		self: &Arc<Self>,
+		target: Option<EntityId>,
+		trigger: &mut Trigger<'_, E>,
+		filter: impl Fn(bool) -> bool,
+	) where
+		E: Event,
+	{
+		let observers = self.observers.read().of::<E>(target, filter);
+		let system = System(self.clone());
+
+		for observer in observers {
+			let run = observer
+				.downcast_ref::<ObserverFn<E>>()
+				.expect("observer should match its event type");
+
+			run(&system, trigger);
+		}
+	}
+}
@@ -0,0 +1,164 @@
+use std::sync::{Arc, Mutex};
+
+use test_log::test;
+
+use crate::{Entity, EntityMethods, EntityRef, System};
+
+use super::{Event, Phase};
+
+#[derive(Clone, Entity)]
+struct EventEntity;
+
+struct Click;
+
+impl Event for Click {}
+
+struct Focus;
+
+impl Event for Focus {
+	const PROPAGATES: bool = false;
+}
+
+type Log = Arc<Mutex<Vec<(&'static str, Phase)>>>;
+
+/// root
+/// └─ a
+///    └─ b
+fn tree(system: &System) -> [EntityRef<EventEntity>; 3] {
+	let [root, a, b] = [(); 3].map(|_| system.create::<EventEntity>());
+
+	system.push_child(root.id, a.id);
+	system.push_child(a.id, b.id);
+
+	[root, a, b]
+}
+
+fn observe_all<E>(system: &System, entities: &[EntityRef<EventEntity>; 3], log: &Log)
+where
+	E: Event,
+{
+	for (entity, name) in entities.iter().zip(["root", "a", "b"]) {
+		let capture = log.clone();
+		let bubble = log.clone();
+
+		system.observe_capture::<E>(entity.id, move |_, trigger| {
+			capture.lock().unwrap().push((name, trigger.phase()))
+		});
+		entity.observe::<E>(move |_, trigger| bubble.lock().unwrap().push((name, trigger.phase())));
+	}
+
+	let global = log.clone();
+
+	system.observe::<E>(move |_, trigger| global.lock().unwrap().push(("global", trigger.phase())));
+}
+
+#[test]
+fn propagation_order() {
+	let system = System::default();
+	let entities = tree(&system);
+	let log = Log::default();
+
+	observe_all::<Click>(&system, &entities, &log);
+	entities[2].trigger(Click);
+
+	assert_eq!(
+		*log.lock().unwrap(),
+		[
+			("root", Phase::Capture),
+			("a", Phase::Capture),
+			("b", Phase::Target),
+			("b", Phase::Target),
+			("a", Phase::Bubble),
+			("root", Phase::Bubble),
+			("global", Phase::Global),
+		]
+	);
+}
+
+#[test]
+fn stop_propagation() {
+	let system = System::default();
+	let entities = tree(&system);
+	let log = Log::default();
+
+	system.observe_capture::<Click>(entities[1].id, |_, trigger| trigger.stop_propagation());
+	observe_all::<Click>(&system, &entities, &log);
+	entities[2].trigger(Click);
+
+	assert_eq!(
+		*log.lock().unwrap(),
+		[
+			("root", Phase::Capture),
+			("a", Phase::Capture),
+			("global", Phase::Global),
+		]
+	);
+}
+
+#[test]
+fn targets_and_global_events() {
+	let system = System::default();
+	let entities = tree(&system);
+	let log = Log::default();
+
+	observe_all::<Focus>(&system, &entities, &log);
+	entities[1].trigger(Focus);
+
+	assert_eq!(
+		*log.lock().unwrap(),
+		[
+			("a", Phase::Target),
+			("a", Phase::Target),
+			("global", Phase::Global)
+		]
+	);
+
+	log.lock().unwrap().clear();
+	system.trigger(Focus);
+
+	assert_eq!(*log.lock().unwrap(), [("global", Phase::Global)]);
+}
+
+#[test]
+fn observers_may_change_the_system() {
+	let system = System::default();
+	let [root, a, b] = tree(&system);
+	let count = Arc::new(Mutex::new(0));
+
+	// bubbles up from b, then moves b away and triggers again from a
+	a.observe::<Click>(move |system, trigger| {
+		if trigger.target() == Some(b.id) {
+			system.reparent(b.id, None);
+			system.trigger_at(trigger.current_target().unwrap(), Click);
+		}
+	});
+
+	let counter = count.clone();
+
+	root.observe::<Click>(move |_, _| *counter.lock().unwrap() += 1);
+	system
+		.get::<EventEntity>(system.children_of(a.id)[0])
+		.unwrap()
+		.trigger(Click);
+
+	assert_eq!(*count.lock().unwrap(), 2);
+	assert!(system.children_of(a.id).is_empty());
+}
+
+#[test]
+fn observers_are_removed() {
+	let system = System::default();
+	let entity = system.create::<EventEntity>();
+	let observer_id = entity.observe::<Click>(|_, _| {});
+
+	system.observe::<Click>(|_, _| {});
+	entity.observe::<Focus>(|_, _| {});
+
+	assert!(system.unobserve(observer_id));
+	assert!(!system.unobserve(observer_id));
+	assert_eq!(system.observers.read().observers.len(), 2);
+
+	drop(entity);
+
+	assert_eq!(system.observers.read().observers.len(), 1);
+}
@@ -0,0 +1,182 @@
+#[cfg(test)]
+mod tests;
+
+use std::{marker::PhantomData, mem, sync::Arc};
+
+use crate::{system::Inner, System, SystemError};
+
+/// Updates the [`Events`] of one type, see [`System::update_events`](crate::System).
+pub(crate) type EventUpdate = fn(&System) -> Result<(), SystemError>;
+
+/// A double buffered queue of events, stored as a resource by
+/// [`System::add_events`](crate::System), for tasks reading them at their own pace with an
+/// [`EventReader`]. Events stay readable for two [`update`](Self::update)s, so every task of a
+/// [`Schedule`](crate::Schedule), which updates the events before its first stage, sees each event
+/// sent during or since its previous run, whatever the order of the tasks.
+pub struct Events<T> {
+	previous: Vec<T>,
+	current: Vec<T>,
+	/// The number of events sent before `previous`.
+	start: usize,
+}
+
+impl<T> Default for Events<T> {
+	fn default() -> Self {
+		Self {
+			previous: Vec::new(),
+			current: Vec::new(),
+			start: 0,
+		}
+	}
+}
+
+impl<T> Events<T> {
+	pub fn send(&mut self, event: T) {
+		self.current.push(event);
+	}
+
+	/// Drops the events sent before the previous update.
+	pub fn update(&mut self) {
+		self.start += self.previous.len();
+		self.previous = mem::take(&mut self.current);
+	}
+
+	/// The number of events that can still be read.
+	#[inline]
+	pub fn len(&self) -> usize {
+		self.previous.len() + self.current.len()
+	}
+
+	#[inline]
+	pub fn is_empty(&self) -> bool {
+		self.len() == 0
+	}
+
+	/// Drops every event, readers skip them.
+	pub fn clear(&mut self) {
+		self.update();
+		self.update();
+	}
+
+	/// A reader of the events sent from now on.
+	pub fn reader(&self) -> EventReader<T> {
+		EventReader {
+			next: self.end(),
+			_marker: PhantomData,
+		}
+	}
+
+	#[inline]
+	fn end(&self) -> usize {
+		self.start + self.len()
+	}
+}
+
+/// Where a reader of [`Events`] is at. A default reader starts with the oldest event still
+/// buffered.
+pub struct EventReader<T> {
+	next: usize,
+	_marker: PhantomData<fn() -> T>,
+}
+
+impl<T> Default for EventReader<T> {
+	fn default() -> Self {
+		Self {
+			next: 0,
+			_marker: PhantomData,
+		}
+	}
+}
+
+impl<T> EventReader<T> {
+	/// The events sent since the last read that are still buffered, oldest first.
+	pub fn read<'e>(&mut self, events: &'e Events<T>) -> impl Iterator<Item = &'e T> {
+		let skip = self.next.saturating_sub(events.start);
+
+		self.next = events.end();
+
+		events.previous.iter().chain(&events.current).skip(skip)
+	}
+
+	/// The number of events [`read`](Self::read) would return.
+	pub fn len(&self, events: &Events<T>) -> usize {
+		events.end() - self.next.max(events.start)
+	}
+
+	pub fn is_empty(&self, events: &Events<T>) -> bool {
+		self.len(events) == 0
+	}
+}
+
+impl Inner {
+	pub fn add_events<T>(self: &Arc<Self>) -> &Arc<Self>
+	where
+		T: Send + Sync + 'static,
+	{
+		self.try_add_events::<T>().unwrap()
+	}
+
+	/// Adds the [`Events`] resource for `T`, unless it exists, and has
+	/// [`update_events`](Self::update_events) update it.
+	pub fn try_add_events<T>(self: &Arc<Self>) -> Result<&Arc<Self>, SystemError>
+	where
+		T: Send + Sync + 'static,
+	{
+		// no other lock is held while inserting, as the storage may be held by this thread
+		if self.try_init_resource(Events::<T>::default)? {
+			self.event_updates.lock().push(update_events::<T>);
+		}
+
+		Ok(self)
+	}
+
+	pub fn update_events(self: &Arc<Self>) {
+		self.try_update_events().unwrap()
+	}
+
+	/// Updates every resource added with [`add_events`](Self::add_events).
+	pub fn try_update_events(self: &Arc<Self>) -> Result<(), SystemError> {
+		let updates = self.event_updates.lock().clone();
+		let system = System(self.clone());
+
+		for update in updates {
+			update(&system)?;
+		}
+
+		Ok(())
+	}
+
+	pub fn send_event<T>(self: &Arc<Self>, event: T)
+	where
+		T: Send + Sync + 'static,
+	{
+		self.try_send_event(event).unwrap()
+	}
+
+	/// Sends an event to the [`Events`] resource for `T`, failing with
+	/// [`SystemError::ResourceNotFound`] unless it was added with [`add_events`](Self::add_events).
+	pub fn try_send_event<T>(self: &Arc<Self>, event: T) -> Result<(), SystemError>
+	where
+		T: Send + Sync + 'static,
+	{
+		self.try_resource_mut::<Events<T>>()?.send(event);
+
+		Ok(())
+	}
+}
+
+fn update_events<T>(system: &System) -> Result<(), SystemError>
+where
+	T: Send + Sync + 'static,
+{
+	match system.try_resource_mut::<Events<T>>() {
+		Ok(mut events) => {
+			events.update();
+
+			Ok(())
+		}
+		// removed since it was added
+		Err(SystemError::ResourceNotFound(_)) => Ok(()),
+		Err(error) => Err(error),
+	}
+}
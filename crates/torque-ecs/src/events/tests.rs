@@ -0,0 +1,116 @@
+use std::sync::{Arc, Mutex};
+
+use test_log::test;
+
+use crate::{Schedule, System, SystemError, Task};
+
+use super::{EventReader, Events};
+
+#[test]
+fn double_buffering() {
+	let mut events = Events::default();
+	let mut reader = EventReader::default();
+
+	events.send(1);
+	events.send(2);
+
+	let mut late = events.reader();
+
+	assert_eq!(reader.read(&events).collect::<Vec<_>>(), [&1, &2]);
+	assert!(reader.is_empty(&events));
+
+	events.update();
+	events.send(3);
+
+	assert_eq!(late.len(&events), 1);
+	assert_eq!(reader.read(&events).collect::<Vec<_>>(), [&3]);
+
+	events.update();
+	events.update();
+	events.send(4);
+
+	assert_eq!(late.read(&events).collect::<Vec<_>>(), [&4]);
+	assert_eq!(EventReader::default().read(&events).count(), 1);
+
+	events.clear();
+
+	assert!(events.is_empty());
+	assert!(reader.is_empty(&events));
+}
+
+#[test]
+fn scheduled_readers_see_every_event() {
+	let system = System::default();
+	let mut schedule = Schedule::new();
+	let read = Arc::new(Mutex::new(Vec::new()));
+	let frame = Arc::new(Mutex::new(0_usize));
+
+	system.add_events::<usize>();
+
+	let output = read.clone();
+	let mut reader = EventReader::<usize>::default();
+
+	// the reader runs before the sender, so it sees the events of a frame on the next one
+	schedule
+		.add_stage("update")
+		.add_task(
+			"update",
+			Task::new("read", move |system| {
+				let events = system.resource::<Events<usize>>();
+
+				output.lock().unwrap().extend(reader.read(&events).copied());
+			}),
+		)
+		.add_task(
+			"update",
+			Task::new("send", move |system| {
+				let mut frame = frame.lock().unwrap();
+
+				*frame += 1;
+				system.send_event(*frame * 10);
+				system.send_event(*frame * 10 + 1);
+			})
+			.after("read"),
+		);
+
+	for _ in 0..3 {
+		schedule.run(&system);
+	}
+
+	assert_eq!(*read.lock().unwrap(), [10, 11, 20, 21]);
+	assert!(System::default().try_send_event(0_usize).is_err());
+}
+
+#[test]
+fn adding_and_updating_while_accessing() {
+	let system = System::default();
+
+	system.add_events::<usize>().add_events::<usize>();
+	system.send_event(1_usize);
+
+	{
+		let mut events = system.resource_mut::<Events<usize>>();
+
+		events.send(2);
+
+		// fails instead of deadlocking or panicking
+		assert!(matches!(
+			system.try_add_events::<u8>(),
+			Err(SystemError::ReentrantLock)
+		));
+		assert!(matches!(
+			system.try_update_events(),
+			Err(SystemError::ReentrantResource(_))
+		));
+	}
+
+	// added twice, updated once per call
+	system.update_events();
+
+	assert_eq!(system.resource::<Events<usize>>().len(), 2);
+
+	system.update_events();
+	system.update_events();
+
+	assert!(system.resource::<Events<usize>>().is_empty());
+}
@@ -0,0 +1,260 @@
+#[cfg(test)]
+mod tests;
+
+use std::{collections::VecDeque, mem::take, sync::Arc};
+
+use crate::{
+	storage_guard, system::Inner, Component, EntityId, EntityMethods, EntityRef, System, SystemError,
+	WeakEntityRef,
+};
+
+/// The parent of an entity in the hierarchy, see [`System::insert_child_at`](crate::System).
+/// Maintained along with [`Children`], setting either directly breaks the links.
+#[derive(Component)]
+#[component(value = WeakEntityRef<()>)]
+pub struct Parent;
+
+impl Parent {
+	/// Removes a child whose parent link goes away, e.g. because it is disposed, from the children
+	/// of its parent.
+	pub(crate) fn on_remove(system: &System, entity_id: EntityId) {
+		let Ok(parent_id) = system.try_entity_with::<Parent, _>(entity_id, WeakEntityRef::id) else {
+			return;
+		};
+
+		let _ = system.try_entity_with_mut::<Children, _>(parent_id, |children| {
+			children.retain(|child| child.id != entity_id)
+		});
+	}
+}
+
+/// The children of an entity in the hierarchy, in order. Children are kept alive by their
+/// parent, which only holds a weak reference to them in return.
+#[derive(Component)]
+#[component(value = Vec<EntityRef<()>>)]
+pub struct Children;
+
+impl Children {
+	/// Detaches the children of an entity whose children go away, e.g. because it is disposed, so
+	/// none of them is left pointing at it.
+	pub(crate) fn on_remove(system: &System, entity_id: EntityId) {
+		let children = system
+			.try_entity_with_mut::<Children, _>(entity_id, take)
+			.unwrap_or_default();
+
+		for child in children {
+			let _ = child.try_remove::<Parent>();
+		}
+	}
+}
+
+/// The order [`System::descendants`](crate::System) visits entities in.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum Traversal {
+	/// Every entity before its children, and its children before its next sibling.
+	#[default]
+	DepthFirst,
+	/// Every entity before anything deeper in the hierarchy.
+	BreadthFirst,
+}
+
+/// The ancestors of an entity, from its parent up to the root.
+pub struct Ancestors<'s> {
+	system: &'s Arc<Inner>,
+	next: Option<EntityId>,
+}
+
+impl Iterator for Ancestors<'_> {
+	type Item = EntityId;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		let entity_id = self.next?;
+
+		self.next = self.system.parent_of(entity_id);
+
+		Some(entity_id)
+	}
+}
+
+/// The descendants of an entity, not including itself. Children are looked up as they are
+/// reached, so changes to parts of the hierarchy not visited yet are seen.
+pub struct Descendants<'s> {
+	system: &'s Arc<Inner>,
+	traversal: Traversal,
+	pending: VecDeque<EntityId>,
+}
+
+impl Iterator for Descendants<'_> {
+	type Item = EntityId;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		let entity_id = self.pending.pop_front()?;
+		let children = self.system.children_of(entity_id);
+
+		match self.traversal {
+			Traversal::DepthFirst => {
+				for child_id in children.into_iter().rev() {
+					self.pending.push_front(child_id);
+				}
+			}
+			Traversal::BreadthFirst => self.pending.extend(children),
+		}
+
+		Some(entity_id)
+	}
+}
+
+impl Inner {
+	/// The parent of the entity, `None` for a root or if the entity is gone.
+	pub fn parent_of(self: &Arc<Self>, entity_id: EntityId) -> Option<EntityId> {
+		self
+			.try_entity_with::<Parent, _>(entity_id, WeakEntityRef::id)
+			.ok()
+	}
+
+	/// The children of the entity, empty if it has none or is gone.
+	pub fn children_of(self: &Arc<Self>, entity_id: EntityId) -> Vec<EntityId> {
+		self
+			.try_entity_with::<Children, _>(entity_id, |children| {
+				children.iter().map(|child| child.id).collect()
+			})
+			.unwrap_or_default()
+	}
+
+	pub fn ancestors(self: &Arc<Self>, entity_id: EntityId) -> Ancestors<'_> {
+		Ancestors {
+			system: self,
+			next: self.parent_of(entity_id),
+		}
+	}
+
+	pub fn descendants(
+		self: &Arc<Self>,
+		entity_id: EntityId,
+		traversal: Traversal,
+	) -> Descendants<'_> {
+		Descendants {
+			system: self,
+			traversal,
+			pending: self.children_of(entity_id).into(),
+		}
+	}
+
+	pub fn insert_child_at(self: &Arc<Self>, parent_id: EntityId, index: usize, child_id: EntityId) {
+		self
+			.try_insert_child_at(parent_id, index, child_id)
+			.unwrap()
+	}
+
+	/// Makes the entity a child of `parent_id` at `index`, or last if `index` is past the end,
+	/// detaching it from its current parent first. Fails with [`SystemError::HierarchyCycle`] if
+	/// `parent_id` is the child itself or one of its descendants.
+	pub fn try_insert_child_at(
+		self: &Arc<Self>,
+		parent_id: EntityId,
+		index: usize,
+		child_id: EntityId,
+	) -> Result<(), SystemError> {
+		// waiting for the hierarchy while holding the storage could deadlock with an insert waiting
+		// for the storage, and the insert needs it exclusively anyway
+		if storage_guard::held(self).is_some() {
+			return Err(SystemError::ReentrantLock);
+		}
+
+		let _hierarchy = self.hierarchy.lock();
+
+		if parent_id == child_id || self.ancestors(parent_id).any(|v| v == child_id) {
+			return Err(SystemError::HierarchyCycle(child_id, parent_id));
+		}
+
+		// held until the parent holds it, so detaching can not dispose the child
+		let child = self
+			.get_any(child_id)
+			.ok_or(SystemError::EntityNotFound(child_id))?;
+		let parent = self
+			.get_any(parent_id)
+			.ok_or(SystemError::EntityNotFound(parent_id))?;
+
+		child.try_remove::<Parent>()?;
+		self.try_entity_set::<Parent>(child_id, parent.downgrade())?;
+
+		self.try_entity_with_mut_or_default::<Children, _>(parent_id, |children| {
+			children.insert(index.min(children.len()), child)
+		})
+	}
+
+	pub fn push_child(self: &Arc<Self>, parent_id: EntityId, child_id: EntityId) {
+		self.try_push_child(parent_id, child_id).unwrap()
+	}
+
+	/// Like [`try_insert_child_at`](Self::try_insert_child_at), adding the child last.
+	pub fn try_push_child(
+		self: &Arc<Self>,
+		parent_id: EntityId,
+		child_id: EntityId,
+	) -> Result<(), SystemError> {
+		self.try_insert_child_at(parent_id, usize::MAX, child_id)
+	}
+
+	pub fn remove_child(self: &Arc<Self>, parent_id: EntityId, child_id: EntityId) -> bool {
+		self.try_remove_child(parent_id, child_id).unwrap()
+	}
+
+	/// Detaches the child from `parent_id`, making it a root. Its parent no longer keeps it alive,
+	/// so it is disposed unless referenced elsewhere. Returns `false` if it was not a child of
+	/// `parent_id`.
+	pub fn try_remove_child(
+		self: &Arc<Self>,
+		parent_id: EntityId,
+		child_id: EntityId,
+	) -> Result<bool, SystemError> {
+		if self.parent_of(child_id) != Some(parent_id) {
+			return Ok(false);
+		}
+
+		self.try_detach(child_id)?;
+
+		Ok(true)
+	}
+
+	pub fn reparent(self: &Arc<Self>, child_id: EntityId, parent_id: Option<EntityId>) {
+		self.try_reparent(child_id, parent_id).unwrap()
+	}
+
+	/// Moves the entity under the end of `parent_id`, or detaches it if `None`.
+	pub fn try_reparent(
+		self: &Arc<Self>,
+		child_id: EntityId,
+		parent_id: Option<EntityId>,
+	) -> Result<(), SystemError> {
+		match parent_id {
+			Some(parent_id) => self.try_push_child(parent_id, child_id),
+			None => self.try_detach(child_id),
+		}
+	}
+
+	/// Removes the parent of the entity. The parent may hold the only reference to it, which goes
+	/// away with the link, so the entity is held until the removal is done and only disposed then.
+	fn try_detach(self: &Arc<Self>, child_id: EntityId) -> Result<(), SystemError> {
+		let child = self
+			.get_any(child_id)
+			.ok_or(SystemError::EntityNotFound(child_id))?;
+
+		child.try_remove::<Parent>().map(drop)
+	}
+
+	/// Despawns the entity along with all of its descendants, including those still referenced
+	/// elsewhere. Returns `false` if the entity was already gone.
+	pub fn despawn_recursive(self: &Arc<Self>, entity_id: EntityId) -> bool {
+		let descendants = self
+			.descendants(entity_id, Traversal::DepthFirst)
+			.collect::<Vec<_>>();
+
+		// leaves first, so every entity is detached from a parent that still exists
+		for descendant_id in descendants.into_iter().rev() {
+			self.despawn(descendant_id);
+		}
+
+		self.despawn(entity_id)
+	}
+}
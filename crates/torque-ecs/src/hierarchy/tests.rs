@@ -0,0 +1,182 @@
+use std::{sync::Barrier, thread};
+
+use test_log::test;
+
+use crate::{Entity, EntityMethods, EntityRef, System, SystemError};
+
+use super::{Children, Traversal};
+
+#[derive(Clone, Entity)]
+struct HierarchyEntity;
+
+/// root
+/// ├─ a
+/// │  ├─ c
+/// │  └─ d
+/// └─ b
+fn tree(system: &System) -> [EntityRef<HierarchyEntity>; 5] {
+	let [root, a, b, c, d] = [(); 5].map(|_| system.create::<HierarchyEntity>());
+
+	system.push_child(root.id, a.id);
+	system.push_child(root.id, b.id);
+	system.push_child(a.id, d.id);
+	system.insert_child_at(a.id, 0, c.id);
+
+	[root, a, b, c, d]
+}
+
+#[test]
+fn traversal() {
+	let system = System::default();
+	let [root, a, b, c, d] = tree(&system);
+
+	assert_eq!(
+		system
+			.descendants(root.id, Traversal::DepthFirst)
+			.collect::<Vec<_>>(),
+		[a.id, c.id, d.id, b.id]
+	);
+	assert_eq!(
+		system
+			.descendants(root.id, Traversal::BreadthFirst)
+			.collect::<Vec<_>>(),
+		[a.id, b.id, c.id, d.id]
+	);
+	assert_eq!(system.ancestors(d.id).collect::<Vec<_>>(), [a.id, root.id]);
+}
+
+#[test]
+fn remove_child_keeps_the_others() {
+	let system = System::default();
+	let [_root, a, _b, c, d] = tree(&system);
+
+	assert!(system.remove_child(a.id, c.id));
+	assert!(!system.remove_child(a.id, c.id));
+	assert_eq!(system.children_of(a.id), [d.id]);
+	assert_eq!(system.parent_of(c.id), None);
+	assert!(system.contains(c.id));
+}
+
+#[test]
+fn reparent_and_cycles() {
+	let system = System::default();
+	let [root, a, b, c, _d] = tree(&system);
+
+	system.reparent(c.id, Some(b.id));
+
+	assert_eq!(system.parent_of(c.id), Some(b.id));
+	assert_eq!(system.children_of(b.id), [c.id]);
+	assert_eq!(system.children_of(a.id).len(), 1);
+
+	assert!(matches!(
+		system.try_reparent(root.id, Some(c.id)),
+		Err(SystemError::HierarchyCycle(..))
+	));
+	assert!(matches!(
+		system.try_push_child(a.id, a.id),
+		Err(SystemError::HierarchyCycle(..))
+	));
+	assert_eq!(system.parent_of(root.id), None);
+}
+
+#[test]
+fn concurrent_inserts_form_no_cycle() {
+	for _ in 0..1000 {
+		let system = System::default();
+		let [a, b] = [(); 2].map(|_| system.create::<HierarchyEntity>());
+		let barrier = Barrier::new(2);
+
+		let [a_under_b, b_under_a] = thread::scope(|scope| {
+			[(a.id, b.id), (b.id, a.id)]
+				.map(|(child_id, parent_id)| {
+					let (system, barrier) = (&system, &barrier);
+
+					scope.spawn(move || {
+						barrier.wait();
+						system.try_push_child(parent_id, child_id)
+					})
+				})
+				.map(|handle| handle.join().unwrap())
+		});
+
+		assert!(a_under_b.is_ok() != b_under_a.is_ok());
+		assert!(system.parent_of(a.id).is_none() || system.parent_of(b.id).is_none());
+	}
+}
+
+#[test]
+fn insert_within_closure() {
+	let system = System::default();
+	let [root, _a, b, c, _d] = tree(&system);
+
+	// fails instead of deadlocking with an insert waiting for the storage
+	let result = root.with::<Children, _>(|_| system.try_push_child(b.id, c.id));
+
+	assert!(matches!(result, Err(SystemError::ReentrantLock)));
+	assert_eq!(system.children_of(b.id), []);
+}
+
+#[test]
+fn parent_keeps_children_alive() {
+	let system = System::default();
+	let parent = system.create::<HierarchyEntity>();
+	let child = system.create::<HierarchyEntity>();
+	let child_id = child.id;
+
+	system.push_child(parent.id, child_id);
+	drop(child);
+
+	assert!(system.contains(child_id));
+
+	// disposing the parent detaches and thereby releases its children
+	drop(parent);
+
+	assert!(!system.contains(child_id));
+}
+
+#[test]
+fn detaching_child_owned_by_parent() {
+	let system = System::default();
+	let parent = system.create::<HierarchyEntity>();
+	let [removed, reparented] = [(); 2].map(|_| {
+		let child = system.create::<HierarchyEntity>();
+
+		system.push_child(parent.id, child.id);
+
+		child.id
+	});
+
+	// the parent holds the only reference, so detaching disposes the child once done
+	assert!(system.remove_child(parent.id, removed));
+	assert!(!system.contains(removed));
+
+	system.reparent(reparented, None);
+
+	assert!(!system.contains(reparented));
+	assert!(system.children_of(parent.id).is_empty());
+}
+
+#[test]
+fn disposed_child_leaves_parent() {
+	let system = System::default();
+	let [_root, a, _b, c, d] = tree(&system);
+
+	system.despawn(c.id);
+
+	assert_eq!(system.children_of(a.id), [d.id]);
+}
+
+#[test]
+fn despawn_recursive() {
+	let system = System::default();
+	let [root, a, b, c, d] = tree(&system);
+
+	assert!(system.despawn_recursive(a.id));
+	assert_eq!(system.children_of(root.id), [b.id]);
+
+	for entity in [&a, &c, &d] {
+		assert!(!system.contains(entity.id));
+	}
+
+	assert!(!system.despawn_recursive(a.id));
+}
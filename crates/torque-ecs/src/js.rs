@@ -0,0 +1,669 @@
+//! JavaScript wrappers of entities. An entity is wrapped in an object of the class of its
+//! registered [`Entity`] type, which extends the class of its base type and, at the root, the
+//! `Entity` class. A wrapper holds an [`EntityRef`], keeping the entity alive until the wrapper is
+//! collected, while a `WeakEntity` holds a [`WeakEntityRef`]. An entity has a single wrapper per
+//! context for as long as the wrapper is reachable, so wrappers of the same entity are `===`, while
+//! every `downgrade()` creates a new `WeakEntity`.
+//!
+//! ```js
+//! if (entity.is("Element")) {
+//!   entity.set("Style", { ...entity.get("Style"), width: 100 });
+//! }
+//!
+//! const weak = entity.downgrade();
+//! weak.deref()?.remove("Style");
+//! ```
+//!
+//! Components are read and written by name, for components registered with
+//! [`ComponentType::scripted`](crate::registry::ComponentType::scripted), e.g. by
+//! `#[component(v8)]`.
+
+use std::{cell::RefCell, rc::Rc, sync::Arc};
+
+use fnv::FnvHashMap;
+use m8::{IntoV8Error, TryFromV8, TryFromV8Error, TryIntoV8};
+use slotmap::Key;
+
+use crate::{
+	registry::{self, EntityType},
+	Component, Entity, EntityId, EntityRef, System, SystemError, WeakEntityRef,
+};
+
+pub const ENTITY_TAG: u16 = m8::Tags::LAST_TAG + 1;
+pub const WEAK_ENTITY_TAG: u16 = m8::Tags::LAST_TAG + 2;
+
+type GetFn = for<'s> fn(
+	&mut v8::HandleScope<'s>,
+	&System,
+	EntityId,
+) -> Result<v8::Local<'s, v8::Value>, Error>;
+type SetFn = fn(&mut v8::HandleScope, &System, EntityId, v8::Local<v8::Value>) -> Result<(), Error>;
+
+/// How to read and write the value of one component from JavaScript.
+#[derive(Clone, Copy)]
+pub(crate) struct ComponentV8 {
+	get: GetFn,
+	set: SetFn,
+	remove: fn(&System, EntityId) -> Result<bool, SystemError>,
+}
+
+impl ComponentV8 {
+	pub(crate) const fn of<C>() -> Self
+	where
+		C: Component + 'static,
+		C::Value: Clone + TryIntoV8 + TryFromV8,
+	{
+		Self {
+			get: |scope, system, entity_id| {
+				let value = system.try_entity_with::<C, _>(entity_id, Clone::clone)?;
+
+				Ok(C::Value::try_into_v8(scope, value)?)
+			},
+			set: |scope, system, entity_id, value| {
+				let value = C::Value::try_from_v8(scope, value)?;
+
+				Ok(system.try_entity_set::<C>(entity_id, value)?)
+			},
+			remove: |system, entity_id| Ok(system.try_entity_remove::<C>(entity_id)?.is_some()),
+		}
+	}
+}
+
+impl std::fmt::Debug for ComponentV8 {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		f.debug_struct("ComponentV8").finish_non_exhaustive()
+	}
+}
+
+/// What is thrown to JavaScript by the methods of wrappers.
+#[derive(Debug, thiserror::Error)]
+pub(crate) enum Error {
+	#[error(transparent)]
+	System(#[from] SystemError),
+	#[error("{0}")]
+	IntoV8(#[from] IntoV8Error),
+	#[error("{0}")]
+	FromV8(#[from] TryFromV8Error),
+	#[error("illegal invocation")]
+	IllegalInvocation,
+	#[error("expected the name of an entity or component type")]
+	ExpectedName,
+	#[error(transparent)]
+	AmbiguousName(#[from] registry::AmbiguousName),
+	#[error("unknown entity type {0}")]
+	UnknownEntity(String),
+	#[error("unknown component {0}")]
+	UnknownComponent(String),
+	#[error("component {0} can not be accessed from javascript")]
+	NotScripted(&'static str),
+}
+
+/// The classes of the current context, created as they are first needed, and the wrappers of
+/// entities created in it.
+#[derive(Default)]
+struct Templates(RefCell<TemplatesInner>);
+
+#[derive(Default)]
+struct TemplatesInner {
+	entity: Option<v8::Global<v8::FunctionTemplate>>,
+	weak_entity: Option<v8::Global<v8::FunctionTemplate>>,
+	entity_types: FnvHashMap<&'static str, v8::Global<v8::FunctionTemplate>>,
+	/// Removed by the finalizer of the wrapper once it is collected.
+	wrappers: FnvHashMap<WrapperKey, v8::Weak<v8::Object>>,
+}
+
+/// The address of the system along with the id, as systems hand out the same ids. The address is
+/// not reused while the wrapper is reachable, as its reference keeps the system alive.
+type WrapperKey = (usize, EntityId);
+
+/// Sets the `Entity` and `WeakEntity` classes and the class of every registered entity type as
+/// properties of `target`, usually the global object. Wrappers can only be created in contexts
+/// passed to `init`.
+pub fn init(scope: &mut v8::HandleScope, target: v8::Local<v8::Object>) {
+	let context = scope.get_current_context();
+
+	context.set_slot(Rc::new(Templates::default()));
+
+	let templates = [
+		("Entity", entity_template(scope)),
+		("WeakEntity", weak_entity_template(scope)),
+	]
+	.into_iter()
+	.map(|(name, template)| (name, Some(template)))
+	.chain(registry::entity_types().map(|v| (v.name(), entity_type_template(scope, v))))
+	.collect::<Vec<_>>();
+
+	for (name, template) in templates {
+		let Some(function) = template.and_then(|v| v.get_function(scope)) else {
+			continue;
+		};
+		let key = v8::String::new(scope, name).unwrap();
+
+		target.set(scope, key.into(), function.into());
+	}
+}
+
+impl<E> TryIntoV8 for EntityRef<E>
+where
+	E: Entity + 'static,
+{
+	/// The wrapper of the entity in the current context, created unless it is still reachable.
+	/// Fails with [`IntoV8Error::Expired`] if the entity was despawned.
+	fn try_into_v8<'s>(
+		scope: &mut v8::HandleScope<'s>,
+		value: Self,
+	) -> Result<v8::Local<'s, v8::Value>, IntoV8Error> {
+		let entity = value.system.get_any(value.id).ok_or(IntoV8Error::Expired)?;
+		let templates = templates(scope).ok_or(IntoV8Error::ContextNotInitialized)?;
+		let key = (Arc::as_ptr(&value.system.0) as usize, value.id);
+		let cached = templates
+			.0
+			.borrow()
+			.wrappers
+			.get(&key)
+			.and_then(|v| v.to_local(scope));
+
+		if let Some(wrapper) = cached {
+			return Ok(wrapper.into());
+		}
+
+		let entity_type = value
+			.system
+			.entity_name(value.id)
+			.and_then(registry::entity_type);
+		let template = match entity_type {
+			Some(entity_type) => entity_type_template(scope, entity_type),
+			None => Some(entity_template(scope)),
+		}
+		.ok_or(IntoV8Error::ContextNotInitialized)?;
+		let wrapper = template
+			.instance_template(scope)
+			.new_instance(scope)
+			.ok_or(IntoV8Error::NewInstanceFailed)?;
+		let heap = scope
+			.get_cpp_heap()
+			.ok_or(IntoV8Error::CppHeapNotAttached)?;
+
+		// SAFETY: the pointer is only held on the stack until the wrapper holds it, and the tag is
+		// only used for wrappers of `EntityRef<()>`
+		unsafe {
+			let ptr = v8::cppgc::make_garbage_collected(heap, entity);
+
+			v8::Object::wrap::<ENTITY_TAG, EntityRef<()>>(scope, wrapper, &ptr);
+		}
+
+		let weak_templates = Rc::downgrade(&templates);
+		let weak = v8::Weak::with_finalizer(
+			scope,
+			wrapper,
+			Box::new(move |_: &mut v8::Isolate| {
+				// a wrapper replacing this one drops the handle and with it this finalizer, so the
+				// entry is still this one
+				if let Some(templates) = weak_templates.upgrade() {
+					if let Ok(mut inner) = templates.0.try_borrow_mut() {
+						inner.wrappers.remove(&key);
+					}
+				}
+			}),
+		);
+
+		templates.0.borrow_mut().wrappers.insert(key, weak);
+
+		Ok(wrapper.into())
+	}
+}
+
+impl<E> TryFromV8 for EntityRef<E>
+where
+	E: Entity + 'static,
+{
+	/// Fails with [`TryFromV8Error::TypeMismatch`] unless the value wraps an entity of type `E`
+	/// that still exists.
+	fn try_from_v8(
+		scope: &mut v8::HandleScope,
+		value: v8::Local<v8::Value>,
+	) -> Result<Self, TryFromV8Error> {
+		let entity = unwrap_entity(scope, value).ok_or(TryFromV8Error::TypeMismatch)?;
+
+		entity
+			.system
+			.get::<E>(entity.id)
+			.ok_or(TryFromV8Error::TypeMismatch)
+	}
+}
+
+impl<E> TryIntoV8 for WeakEntityRef<E>
+where
+	E: Entity + 'static,
+{
+	fn try_into_v8<'s>(
+		scope: &mut v8::HandleScope<'s>,
+		value: Self,
+	) -> Result<v8::Local<'s, v8::Value>, IntoV8Error> {
+		templates(scope).ok_or(IntoV8Error::ContextNotInitialized)?;
+
+		let wrapper = weak_entity_template(scope)
+			.instance_template(scope)
+			.new_instance(scope)
+			.ok_or(IntoV8Error::NewInstanceFailed)?;
+		let weak = WeakEntityRef::<()>::new(value.system().clone(), value.id());
+		let heap = scope
+			.get_cpp_heap()
+			.ok_or(IntoV8Error::CppHeapNotAttached)?;
+
+		// SAFETY: the pointer is only held on the stack until the wrapper holds it, and the tag is
+		// only used for wrappers of `WeakEntityRef<()>`
+		unsafe {
+			let ptr = v8::cppgc::make_garbage_collected(heap, weak);
+
+			v8::Object::wrap::<WEAK_ENTITY_TAG, WeakEntityRef<()>>(scope, wrapper, &ptr);
+		}
+
+		Ok(wrapper.into())
+	}
+}
+
+impl<E> TryFromV8 for WeakEntityRef<E>
+where
+	E: Entity + 'static,
+{
+	/// Accepts a `WeakEntity` or an entity, failing with [`TryFromV8Error::TypeMismatch`] unless
+	/// the entity is of type `E`. A `WeakEntity` of an entity that is gone is accepted.
+	fn try_from_v8(
+		scope: &mut v8::HandleScope,
+		value: v8::Local<v8::Value>,
+	) -> Result<Self, TryFromV8Error> {
+		let (system, entity_id) = match unwrap_weak_entity(scope, value) {
+			Some(weak) => (weak.system().clone(), weak.id()),
+			None => {
+				let entity = unwrap_entity(scope, value).ok_or(TryFromV8Error::TypeMismatch)?;
+
+				(entity.system.clone(), entity.id)
+			}
+		};
+
+		if system.contains(entity_id) && !system.entity_is::<E>(entity_id) {
+			return Err(TryFromV8Error::TypeMismatch);
+		}
+
+		Ok(WeakEntityRef::new(system, entity_id))
+	}
+}
+
+fn templates(scope: &mut v8::HandleScope) -> Option<Rc<Templates>> {
+	scope
+		.get_current_context()
+		.get_slot::<Rc<Templates>>()
+		.cloned()
+}
+
+/// The `Entity` class, which the classes of entity types extend. Entities of unregistered types
+/// are wrapped in it directly.
+fn entity_template<'s>(scope: &mut v8::HandleScope<'s>) -> v8::Local<'s, v8::FunctionTemplate> {
+	let templates = templates(scope).expect("js::init should be called for the context");
+
+	if let Some(template) = &templates.0.borrow().entity {
+		return v8::Local::new(scope, template);
+	}
+
+	let template = class_template(scope, "Entity");
+	let prototype = template.prototype_template(scope);
+
+	set_getter(scope, prototype, "id", entity_id);
+	set_getter(scope, prototype, "type", entity_type_name);
+	set_method(scope, prototype, "is", entity_is);
+	set_method(scope, prototype, "as", entity_as);
+	set_method(scope, prototype, "has", entity_has);
+	set_method(scope, prototype, "get", entity_get);
+	set_method(scope, prototype, "set", entity_set);
+	set_method(scope, prototype, "remove", entity_remove);
+	set_method(scope, prototype, "downgrade", entity_downgrade);
+
+	templates.0.borrow_mut().entity = Some(v8::Global::new(scope, template));
+
+	template
+}
+
+fn weak_entity_template<'s>(
+	scope: &mut v8::HandleScope<'s>,
+) -> v8::Local<'s, v8::FunctionTemplate> {
+	let templates = templates(scope).expect("js::init should be called for the context");
+
+	if let Some(template) = &templates.0.borrow().weak_entity {
+		return v8::Local::new(scope, template);
+	}
+
+	let template = class_template(scope, "WeakEntity");
+	let prototype = template.prototype_template(scope);
+
+	set_getter(scope, prototype, "id", weak_entity_id);
+	set_method(scope, prototype, "deref", weak_entity_deref);
+
+	templates.0.borrow_mut().weak_entity = Some(v8::Global::new(scope, template));
+
+	template
+}
+
+/// The class of a registered entity type, extending the class of its base type. `None` if the
+/// context was not passed to [`init`].
+fn entity_type_template<'s>(
+	scope: &mut v8::HandleScope<'s>,
+	entity_type: &'static EntityType,
+) -> Option<v8::Local<'s, v8::FunctionTemplate>> {
+	let templates = templates(scope)?;
+
+	if let Some(template) = templates.0.borrow().entity_types.get(entity_type.name()) {
+		return Some(v8::Local::new(scope, template));
+	}
+
+	let base = match entity_type.base() {
+		Some(base) => entity_type_template(scope, base)?,
+		None => entity_template(scope),
+	};
+	let template = class_template(scope, entity_type.name());
+
+	template.inherit(base);
+
+	templates
+		.0
+		.borrow_mut()
+		.entity_types
+		.insert(entity_type.name(), v8::Global::new(scope, template));
+
+	Some(template)
+}
+
+/// A class whose constructor throws, instances are only created from Rust.
+fn class_template<'s>(
+	scope: &mut v8::HandleScope<'s>,
+	name: &str,
+) -> v8::Local<'s, v8::FunctionTemplate> {
+	let template = v8::FunctionTemplate::new(scope, |scope, _args, _rv| {
+		m8::throw_error!(scope, "illegal constructor");
+	});
+	let class_name = v8::String::new(scope, name).unwrap();
+
+	template.set_class_name(class_name);
+
+	template
+}
+
+fn set_method(
+	scope: &mut v8::HandleScope,
+	prototype: v8::Local<v8::ObjectTemplate>,
+	name: &str,
+	callback: impl v8::MapFnTo<v8::FunctionCallback>,
+) {
+	let key = v8::String::new(scope, name).unwrap();
+	let method = v8::FunctionTemplate::new(scope, callback);
+
+	prototype.set(key.into(), method.into());
+}
+
+fn set_getter(
+	scope: &mut v8::HandleScope,
+	prototype: v8::Local<v8::ObjectTemplate>,
+	name: &str,
+	callback: impl v8::MapFnTo<v8::FunctionCallback>,
+) {
+	let key = v8::String::new(scope, name).unwrap();
+	let getter = v8::FunctionTemplate::new(scope, callback);
+
+	prototype.set_accessor_property(
+		key.into(),
+		Some(getter),
+		None,
+		v8::PropertyAttribute::READ_ONLY,
+	);
+}
+
+fn unwrap_entity(
+	scope: &mut v8::HandleScope,
+	value: v8::Local<v8::Value>,
+) -> Option<v8::cppgc::Ptr<EntityRef<()>>> {
+	let wrapper = value.try_cast::<v8::Object>().ok()?;
+
+	unsafe { v8::Object::unwrap::<ENTITY_TAG, EntityRef<()>>(scope, wrapper) }
+}
+
+fn unwrap_weak_entity(
+	scope: &mut v8::HandleScope,
+	value: v8::Local<v8::Value>,
+) -> Option<v8::cppgc::Ptr<WeakEntityRef<()>>> {
+	let wrapper = value.try_cast::<v8::Object>().ok()?;
+
+	unsafe { v8::Object::unwrap::<WEAK_ENTITY_TAG, WeakEntityRef<()>>(scope, wrapper) }
+}
+
+/// Runs a method of an entity wrapper, throwing its error.
+fn with_entity<'s>(
+	scope: &mut v8::HandleScope<'s>,
+	args: &v8::FunctionCallbackArguments<'s>,
+	mut rv: v8::ReturnValue,
+	f: impl FnOnce(&mut v8::HandleScope<'s>, &EntityRef<()>) -> Result<v8::Local<'s, v8::Value>, Error>,
+) {
+	let result = unwrap_entity(scope, args.this().into())
+		.ok_or(Error::IllegalInvocation)
+		.and_then(|entity| f(scope, &entity));
+
+	match result {
+		Ok(value) => rv.set(value),
+		Err(error) => {
+			m8::throw_error!(scope, &error.to_string());
+		}
+	}
+}
+
+/// The argument at `index`, as the name of a type.
+fn name_arg(
+	scope: &mut v8::HandleScope,
+	args: &v8::FunctionCallbackArguments,
+	index: i32,
+) -> Result<String, Error> {
+	let name = args.get(index);
+
+	if !name.is_string() {
+		return Err(Error::ExpectedName);
+	}
+
+	Ok(name.to_rust_string_lossy(scope))
+}
+
+/// The component type named by the argument at `index`, if it can be accessed from JavaScript.
+fn component_arg(
+	scope: &mut v8::HandleScope,
+	args: &v8::FunctionCallbackArguments,
+	index: i32,
+) -> Result<ComponentV8, Error> {
+	let name = name_arg(scope, args, index)?;
+	let component_type = registry::try_component_type(&name)?.ok_or(Error::UnknownComponent(name))?;
+
+	component_type
+		.v8()
+		.ok_or(Error::NotScripted(component_type.name()))
+}
+
+/// Whether the entity is of the type named by the argument at `index`, or extends it.
+fn is_arg(
+	scope: &mut v8::HandleScope,
+	args: &v8::FunctionCallbackArguments,
+	index: i32,
+	entity: &EntityRef<()>,
+) -> Result<bool, Error> {
+	let name = name_arg(scope, args, index)?;
+	let expected = registry::try_entity_type(&name)?.ok_or(Error::UnknownEntity(name))?;
+	let entity_name = entity
+		.system
+		.entity_name(entity.id)
+		.ok_or(SystemError::EntityNotFound(entity.id))?;
+
+	Ok(
+		entity_name == expected.name()
+			|| registry::entity_type(entity_name)
+				.is_some_and(|v| v.type_ids().contains(&expected.type_id())),
+	)
+}
+
+fn id_value<'s>(scope: &mut v8::HandleScope<'s>, entity_id: EntityId) -> v8::Local<'s, v8::Value> {
+	v8::BigInt::new_from_u64(scope, entity_id.data().as_ffi()).into()
+}
+
+fn entity_id<'s>(
+	scope: &mut v8::HandleScope<'s>,
+	args: v8::FunctionCallbackArguments<'s>,
+	rv: v8::ReturnValue,
+) {
+	with_entity(scope, &args, rv, |scope, entity| {
+		Ok(id_value(scope, entity.id))
+	});
+}
+
+fn entity_type_name<'s>(
+	scope: &mut v8::HandleScope<'s>,
+	args: v8::FunctionCallbackArguments<'s>,
+	rv: v8::ReturnValue,
+) {
+	with_entity(scope, &args, rv, |scope, entity| {
+		let name = entity
+			.system
+			.entity_name(entity.id)
+			.ok_or(SystemError::EntityNotFound(entity.id))?;
+
+		Ok(v8::String::new(scope, name).unwrap().into())
+	});
+}
+
+/// `is(name)`, whether the entity is of the named type or extends it.
+fn entity_is<'s>(
+	scope: &mut v8::HandleScope<'s>,
+	args: v8::FunctionCallbackArguments<'s>,
+	rv: v8::ReturnValue,
+) {
+	with_entity(scope, &args, rv, |scope, entity| {
+		let is = is_arg(scope, &args, 0, entity)?;
+
+		Ok(v8::Boolean::new(scope, is).into())
+	});
+}
+
+/// `as(name)`, the entity if it is of the named type, `null` otherwise.
+fn entity_as<'s>(
+	scope: &mut v8::HandleScope<'s>,
+	args: v8::FunctionCallbackArguments<'s>,
+	rv: v8::ReturnValue,
+) {
+	with_entity(scope, &args, rv, |scope, entity| {
+		if is_arg(scope, &args, 0, entity)? {
+			Ok(args.this().into())
+		} else {
+			Ok(v8::null(scope).into())
+		}
+	});
+}
+
+/// `has(name)`, for any registered component.
+fn entity_has<'s>(
+	scope: &mut v8::HandleScope<'s>,
+	args: v8::FunctionCallbackArguments<'s>,
+	rv: v8::ReturnValue,
+) {
+	with_entity(scope, &args, rv, |scope, entity| {
+		let name = name_arg(scope, &args, 0)?;
+		let component_type =
+			registry::try_component_type(&name)?.ok_or(Error::UnknownComponent(name))?;
+		let has = entity
+			.system
+			.try_entity_component_names(entity.id)?
+			.any(|v| v == component_type.name());
+
+		Ok(v8::Boolean::new(scope, has).into())
+	});
+}
+
+/// `get(name)`, a copy of the value of the component.
+fn entity_get<'s>(
+	scope: &mut v8::HandleScope<'s>,
+	args: v8::FunctionCallbackArguments<'s>,
+	rv: v8::ReturnValue,
+) {
+	with_entity(scope, &args, rv, |scope, entity| {
+		let component = component_arg(scope, &args, 0)?;
+
+		(component.get)(scope, &entity.system, entity.id)
+	});
+}
+
+/// `set(name, value)`, returning the entity.
+fn entity_set<'s>(
+	scope: &mut v8::HandleScope<'s>,
+	args: v8::FunctionCallbackArguments<'s>,
+	rv: v8::ReturnValue,
+) {
+	with_entity(scope, &args, rv, |scope, entity| {
+		let component = component_arg(scope, &args, 0)?;
+
+		(component.set)(scope, &entity.system, entity.id, args.get(1))?;
+
+		Ok(args.this().into())
+	});
+}
+
+/// `remove(name)`, whether the entity carried the component.
+fn entity_remove<'s>(
+	scope: &mut v8::HandleScope<'s>,
+	args: v8::FunctionCallbackArguments<'s>,
+	rv: v8::ReturnValue,
+) {
+	with_entity(scope, &args, rv, |scope, entity| {
+		let component = component_arg(scope, &args, 0)?;
+		let removed = (component.remove)(&entity.system, entity.id)?;
+
+		Ok(v8::Boolean::new(scope, removed).into())
+	});
+}
+
+fn entity_downgrade<'s>(
+	scope: &mut v8::HandleScope<'s>,
+	args: v8::FunctionCallbackArguments<'s>,
+	rv: v8::ReturnValue,
+) {
+	with_entity(scope, &args, rv, |scope, entity| {
+		let weak = WeakEntityRef::<()>::new(entity.system.clone(), entity.id);
+
+		Ok(WeakEntityRef::try_into_v8(scope, weak)?)
+	});
+}
+
+fn weak_entity_id<'s>(
+	scope: &mut v8::HandleScope<'s>,
+	args: v8::FunctionCallbackArguments<'s>,
+	mut rv: v8::ReturnValue,
+) {
+	match unwrap_weak_entity(scope, args.this().into()) {
+		Some(weak) => rv.set(id_value(scope, weak.id())),
+		None => {
+			m8::throw_error!(scope, &Error::IllegalInvocation.to_string());
+		}
+	}
+}
+
+/// `deref()`, a wrapper keeping the entity alive, `undefined` if it is gone.
+fn weak_entity_deref<'s>(
+	scope: &mut v8::HandleScope<'s>,
+	args: v8::FunctionCallbackArguments<'s>,
+	mut rv: v8::ReturnValue,
+) {
+	let result = unwrap_weak_entity(scope, args.this().into())
+		.ok_or(Error::IllegalInvocation)
+		.and_then(|weak| match weak.upgrade() {
+			Some(entity) => Ok(EntityRef::try_into_v8(scope, entity)?),
+			None => Ok(v8::undefined(scope).into()),
+		});
+
+	match result {
+		Ok(value) => rv.set(value),
+		Err(error) => {
+			m8::throw_error!(scope, &error.to_string());
+		}
+	}
+}
@@ -1,23 +1,57 @@
 extern crate self as torque_ecs;
 
+mod cell_borrow;
+mod commands;
 mod component;
+mod component_hooks;
+mod component_ref;
 mod entity;
 mod entity_id;
 mod entity_ref;
+mod event;
+mod events;
 mod extends;
+mod hierarchy;
+#[cfg(feature = "v8")]
+pub mod js;
+mod query;
+pub mod registry;
+mod resource;
+mod schedule;
+mod schedule_error;
+mod snapshot;
+mod storage;
+mod storage_guard;
 mod system;
 mod system_error;
+mod task;
+mod tick;
 mod weak_entity_ref;
 
 pub use self::{
+	commands::Commands,
 	component::Component,
+	component_hooks::ComponentHook,
+	component_ref::{Ref, RefMut},
 	entity::{Entity, EntityMethods},
 	entity_id::EntityId,
 	entity_ref::EntityRef,
+	event::{Event, ObserverId, Phase, Trigger},
+	events::{EventReader, Events},
 	extends::Extends,
+	hierarchy::{Ancestors, Children, Descendants, Parent, Traversal},
+	query::{Access, Added, Changed, Is, Query, QueryData, QueryFilter, QueryIter, With, Without},
+	resource::{Res, ResMut},
+	schedule::Schedule,
+	schedule_error::ScheduleError,
+	snapshot::Snapshot,
+	storage::ComponentId,
 	system::System,
 	system_error::SystemError,
+	task::Task,
+	tick::Tick,
 	weak_entity_ref::WeakEntityRef,
 };
 
+pub use inventory;
 pub use torque_ecs_macros::{Component, Entity};
@@ -0,0 +1,281 @@
+use std::any::TypeId;
+
+use crate::{
+	storage::{Archetype, ComponentId, Components},
+	tick::{ComponentTicks, Tick},
+	Component,
+};
+
+use super::Access;
+
+/// The component terms of a query, e.g. `&Style`, `&mut Layout`, `Option<&Parent>` or a tuple of
+/// those. Items borrow the component values, not the `Component` marker types.
+///
+/// # Safety
+///
+/// `access` must report every component `item` hands out, reads as reads and writes as writes,
+/// since that is what keeps two terms of one query from aliasing. `borrows` must report the same
+/// components by id, since that is what keeps other threads from aliasing them.
+pub unsafe trait QueryData {
+	type Item<'q>;
+
+	#[doc(hidden)]
+	type State: Copy;
+
+	#[doc(hidden)]
+	type Fetch: Copy;
+
+	#[doc(hidden)]
+	fn access(access: &mut Access);
+
+	/// Returns `None` when the query can not match anything, e.g. a required component that was
+	/// never stored.
+	#[doc(hidden)]
+	fn init_state(components: &Components) -> Option<Self::State>;
+
+	/// Pushes the components the query borrows, `true` for those it writes.
+	#[doc(hidden)]
+	fn borrows(state: &Self::State, borrows: &mut Vec<(ComponentId, bool)>);
+
+	#[doc(hidden)]
+	fn matches(state: &Self::State, archetype: &Archetype) -> bool;
+
+	/// Components handed out mutably are marked changed at `change_tick`.
+	#[doc(hidden)]
+	fn fetch(state: &Self::State, archetype: &Archetype, change_tick: Tick) -> Self::Fetch;
+
+	/// # Safety
+	///
+	/// `row` must be in bounds of the archetype `fetch` was created from, the columns reported by
+	/// `borrows` must be borrowed for `'q` and every row must be fetched at most once.
+	#[doc(hidden)]
+	unsafe fn item<'q>(fetch: Self::Fetch, row: usize) -> Self::Item<'q>;
+}
+
+#[doc(hidden)]
+pub struct ReadFetch<T>(*const T);
+
+impl<T> Clone for ReadFetch<T> {
+	fn clone(&self) -> Self {
+		*self
+	}
+}
+
+impl<T> Copy for ReadFetch<T> {}
+
+#[doc(hidden)]
+pub struct WriteFetch<T>(*mut T, *mut ComponentTicks, Tick);
+
+impl<T> Clone for WriteFetch<T> {
+	fn clone(&self) -> Self {
+		*self
+	}
+}
+
+impl<T> Copy for WriteFetch<T> {}
+
+unsafe impl<C> QueryData for &C
+where
+	C: Component + 'static,
+{
+	type Item<'q> = &'q C::Value;
+
+	type State = ComponentId;
+
+	type Fetch = ReadFetch<C::Value>;
+
+	fn access(access: &mut Access) {
+		access.add_read(TypeId::of::<C>(), C::NAME);
+	}
+
+	fn init_state(components: &Components) -> Option<Self::State> {
+		components.id::<C>()
+	}
+
+	fn borrows(state: &Self::State, borrows: &mut Vec<(ComponentId, bool)>) {
+		borrows.push((*state, false));
+	}
+
+	fn matches(state: &Self::State, archetype: &Archetype) -> bool {
+		archetype.contains(*state)
+	}
+
+	fn fetch(state: &Self::State, archetype: &Archetype, _change_tick: Tick) -> Self::Fetch {
+		ReadFetch(
+			archetype
+				.column(*state)
+				.expect("archetype should match")
+				.values_ptr::<C::Value>(),
+		)
+	}
+
+	unsafe fn item<'q>(fetch: Self::Fetch, row: usize) -> Self::Item<'q> {
+		&*fetch.0.add(row)
+	}
+}
+
+unsafe impl<C> QueryData for &mut C
+where
+	C: Component + 'static,
+{
+	type Item<'q> = &'q mut C::Value;
+
+	type State = ComponentId;
+
+	type Fetch = WriteFetch<C::Value>;
+
+	fn access(access: &mut Access) {
+		access.add_write(TypeId::of::<C>(), C::NAME);
+	}
+
+	fn init_state(components: &Components) -> Option<Self::State> {
+		components.id::<C>()
+	}
+
+	fn borrows(state: &Self::State, borrows: &mut Vec<(ComponentId, bool)>) {
+		borrows.push((*state, true));
+	}
+
+	fn matches(state: &Self::State, archetype: &Archetype) -> bool {
+		archetype.contains(*state)
+	}
+
+	fn fetch(state: &Self::State, archetype: &Archetype, change_tick: Tick) -> Self::Fetch {
+		let column = archetype.column(*state).expect("archetype should match");
+
+		WriteFetch(
+			column.values_ptr::<C::Value>(),
+			column.ticks_ptr(),
+			change_tick,
+		)
+	}
+
+	unsafe fn item<'q>(fetch: Self::Fetch, row: usize) -> Self::Item<'q> {
+		(*fetch.1.add(row)).changed = fetch.2;
+
+		&mut *fetch.0.add(row)
+	}
+}
+
+unsafe impl<T> QueryData for Option<T>
+where
+	T: QueryData,
+{
+	type Item<'q> = Option<T::Item<'q>>;
+
+	type State = Option<T::State>;
+
+	type Fetch = Option<T::Fetch>;
+
+	fn access(access: &mut Access) {
+		T::access(access);
+	}
+
+	fn init_state(components: &Components) -> Option<Self::State> {
+		Some(T::init_state(components))
+	}
+
+	fn borrows(state: &Self::State, borrows: &mut Vec<(ComponentId, bool)>) {
+		if let Some(state) = state {
+			T::borrows(state, borrows);
+		}
+	}
+
+	fn matches(_state: &Self::State, _archetype: &Archetype) -> bool {
+		true
+	}
+
+	fn fetch(state: &Self::State, archetype: &Archetype, change_tick: Tick) -> Self::Fetch {
+		state
+			.as_ref()
+			.filter(|state| T::matches(state, archetype))
+			.map(|state| T::fetch(state, archetype, change_tick))
+	}
+
+	unsafe fn item<'q>(fetch: Self::Fetch, row: usize) -> Self::Item<'q> {
+		fetch.map(|fetch| T::item(fetch, row))
+	}
+}
+
+/// Matches every entity and yields nothing, handy as the data of a query that only cares about
+/// the ids its filter selects.
+unsafe impl QueryData for () {
+	type Item<'q> = ();
+
+	type State = ();
+
+	type Fetch = ();
+
+	fn access(_access: &mut Access) {}
+
+	fn init_state(_components: &Components) -> Option<Self::State> {
+		Some(())
+	}
+
+	fn borrows(_state: &Self::State, _borrows: &mut Vec<(ComponentId, bool)>) {}
+
+	fn matches(_state: &Self::State, _archetype: &Archetype) -> bool {
+		true
+	}
+
+	fn fetch(_state: &Self::State, _archetype: &Archetype, _change_tick: Tick) -> Self::Fetch {}
+
+	unsafe fn item<'q>(_fetch: Self::Fetch, _row: usize) -> Self::Item<'q> {}
+}
+
+macro_rules! impl_query_data_tuple {
+	($($name: ident),*) => {
+		#[allow(non_snake_case)]
+		unsafe impl<$($name),*> QueryData for ($($name,)*)
+		where
+			$($name: QueryData,)*
+		{
+			type Item<'q> = ($($name::Item<'q>,)*);
+
+			type State = ($($name::State,)*);
+
+			type Fetch = ($($name::Fetch,)*);
+
+			fn access(access: &mut Access) {
+				$($name::access(access);)*
+			}
+
+			fn init_state(components: &Components) -> Option<Self::State> {
+				Some(($($name::init_state(components)?,)*))
+			}
+
+			fn borrows(state: &Self::State, borrows: &mut Vec<(ComponentId, bool)>) {
+				let ($($name,)*) = state;
+
+				$($name::borrows($name, borrows);)*
+			}
+
+			fn matches(state: &Self::State, archetype: &Archetype) -> bool {
+				let ($($name,)*) = state;
+
+				$($name::matches($name, archetype))&&*
+			}
+
+			fn fetch(state: &Self::State, archetype: &Archetype, change_tick: Tick) -> Self::Fetch {
+				let ($($name,)*) = state;
+
+				($($name::fetch($name, archetype, change_tick),)*)
+			}
+
+			unsafe fn item<'q>(fetch: Self::Fetch, row: usize) -> Self::Item<'q> {
+				let ($($name,)*) = fetch;
+
+				($($name::item($name, row),)*)
+			}
+		}
+	};
+}
+
+impl_query_data_tuple!(A);
+impl_query_data_tuple!(A, B);
+impl_query_data_tuple!(A, B, C);
+impl_query_data_tuple!(A, B, C, D);
+impl_query_data_tuple!(A, B, C, D, E);
+impl_query_data_tuple!(A, B, C, D, E, F);
+impl_query_data_tuple!(A, B, C, D, E, F, G);
+impl_query_data_tuple!(A, B, C, D, E, F, G, H);
@@ -0,0 +1,311 @@
+use std::{any::TypeId, marker::PhantomData};
+
+use crate::{
+	storage::{Archetype, ComponentId, Components},
+	tick::{ComponentTicks, Tick},
+	Component, Entity,
+};
+
+use super::Access;
+
+/// Narrows down which entities a query visits without fetching any data.
+pub trait QueryFilter {
+	#[doc(hidden)]
+	type State: Copy;
+
+	#[doc(hidden)]
+	type Fetch: Copy;
+
+	/// Whether `matches_row` has to be consulted for every row.
+	#[doc(hidden)]
+	const PER_ROW: bool;
+
+	/// Returns `None` when the filter can not match anything.
+	#[doc(hidden)]
+	fn init_state(components: &Components) -> Option<Self::State>;
+
+	/// Reports the components whose ticks the filter reads.
+	#[doc(hidden)]
+	fn access(_access: &mut Access) {}
+
+	/// Pushes the components whose ticks the filter reads, see [`QueryData`](super::QueryData).
+	#[doc(hidden)]
+	fn borrows(_state: &Self::State, _borrows: &mut Vec<(ComponentId, bool)>) {}
+
+	#[doc(hidden)]
+	fn matches_archetype(state: &Self::State, archetype: &Archetype) -> bool;
+
+	/// Only called for archetypes `matches_archetype` accepted. `since` is the tick change filters
+	/// compare against.
+	#[doc(hidden)]
+	fn fetch(state: &Self::State, archetype: &Archetype, since: Tick) -> Self::Fetch;
+
+	/// # Safety
+	///
+	/// `row` must be in bounds of the archetype `fetch` was created from, and that archetype must
+	/// not have been moved or resized since.
+	#[doc(hidden)]
+	unsafe fn matches_row(fetch: &Self::Fetch, row: usize, type_ids: &[TypeId]) -> bool;
+}
+
+/// Only entities carrying `C`.
+pub struct With<C>(PhantomData<C>);
+
+impl<C> QueryFilter for With<C>
+where
+	C: Component + 'static,
+{
+	type State = ComponentId;
+
+	type Fetch = ();
+
+	const PER_ROW: bool = false;
+
+	fn init_state(components: &Components) -> Option<Self::State> {
+		components.id::<C>()
+	}
+
+	fn matches_archetype(state: &Self::State, archetype: &Archetype) -> bool {
+		archetype.contains(*state)
+	}
+
+	fn fetch(_state: &Self::State, _archetype: &Archetype, _since: Tick) -> Self::Fetch {}
+
+	unsafe fn matches_row(_fetch: &Self::Fetch, _row: usize, _type_ids: &[TypeId]) -> bool {
+		true
+	}
+}
+
+/// Only entities not carrying `C`.
+pub struct Without<C>(PhantomData<C>);
+
+impl<C> QueryFilter for Without<C>
+where
+	C: Component + 'static,
+{
+	type State = Option<ComponentId>;
+
+	type Fetch = ();
+
+	const PER_ROW: bool = false;
+
+	fn init_state(components: &Components) -> Option<Self::State> {
+		Some(components.id::<C>())
+	}
+
+	fn matches_archetype(state: &Self::State, archetype: &Archetype) -> bool {
+		state.is_none_or(|component_id| !archetype.contains(component_id))
+	}
+
+	fn fetch(_state: &Self::State, _archetype: &Archetype, _since: Tick) -> Self::Fetch {}
+
+	unsafe fn matches_row(_fetch: &Self::Fetch, _row: usize, _type_ids: &[TypeId]) -> bool {
+		true
+	}
+}
+
+/// Only entities of type `E` or of a type extending it, the query counterpart of
+/// [`EntityMethods::is`](crate::EntityMethods::is).
+pub struct Is<E>(PhantomData<E>);
+
+impl<E> QueryFilter for Is<E>
+where
+	E: Entity + 'static,
+{
+	type State = TypeId;
+
+	type Fetch = TypeId;
+
+	const PER_ROW: bool = true;
+
+	fn init_state(_components: &Components) -> Option<Self::State> {
+		Some(TypeId::of::<E>())
+	}
+
+	fn matches_archetype(_state: &Self::State, _archetype: &Archetype) -> bool {
+		true
+	}
+
+	fn fetch(state: &Self::State, _archetype: &Archetype, _since: Tick) -> Self::Fetch {
+		*state
+	}
+
+	unsafe fn matches_row(fetch: &Self::Fetch, _row: usize, type_ids: &[TypeId]) -> bool {
+		type_ids.contains(fetch)
+	}
+}
+
+#[doc(hidden)]
+#[derive(Clone, Copy)]
+pub struct TicksFetch(*const ComponentTicks, Tick);
+
+impl TicksFetch {
+	fn new(component_id: ComponentId, archetype: &Archetype, since: Tick) -> Self {
+		Self(
+			archetype
+				.column(component_id)
+				.expect("archetype should match")
+				.ticks_ptr(),
+			since,
+		)
+	}
+
+	/// # Safety
+	///
+	/// See [`QueryFilter::matches_row`].
+	unsafe fn get(&self, row: usize) -> ComponentTicks {
+		*self.0.add(row)
+	}
+}
+
+/// Only entities `C` was added to after the tick passed to [`Query::since`](super::Query::since).
+pub struct Added<C>(PhantomData<C>);
+
+impl<C> QueryFilter for Added<C>
+where
+	C: Component + 'static,
+{
+	type State = ComponentId;
+
+	type Fetch = TicksFetch;
+
+	const PER_ROW: bool = true;
+
+	fn init_state(components: &Components) -> Option<Self::State> {
+		components.id::<C>()
+	}
+
+	fn access(access: &mut Access) {
+		access.add_read(TypeId::of::<C>(), C::NAME);
+	}
+
+	fn borrows(state: &Self::State, borrows: &mut Vec<(ComponentId, bool)>) {
+		borrows.push((*state, false));
+	}
+
+	fn matches_archetype(state: &Self::State, archetype: &Archetype) -> bool {
+		archetype.contains(*state)
+	}
+
+	fn fetch(state: &Self::State, archetype: &Archetype, since: Tick) -> Self::Fetch {
+		TicksFetch::new(*state, archetype, since)
+	}
+
+	unsafe fn matches_row(fetch: &Self::Fetch, row: usize, _type_ids: &[TypeId]) -> bool {
+		fetch.get(row).added.is_newer_than(fetch.1)
+	}
+}
+
+/// Only entities whose `C` was added or written to after the tick passed to
+/// [`Query::since`](super::Query::since).
+pub struct Changed<C>(PhantomData<C>);
+
+impl<C> QueryFilter for Changed<C>
+where
+	C: Component + 'static,
+{
+	type State = ComponentId;
+
+	type Fetch = TicksFetch;
+
+	const PER_ROW: bool = true;
+
+	fn init_state(components: &Components) -> Option<Self::State> {
+		components.id::<C>()
+	}
+
+	fn access(access: &mut Access) {
+		access.add_read(TypeId::of::<C>(), C::NAME);
+	}
+
+	fn borrows(state: &Self::State, borrows: &mut Vec<(ComponentId, bool)>) {
+		borrows.push((*state, false));
+	}
+
+	fn matches_archetype(state: &Self::State, archetype: &Archetype) -> bool {
+		archetype.contains(*state)
+	}
+
+	fn fetch(state: &Self::State, archetype: &Archetype, since: Tick) -> Self::Fetch {
+		TicksFetch::new(*state, archetype, since)
+	}
+
+	unsafe fn matches_row(fetch: &Self::Fetch, row: usize, _type_ids: &[TypeId]) -> bool {
+		fetch.get(row).changed.is_newer_than(fetch.1)
+	}
+}
+
+impl QueryFilter for () {
+	type State = ();
+
+	type Fetch = ();
+
+	const PER_ROW: bool = false;
+
+	fn init_state(_components: &Components) -> Option<Self::State> {
+		Some(())
+	}
+
+	fn matches_archetype(_state: &Self::State, _archetype: &Archetype) -> bool {
+		true
+	}
+
+	fn fetch(_state: &Self::State, _archetype: &Archetype, _since: Tick) -> Self::Fetch {}
+
+	unsafe fn matches_row(_fetch: &Self::Fetch, _row: usize, _type_ids: &[TypeId]) -> bool {
+		true
+	}
+}
+
+macro_rules! impl_query_filter_tuple {
+	($($name: ident),*) => {
+		#[allow(non_snake_case)]
+		impl<$($name),*> QueryFilter for ($($name,)*)
+		where
+			$($name: QueryFilter,)*
+		{
+			type State = ($($name::State,)*);
+
+			type Fetch = ($($name::Fetch,)*);
+
+			const PER_ROW: bool = $($name::PER_ROW)||*;
+
+			fn init_state(components: &Components) -> Option<Self::State> {
+				Some(($($name::init_state(components)?,)*))
+			}
+
+			fn access(access: &mut Access) {
+				$($name::access(access);)*
+			}
+
+			fn borrows(state: &Self::State, borrows: &mut Vec<(ComponentId, bool)>) {
+				let ($($name,)*) = state;
+
+				$($name::borrows($name, borrows);)*
+			}
+
+			fn matches_archetype(state: &Self::State, archetype: &Archetype) -> bool {
+				let ($($name,)*) = state;
+
+				$($name::matches_archetype($name, archetype))&&*
+			}
+
+			fn fetch(state: &Self::State, archetype: &Archetype, since: Tick) -> Self::Fetch {
+				let ($($name,)*) = state;
+
+				($($name::fetch($name, archetype, since),)*)
+			}
+
+			unsafe fn matches_row(fetch: &Self::Fetch, row: usize, type_ids: &[TypeId]) -> bool {
+				let ($($name,)*) = fetch;
+
+				$($name::matches_row($name, row, type_ids))&&*
+			}
+		}
+	};
+}
+
+impl_query_filter_tuple!(A);
+impl_query_filter_tuple!(A, B);
+impl_query_filter_tuple!(A, B, C);
+impl_query_filter_tuple!(A, B, C, D);
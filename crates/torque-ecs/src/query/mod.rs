@@ -0,0 +1,318 @@
+#[cfg(test)]
+mod tests;
+
+mod data;
+mod filter;
+
+use std::any::TypeId;
+
+use slotmap::SecondaryMap;
+
+use crate::{
+	cell_borrow::{BorrowKind, CellBorrow},
+	storage::{Archetype, EntityMeta},
+	storage_guard::StorageRead,
+	tick::Tick,
+	EntityId, SystemError,
+};
+
+pub use self::{
+	data::QueryData,
+	filter::{Added, Changed, Is, QueryFilter, With, Without},
+};
+
+/// The components a query reads and writes, used to reject queries whose terms would alias.
+#[derive(Debug, Default)]
+pub struct Access {
+	reads: Vec<(TypeId, &'static str)>,
+	writes: Vec<(TypeId, &'static str)>,
+}
+
+impl Access {
+	pub fn of<Q>() -> Self
+	where
+		Q: QueryData,
+	{
+		let mut access = Self::default();
+
+		Q::access(&mut access);
+
+		access
+	}
+
+	/// The components whose ticks `F` reads.
+	pub fn of_filter<F>() -> Self
+	where
+		F: QueryFilter,
+	{
+		let mut access = Self::default();
+
+		F::access(&mut access);
+
+		access
+	}
+
+	pub(crate) fn add_read(&mut self, type_id: TypeId, name: &'static str) {
+		if let Some((_, name)) = self.writes.iter().find(|(v, _)| v == &type_id) {
+			panic!("component {name} is accessed mutably and immutably by the same query");
+		}
+
+		self.reads.push((type_id, name));
+	}
+
+	pub(crate) fn add_write(&mut self, type_id: TypeId, name: &'static str) {
+		if self
+			.reads
+			.iter()
+			.chain(self.writes.iter())
+			.any(|(v, _)| v == &type_id)
+		{
+			panic!("component {name} is accessed mutably more than once by the same query");
+		}
+
+		self.writes.push((type_id, name));
+	}
+
+	/// Adds everything `other` accesses. Unlike the terms of a single query, the accesses merged
+	/// here are never held at the same time, so they may overlap.
+	pub(crate) fn extend(&mut self, other: &Access) {
+		for read in &other.reads {
+			if !self.reads.contains(read) {
+				self.reads.push(*read);
+			}
+		}
+
+		for write in &other.writes {
+			if !self.writes.contains(write) {
+				self.writes.push(*write);
+			}
+		}
+	}
+
+	/// Whether work accessing `self` may run at the same time as work accessing `other`, i.e.
+	/// neither writes a component the other accesses.
+	pub fn is_compatible(&self, other: &Access) -> bool {
+		let conflicts = |a: &Access, b: &Access| {
+			a.writes
+				.iter()
+				.any(|(w, _)| b.reads.iter().chain(b.writes.iter()).any(|(v, _)| v == w))
+		};
+
+		!conflicts(self, other) && !conflicts(other, self)
+	}
+}
+
+/// A borrow of every entity matching `Q` and `F`. The system stays locked shared and the columns
+/// of the matching entities stay borrowed until the query is dropped: other queries and threads
+/// reading the same components run alongside it, while those writing a component the query
+/// accesses wait for it. Entities can not be created, disposed or gain components meanwhile, and
+/// accessing a component the query borrows in a conflicting way from the iterating thread returns
+/// [`SystemError::Reentrant`](crate::SystemError::Reentrant).
+///
+/// Every component handed out mutably is marked changed, whether or not it is written to.
+pub struct Query<'s, Q, F = ()>
+where
+	Q: QueryData,
+	F: QueryFilter,
+{
+	// dropped in order, releasing the borrows before the storage
+	_borrows: Vec<CellBorrow<'s>>,
+	storage: StorageRead<'s>,
+	state: Option<(Q::State, F::State)>,
+	since: Tick,
+	change_tick: Tick,
+}
+
+impl<'s, Q, F> Query<'s, Q, F>
+where
+	Q: QueryData,
+	F: QueryFilter,
+{
+	/// Borrows the columns of every matching archetype, in the order of the archetypes and then of
+	/// the components, so queries running on several threads can not wait on each other in a
+	/// cycle.
+	pub(crate) fn new(storage: StorageRead<'s>) -> Result<Self, SystemError> {
+		Access::of::<Q>();
+
+		let components = storage.components();
+		let state = Q::init_state(components).zip(F::init_state(components));
+		let mut borrows = Vec::new();
+
+		if let Some((query_state, filter_state)) = &state {
+			let mut columns = Vec::new();
+
+			Q::borrows(query_state, &mut columns);
+			F::borrows(filter_state, &mut columns);
+
+			// a component both read and written is borrowed once, for writing
+			columns.sort_by_key(|&(component_id, write)| (component_id, !write));
+			columns.dedup_by_key(|(component_id, _)| *component_id);
+
+			for archetype in storage.archetypes().0 {
+				if archetype.entities().is_empty()
+					|| !Q::matches(query_state, archetype)
+					|| !F::matches_archetype(filter_state, archetype)
+				{
+					continue;
+				}
+
+				for &(component_id, write) in &columns {
+					// optional terms the archetype does not carry
+					let Some(column) = archetype.column(component_id) else {
+						continue;
+					};
+					let kind = if write {
+						BorrowKind::ColumnWrite
+					} else {
+						BorrowKind::ColumnRead
+					};
+					let borrow = column
+						.borrow(kind)
+						.ok_or_else(|| SystemError::ReentrantQuery(components.info(component_id).name))?;
+
+					// SAFETY: the borrows are dropped before the storage, which keeps the columns in
+					// place
+					borrows.push(unsafe { borrow.detach() });
+				}
+			}
+		}
+
+		let change_tick = storage.next_tick();
+
+		Ok(Self {
+			_borrows: borrows,
+			storage,
+			state,
+			since: Tick::ZERO,
+			change_tick,
+		})
+	}
+
+	/// Sets the tick [`Added`] and [`Changed`] compare against, usually a
+	/// [`System::change_tick`](crate::System) taken the last time the same work ran. Defaults to
+	/// [`Tick::ZERO`], which matches every component.
+	pub fn since(mut self, tick: Tick) -> Self {
+		self.since = tick;
+
+		self
+	}
+
+	/// The tick components handed out mutably by this query are marked changed at.
+	#[inline]
+	pub fn change_tick(&self) -> Tick {
+		self.change_tick
+	}
+
+	pub fn iter(&mut self) -> QueryIter<'_, Q, F> {
+		let (archetypes, entities) = self.storage.archetypes();
+
+		QueryIter {
+			archetypes: archetypes.iter(),
+			entities,
+			state: self.state,
+			since: self.since,
+			change_tick: self.change_tick,
+			current: None,
+		}
+	}
+}
+
+impl<'q, Q, F> IntoIterator for &'q mut Query<'_, Q, F>
+where
+	Q: QueryData,
+	F: QueryFilter,
+{
+	type IntoIter = QueryIter<'q, Q, F>;
+	type Item = (EntityId, Q::Item<'q>);
+
+	fn into_iter(self) -> Self::IntoIter {
+		self.iter()
+	}
+}
+
+pub struct QueryIter<'q, Q, F>
+where
+	Q: QueryData,
+	F: QueryFilter,
+{
+	archetypes: std::slice::Iter<'q, Archetype>,
+	entities: &'q SecondaryMap<EntityId, EntityMeta>,
+	state: Option<(Q::State, F::State)>,
+	since: Tick,
+	change_tick: Tick,
+	current: Option<Cursor<'q, Q, F>>,
+}
+
+/// Where a [`QueryIter`] is within the archetype it is currently walking.
+struct Cursor<'q, Q, F>
+where
+	Q: QueryData,
+	F: QueryFilter,
+{
+	entities: &'q [EntityId],
+	fetch: Q::Fetch,
+	filter_fetch: F::Fetch,
+	row: usize,
+}
+
+impl<'q, Q, F> Iterator for QueryIter<'q, Q, F>
+where
+	Q: QueryData,
+	F: QueryFilter,
+{
+	type Item = (EntityId, Q::Item<'q>);
+
+	fn next(&mut self) -> Option<Self::Item> {
+		let (query_state, filter_state) = self.state.as_ref()?;
+
+		loop {
+			if let Some(Cursor {
+				entities,
+				fetch,
+				filter_fetch,
+				row,
+			}) = self.current.as_mut()
+			{
+				if let Some(entity_id) = entities.get(*row).copied() {
+					let current_row = *row;
+
+					*row += 1;
+
+					// SAFETY: the row is in bounds and the archetype can not change while the query
+					// holds the storage
+					if F::PER_ROW
+						&& !unsafe {
+							F::matches_row(filter_fetch, current_row, self.entities[entity_id].type_ids)
+						} {
+						continue;
+					}
+
+					// SAFETY: every row of the archetype is visited once, its columns are borrowed by
+					// the query, and the query is borrowed mutably for 'q
+					return Some((entity_id, unsafe { Q::item(*fetch, current_row) }));
+				}
+
+				self.current = None;
+			}
+
+			let archetype = self.archetypes.next()?;
+
+			if archetype.entities().is_empty()
+				|| !Q::matches(query_state, archetype)
+				|| !F::matches_archetype(filter_state, archetype)
+			{
+				continue;
+			}
+
+			let filter_fetch = F::fetch(filter_state, archetype, self.since);
+			let fetch = Q::fetch(query_state, archetype, self.change_tick);
+
+			self.current = Some(Cursor {
+				entities: archetype.entities(),
+				fetch,
+				filter_fetch,
+				row: 0,
+			});
+		}
+	}
+}
@@ -0,0 +1,220 @@
+use test_log::test;
+
+use crate::{Added, Changed, Component, Entity, EntityMethods, Is, System, With, Without};
+
+#[derive(Entity)]
+struct Base;
+
+#[derive(Entity)]
+#[extends(Base)]
+struct Derived;
+
+#[derive(Entity)]
+struct Other;
+
+struct Position;
+
+impl Component for Position {
+	const NAME: &str = "Position";
+
+	type Value = (i32, i32);
+}
+
+struct Velocity;
+
+impl Component for Velocity {
+	const NAME: &str = "Velocity";
+
+	type Value = (i32, i32);
+}
+
+struct Hidden;
+
+impl Component for Hidden {
+	const NAME: &str = "Hidden";
+
+	type Value = ();
+}
+
+fn sorted<T>(mut values: Vec<T>) -> Vec<T>
+where
+	T: Ord,
+{
+	values.sort();
+
+	values
+}
+
+#[test]
+fn query_reads_and_writes() {
+	let system = System::default();
+
+	let moving = system.create::<Base>();
+	let fixed = system.create::<Base>();
+
+	moving.set::<Position>((0, 0)).set::<Velocity>((1, 2));
+	fixed.set::<Position>((5, 5));
+
+	for (_, (position, velocity)) in system.query::<(&mut Position, &Velocity)>().iter() {
+		position.0 += velocity.0;
+		position.1 += velocity.1;
+	}
+
+	assert_eq!(moving.get::<Position>(), (1, 2));
+	assert_eq!(fixed.get::<Position>(), (5, 5));
+}
+
+#[test]
+fn query_filters() {
+	let system = System::default();
+
+	let visible = system.create::<Base>();
+	let hidden = system.create::<Base>();
+
+	visible.set::<Position>((1, 1));
+	hidden.set::<Position>((2, 2)).set::<Hidden>(());
+
+	assert_eq!(
+		sorted(
+			system
+				.query_filtered::<&Position, Without<Hidden>>()
+				.iter()
+				.map(|(id, _)| id)
+				.collect()
+		),
+		vec![visible.id]
+	);
+	assert_eq!(
+		sorted(
+			system
+				.query_filtered::<(), With<Hidden>>()
+				.iter()
+				.map(|(id, _)| id)
+				.collect()
+		),
+		vec![hidden.id]
+	);
+	assert_eq!(
+		sorted(
+			system
+				.query::<(&Position, Option<&Hidden>)>()
+				.iter()
+				.map(|(id, (_, hidden))| (id, hidden.is_some()))
+				.collect()
+		),
+		sorted(vec![(visible.id, false), (hidden.id, true)])
+	);
+}
+
+#[test]
+fn query_filters_by_entity_type() {
+	let system = System::default();
+
+	let base = system.create::<Base>();
+	let derived = system.create::<Derived>();
+	let other = system.create::<Other>();
+
+	for entity_id in [base.id, derived.id, other.id] {
+		system.entity_set::<Position>(entity_id, (0, 0));
+	}
+
+	assert_eq!(
+		sorted(
+			system
+				.query_filtered::<&Position, Is<Base>>()
+				.iter()
+				.map(|(id, _)| id)
+				.collect()
+		),
+		sorted(vec![base.id, derived.id])
+	);
+	assert_eq!(
+		system
+			.query_filtered::<&Position, Is<Derived>>()
+			.iter()
+			.map(|(id, _)| id)
+			.collect::<Vec<_>>(),
+		vec![derived.id]
+	);
+}
+
+#[test]
+fn query_changed_since() {
+	let system = System::default();
+
+	let a = system.create::<Base>();
+	let b = system.create::<Base>();
+
+	a.set::<Position>((0, 0));
+	b.set::<Position>((0, 0));
+
+	let tick = system.change_tick();
+
+	a.with_mut::<Position, _>(|position| position.0 = 1);
+
+	let changed = |tick| {
+		sorted(
+			system
+				.query_filtered::<(), Changed<Position>>()
+				.since(tick)
+				.iter()
+				.map(|(id, _)| id)
+				.collect::<Vec<_>>(),
+		)
+	};
+
+	assert_eq!(changed(tick), vec![a.id]);
+
+	let tick = system.change_tick();
+
+	assert_eq!(changed(tick), vec![]);
+
+	for (_, position) in system.query::<&mut Position>().iter() {
+		position.1 = 1;
+	}
+
+	assert_eq!(changed(tick), sorted(vec![a.id, b.id]));
+}
+
+#[test]
+fn query_added_since() {
+	let system = System::default();
+
+	let a = system.create::<Base>();
+	let b = system.create::<Base>();
+
+	a.set::<Position>((0, 0));
+
+	let tick = system.change_tick();
+
+	a.set::<Position>((1, 1));
+	b.set::<Position>((0, 0));
+
+	assert_eq!(
+		system
+			.query_filtered::<(), Added<Position>>()
+			.since(tick)
+			.iter()
+			.map(|(id, _)| id)
+			.collect::<Vec<_>>(),
+		vec![b.id]
+	);
+}
+
+#[test]
+fn query_unknown_component_is_empty() {
+	let system = System::default();
+
+	let _entity = system.create::<Base>();
+
+	assert_eq!(system.query::<&Position>().iter().count(), 0);
+	assert_eq!(system.query::<Option<&Position>>().iter().count(), 1);
+}
+
+#[test]
+#[should_panic]
+fn query_aliasing_panics() {
+	let system = System::default();
+
+	system.query::<(&Position, &mut Position)>();
+}
@@ -0,0 +1,405 @@
+//! Entity and component types looked up by name at runtime. Types deriving [`Entity`] or
+//! [`Component`] are registered automatically, others can be added with
+//! `inventory::submit! { ComponentType::of::<C>() }`.
+
+#[cfg(test)]
+mod tests;
+
+use std::{
+	any::{type_name, TypeId},
+	collections::hash_map::Entry,
+	fmt,
+	marker::PhantomData,
+	sync::LazyLock,
+};
+
+use fnv::FnvHashMap;
+use serde::{de::DeserializeOwned, Serialize};
+
+#[cfg(feature = "v8")]
+use crate::js::ComponentV8;
+use crate::{snapshot::ComponentSerde, Component, Entity, EntityId, System, SystemError};
+
+/// Formats the value of a component of an entity.
+pub type DebugFn =
+	fn(&System, EntityId, &mut fmt::Formatter<'_>) -> Result<fmt::Result, SystemError>;
+
+/// Sets components of an entity, see [`Entity::init`].
+pub type InitFn = fn(&System, EntityId) -> Result<(), SystemError>;
+
+/// An [`Entity`] type, along with the type it extends.
+#[derive(Clone, Copy, Debug)]
+pub struct EntityType {
+	name: &'static str,
+	type_name: fn() -> &'static str,
+	type_id: fn() -> TypeId,
+	base_type_id: fn() -> TypeId,
+	type_ids: fn() -> &'static [TypeId],
+	init: InitFn,
+}
+
+impl EntityType {
+	pub const fn of<E>() -> Self
+	where
+		E: Entity + 'static,
+	{
+		Self {
+			name: E::NAME,
+			type_name: type_name::<E>,
+			type_id: E::type_id,
+			base_type_id: <E::Base as Entity>::type_id,
+			type_ids: E::type_ids,
+			init: E::init,
+		}
+	}
+
+	#[inline]
+	pub fn name(&self) -> &'static str {
+		self.name
+	}
+
+	/// The path of the type as given by [`type_name`].
+	#[inline]
+	pub fn type_name(&self) -> &'static str {
+		(self.type_name)()
+	}
+
+	#[inline]
+	pub fn type_id(&self) -> TypeId {
+		(self.type_id)()
+	}
+
+	/// The type and every type it extends, see [`Entity::type_ids`].
+	#[inline]
+	pub fn type_ids(&self) -> &'static [TypeId] {
+		(self.type_ids)()
+	}
+
+	/// The registered type this type extends directly, `None` for a root type.
+	pub fn base(&self) -> Option<&'static EntityType> {
+		entity_type_of((self.base_type_id)())
+	}
+
+	/// Sets the required components on a newly created entity, see [`Entity::init`].
+	#[inline]
+	pub fn init(&self, system: &System, entity_id: EntityId) -> Result<(), SystemError> {
+		(self.init)(system, entity_id)
+	}
+
+	/// The registered types this type extends, nearest first.
+	pub fn extends(&self) -> impl Iterator<Item = &'static EntityType> {
+		std::iter::successors(self.base(), |entity_type| entity_type.base())
+	}
+}
+
+/// A [`Component`] type, along with how to format its value if it implements [`fmt::Debug`].
+#[derive(Debug)]
+pub struct ComponentType {
+	name: &'static str,
+	type_name: fn() -> &'static str,
+	type_id: fn() -> TypeId,
+	value_type_name: fn() -> &'static str,
+	debug: fn() -> Option<DebugFn>,
+	serde: Option<ComponentSerde>,
+	default: Option<InitFn>,
+	#[cfg(feature = "v8")]
+	v8: Option<ComponentV8>,
+}
+
+impl ComponentType {
+	/// A component whose value is formatted with its [`fmt::Debug`] implementation.
+	pub const fn of<C>() -> Self
+	where
+		C: Component + 'static,
+		C::Value: fmt::Debug,
+	{
+		Self::new::<C>(|| Some(debug_value::<C>))
+	}
+
+	/// A component whose value can not be formatted.
+	pub const fn opaque<C>() -> Self
+	where
+		C: Component + 'static,
+	{
+		Self::new::<C>(|| None)
+	}
+
+	/// A component formatted by what `debug` returns, which the [`Component`] derive looks up
+	/// without knowing whether the value implements [`fmt::Debug`].
+	#[doc(hidden)]
+	pub const fn new<C>(debug: fn() -> Option<DebugFn>) -> Self
+	where
+		C: Component + 'static,
+	{
+		Self {
+			name: C::NAME,
+			type_name: type_name::<C>,
+			type_id: TypeId::of::<C>,
+			value_type_name: type_name::<C::Value>,
+			debug,
+			serde: None,
+			default: None,
+			#[cfg(feature = "v8")]
+			v8: None,
+		}
+	}
+
+	/// Includes the component in snapshots, see [`System::snapshot`](crate::System). `C` must be
+	/// the component this type was created for.
+	pub const fn serialized<C>(mut self) -> Self
+	where
+		C: Component + 'static,
+		C::Value: Serialize + DeserializeOwned,
+	{
+		self.serde = Some(ComponentSerde::of::<C>());
+
+		self
+	}
+
+	/// Allows setting the component to its default value by name, see
+	/// [`set_default`](Self::set_default). `C` must be the component this type was created for.
+	pub const fn defaulted<C>(mut self) -> Self
+	where
+		C: Component + 'static,
+		C::Value: Default,
+	{
+		self.default =
+			Some(|system, entity_id| system.try_entity_set::<C>(entity_id, C::Value::default()));
+
+		self
+	}
+
+	/// Allows reading and writing the component from JavaScript, see [`js`](crate::js). `C`
+	/// must be the component this type was created for.
+	#[cfg(feature = "v8")]
+	pub const fn scripted<C>(mut self) -> Self
+	where
+		C: Component + 'static,
+		C::Value: Clone + m8::TryIntoV8 + m8::TryFromV8,
+	{
+		self.v8 = Some(ComponentV8::of::<C>());
+
+		self
+	}
+
+	#[inline]
+	pub fn name(&self) -> &'static str {
+		self.name
+	}
+
+	/// The path of the component type as given by [`type_name`].
+	#[inline]
+	pub fn type_name(&self) -> &'static str {
+		(self.type_name)()
+	}
+
+	#[inline]
+	pub fn type_id(&self) -> TypeId {
+		(self.type_id)()
+	}
+
+	/// The name of [`Component::Value`] as given by [`type_name`].
+	#[inline]
+	pub fn value_type_name(&self) -> &'static str {
+		(self.value_type_name)()
+	}
+
+	#[inline]
+	pub fn is_serialized(&self) -> bool {
+		self.serde.is_some()
+	}
+
+	#[inline]
+	pub(crate) fn serde(&self) -> Option<ComponentSerde> {
+		self.serde
+	}
+
+	#[cfg(feature = "v8")]
+	#[inline]
+	pub fn is_scripted(&self) -> bool {
+		self.v8.is_some()
+	}
+
+	#[cfg(feature = "v8")]
+	#[inline]
+	pub(crate) fn v8(&self) -> Option<ComponentV8> {
+		self.v8
+	}
+
+	/// Sets the component on the entity to its default value. `None` if the component has no
+	/// default, see [`defaulted`](Self::defaulted).
+	pub fn set_default(
+		&self,
+		system: &System,
+		entity_id: EntityId,
+	) -> Option<Result<(), SystemError>> {
+		self.default.map(|default| default(system, entity_id))
+	}
+
+	/// The value of the component on the entity, formatted with [`fmt::Debug`]. `None` if the
+	/// value can not be formatted.
+	pub fn debug<'s>(&self, system: &'s System, entity_id: EntityId) -> Option<DebugValue<'s>> {
+		Some(DebugValue {
+			system,
+			entity_id,
+			debug: (self.debug)()?,
+		})
+	}
+}
+
+/// See [`ComponentType::debug`]. Errors accessing the value, e.g. because the entity does not
+/// carry the component, are formatted in its place.
+pub struct DebugValue<'s> {
+	system: &'s System,
+	entity_id: EntityId,
+	debug: DebugFn,
+}
+
+impl fmt::Debug for DebugValue<'_> {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match (self.debug)(self.system, self.entity_id, f) {
+			Ok(result) => result,
+			Err(error) => write!(f, "<{error}>"),
+		}
+	}
+}
+
+fn debug_value<C>(
+	system: &System,
+	entity_id: EntityId,
+	f: &mut fmt::Formatter<'_>,
+) -> Result<fmt::Result, SystemError>
+where
+	C: Component + 'static,
+	C::Value: fmt::Debug,
+{
+	system.try_entity_with::<C, _>(entity_id, |value| fmt::Debug::fmt(value, f))
+}
+
+/// Picks [`ComponentType::of`] or [`ComponentType::opaque`] by autoref specialization, for the
+/// [`Component`] derive.
+#[doc(hidden)]
+pub struct DebugProbe<C>(pub PhantomData<C>);
+
+#[doc(hidden)]
+pub trait DebugViaValue {
+	fn debug_fn(&self) -> Option<DebugFn>;
+}
+
+impl<C> DebugViaValue for &DebugProbe<C>
+where
+	C: Component + 'static,
+	C::Value: fmt::Debug,
+{
+	fn debug_fn(&self) -> Option<DebugFn> {
+		Some(debug_value::<C>)
+	}
+}
+
+#[doc(hidden)]
+pub trait DebugOpaque {
+	fn debug_fn(&self) -> Option<DebugFn>;
+}
+
+impl<C> DebugOpaque for DebugProbe<C> {
+	fn debug_fn(&self) -> Option<DebugFn> {
+		None
+	}
+}
+
+inventory::collect!(EntityType);
+inventory::collect!(ComponentType);
+
+/// Several types are registered as one name, so none of them can be looked up by it.
+#[derive(Clone, Copy, Debug, thiserror::Error)]
+#[error("types {} and {} are both registered as {:?}", .types[0], .types[1], .name)]
+pub struct AmbiguousName {
+	pub name: &'static str,
+	/// The paths of two of the types, as given by [`type_name`].
+	pub types: [&'static str; 2],
+}
+
+type ByName<T> = FnvHashMap<&'static str, Result<&'static T, AmbiguousName>>;
+
+struct Index {
+	entities: ByName<EntityType>,
+	entities_by_type_id: FnvHashMap<TypeId, &'static EntityType>,
+	components: ByName<ComponentType>,
+}
+
+static INDEX: LazyLock<Index> = LazyLock::new(|| Index {
+	entities: by_name(entity_types(), |v| (v.name, v.type_id(), v.type_name())),
+	entities_by_type_id: entity_types().map(|v| (v.type_id(), v)).collect(),
+	components: by_name(component_types(), |v| (v.name, v.type_id(), v.type_name())),
+});
+
+/// Indexes `types` by name. Snapshots and scripts resolve types by name, so a name shared by two
+/// types resolves to neither.
+fn by_name<T>(
+	types: impl Iterator<Item = &'static T>,
+	key: impl Fn(&T) -> (&'static str, TypeId, &'static str),
+) -> ByName<T> {
+	let mut index = ByName::<T>::default();
+
+	for value in types {
+		let (name, type_id, type_name) = key(value);
+
+		match index.entry(name) {
+			Entry::Vacant(entry) => {
+				entry.insert(Ok(value));
+			}
+			Entry::Occupied(mut entry) => {
+				let Ok(other) = *entry.get() else {
+					continue;
+				};
+				let (_, other_type_id, other_type_name) = key(other);
+
+				// the same type submitted twice resolves the same either way
+				if other_type_id != type_id {
+					*entry.get_mut() = Err(AmbiguousName {
+						name,
+						types: [other_type_name, type_name],
+					});
+				}
+			}
+		}
+	}
+
+	index
+}
+
+/// Every registered entity type, in no particular order.
+pub fn entity_types() -> impl Iterator<Item = &'static EntityType> {
+	inventory::iter::<EntityType>.into_iter()
+}
+
+/// Every registered component type, in no particular order.
+pub fn component_types() -> impl Iterator<Item = &'static ComponentType> {
+	inventory::iter::<ComponentType>.into_iter()
+}
+
+/// The entity type registered as `name`, `None` if there is none or several, see
+/// [`try_entity_type`].
+pub fn entity_type(name: &str) -> Option<&'static EntityType> {
+	try_entity_type(name).ok().flatten()
+}
+
+/// The entity type registered as `name`, failing if several types are.
+pub fn try_entity_type(name: &str) -> Result<Option<&'static EntityType>, AmbiguousName> {
+	INDEX.entities.get(name).copied().transpose()
+}
+
+pub fn entity_type_of(type_id: TypeId) -> Option<&'static EntityType> {
+	INDEX.entities_by_type_id.get(&type_id).copied()
+}
+
+/// The component type registered as `name`, `None` if there is none or several, see
+/// [`try_component_type`].
+pub fn component_type(name: &str) -> Option<&'static ComponentType> {
+	try_component_type(name).ok().flatten()
+}
+
+/// The component type registered as `name`, failing if several types are.
+pub fn try_component_type(name: &str) -> Result<Option<&'static ComponentType>, AmbiguousName> {
+	INDEX.components.get(name).copied().transpose()
+}
@@ -0,0 +1,178 @@
+use std::any::TypeId;
+
+use test_log::test;
+
+use crate::{Component, Entity, EntityMethods, System};
+
+use super::{component_type, entity_type, entity_type_of, try_component_type, ComponentType};
+
+#[derive(Clone, Entity)]
+struct RegistryEntity;
+
+#[derive(Clone, Entity)]
+#[extends(RegistryEntity)]
+struct RegistryEntity2;
+
+#[derive(Clone, Entity)]
+#[extends(RegistryEntity2)]
+struct RegistryEntity3;
+
+mod sibling {
+	#[derive(Clone, crate::Entity)]
+	pub struct SiblingEntity;
+}
+
+mod renamed_sibling {
+	#[derive(Clone, crate::Entity)]
+	#[entity(name = "renamed.SiblingEntity")]
+	pub struct SiblingEntity;
+}
+
+#[derive(Component, Debug)]
+struct RegistryComponent(#[allow(dead_code)] usize);
+
+#[derive(Component)]
+struct OpaqueComponent;
+
+struct ManualComponent;
+
+impl Component for ManualComponent {
+	const NAME: &str = "ManualComponent";
+
+	type Value = Vec<u8>;
+}
+
+inventory::submit! { ComponentType::of::<ManualComponent>() }
+
+inventory::submit! { ComponentType::of::<ManualComponent>() }
+
+/// Shares its name with [`ShadowingComponent2`].
+struct ShadowingComponent;
+
+impl Component for ShadowingComponent {
+	const NAME: &str = "Shadowed";
+
+	type Value = u8;
+}
+
+struct ShadowingComponent2;
+
+impl Component for ShadowingComponent2 {
+	const NAME: &str = "Shadowed";
+
+	type Value = u16;
+}
+
+inventory::submit! { ComponentType::of::<ShadowingComponent>() }
+inventory::submit! { ComponentType::of::<ShadowingComponent2>() }
+
+#[test]
+fn entity_types() {
+	let entity_type = entity_type("RegistryEntity3").unwrap();
+
+	assert_eq!(entity_type.type_id(), TypeId::of::<RegistryEntity3>());
+	assert_eq!(entity_type.type_ids(), RegistryEntity3::type_ids());
+	assert_eq!(
+		entity_type.extends().map(|v| v.name()).collect::<Vec<_>>(),
+		["RegistryEntity2", "RegistryEntity"]
+	);
+	assert!(entity_type_of(TypeId::of::<RegistryEntity>())
+		.unwrap()
+		.base()
+		.is_none());
+	assert!(super::entity_type("Missing").is_none());
+}
+
+#[test]
+fn component_debug() {
+	let system = System::default();
+	let entity = system.create::<RegistryEntity>();
+
+	entity
+		.set::<RegistryComponent>(RegistryComponent(3))
+		.set::<OpaqueComponent>(OpaqueComponent)
+		.set::<ManualComponent>(vec![1, 2]);
+
+	let debug = |name| {
+		component_type(name)
+			.unwrap()
+			.debug(&system, entity.id)
+			.map(|v| format!("{v:?}"))
+	};
+
+	assert_eq!(debug("RegistryComponent").unwrap(), "RegistryComponent(3)");
+	assert_eq!(debug("ManualComponent").unwrap(), "[1, 2]");
+	assert!(debug("OpaqueComponent").is_none());
+	assert!(debug("Parent").is_none());
+
+	entity.remove::<RegistryComponent>();
+
+	assert!(debug("RegistryComponent").unwrap().starts_with('<'));
+	assert_eq!(
+		component_type("ManualComponent").unwrap().value_type_name(),
+		"alloc::vec::Vec<u8>"
+	);
+}
+
+#[derive(Component)]
+#[component(value = Vec<u32>, name = "test.Tags", serialize, default)]
+struct Tags;
+
+#[test]
+fn component_options() {
+	let system = System::default();
+	let entity = system.create::<RegistryEntity>();
+	let component_type = component_type("test.Tags").unwrap();
+
+	assert_eq!(Tags::NAME, "test.Tags");
+	assert!(component_type.is_serialized());
+	assert!(!super::component_type("RegistryComponent")
+		.unwrap()
+		.is_serialized());
+	assert!(super::component_type("Tags").is_none());
+
+	component_type
+		.set_default(&system, entity.id)
+		.unwrap()
+		.unwrap();
+	assert_eq!(entity.get::<Tags>(), Vec::<u32>::new());
+
+	assert!(super::component_type("RegistryComponent")
+		.unwrap()
+		.set_default(&system, entity.id)
+		.is_none());
+}
+
+#[test]
+fn duplicate_names() {
+	let ambiguous = try_component_type("Shadowed").unwrap_err();
+
+	assert_eq!(ambiguous.name, "Shadowed");
+	assert!(ambiguous
+		.types
+		.iter()
+		.all(|v| v.ends_with("ShadowingComponent") || v.ends_with("ShadowingComponent2")));
+	assert!(component_type("Shadowed").is_none());
+
+	// the same type submitted twice is not ambiguous
+	assert!(component_type("ManualComponent").is_some());
+}
+
+#[test]
+fn renamed_entities() {
+	let system = System::default();
+	let renamed = system.create::<renamed_sibling::SiblingEntity>();
+
+	assert_eq!(
+		entity_type("SiblingEntity").unwrap().type_id(),
+		TypeId::of::<sibling::SiblingEntity>()
+	);
+	assert_eq!(
+		entity_type("renamed.SiblingEntity").unwrap().type_id(),
+		TypeId::of::<renamed_sibling::SiblingEntity>()
+	);
+	assert_eq!(
+		system.entity_name(renamed.id),
+		Some("renamed.SiblingEntity")
+	);
+}
@@ -0,0 +1,106 @@
+#[cfg(test)]
+mod tests;
+
+use std::{
+	fmt::{self, Debug},
+	ops::{Deref, DerefMut},
+};
+
+use crate::{cell_borrow::CellBorrow, storage_guard::StorageRead};
+
+/// Shared access to a resource, see [`System::resource`](crate::System). Holding it keeps the
+/// system locked shared, so entities can not be created, disposed or gain components on this
+/// thread until it is dropped.
+pub struct Res<'s, R> {
+	// dropped in order, releasing the borrow before the storage
+	_borrow: CellBorrow<'s>,
+	_storage: StorageRead<'s>,
+	value: *const R,
+}
+
+impl<'s, R> Res<'s, R> {
+	/// # Safety
+	///
+	/// `value` must point to the resource `borrow` borrows shared, in the storage held by
+	/// `storage`.
+	pub(crate) unsafe fn new(
+		storage: StorageRead<'s>,
+		borrow: CellBorrow<'s>,
+		value: *const R,
+	) -> Self {
+		Self {
+			_borrow: borrow,
+			_storage: storage,
+			value,
+		}
+	}
+}
+
+impl<R> Deref for Res<'_, R> {
+	type Target = R;
+
+	fn deref(&self) -> &Self::Target {
+		// SAFETY: the resource is borrowed shared for as long as self lives
+		unsafe { &*self.value }
+	}
+}
+
+impl<R> Debug for Res<'_, R>
+where
+	R: Debug,
+{
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		(**self).fmt(f)
+	}
+}
+
+/// Exclusive access to a resource, see [`System::resource_mut`](crate::System). The resource is
+/// marked changed when this is created.
+pub struct ResMut<'s, R> {
+	_borrow: CellBorrow<'s>,
+	_storage: StorageRead<'s>,
+	value: *mut R,
+}
+
+impl<'s, R> ResMut<'s, R> {
+	/// # Safety
+	///
+	/// `value` must point to the resource `borrow` borrows exclusively, in the storage held by
+	/// `storage`.
+	pub(crate) unsafe fn new(
+		storage: StorageRead<'s>,
+		borrow: CellBorrow<'s>,
+		value: *mut R,
+	) -> Self {
+		Self {
+			_borrow: borrow,
+			_storage: storage,
+			value,
+		}
+	}
+}
+
+impl<R> Deref for ResMut<'_, R> {
+	type Target = R;
+
+	fn deref(&self) -> &Self::Target {
+		// SAFETY: the resource is borrowed exclusively for as long as self lives
+		unsafe { &*self.value }
+	}
+}
+
+impl<R> DerefMut for ResMut<'_, R> {
+	fn deref_mut(&mut self) -> &mut Self::Target {
+		// SAFETY: the resource is borrowed exclusively for as long as self lives
+		unsafe { &mut *self.value }
+	}
+}
+
+impl<R> Debug for ResMut<'_, R>
+where
+	R: Debug,
+{
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		(**self).fmt(f)
+	}
+}
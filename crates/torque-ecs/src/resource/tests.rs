@@ -0,0 +1,51 @@
+use test_log::test;
+
+use crate::{System, SystemError};
+
+#[derive(Debug, Default, PartialEq)]
+struct FontSize(f32);
+
+#[test]
+fn insert_and_access() {
+	let system = System::default();
+
+	assert!(!system.contains_resource::<FontSize>());
+	assert_eq!(system.insert_resource(FontSize(12.0)), None);
+	assert_eq!(*system.resource::<FontSize>(), FontSize(12.0));
+
+	system.resource_mut::<FontSize>().0 = 14.0;
+
+	assert_eq!(system.insert_resource(FontSize(16.0)), Some(FontSize(14.0)));
+	assert_eq!(system.remove_resource::<FontSize>(), Some(FontSize(16.0)));
+	assert!(matches!(
+		system.try_resource::<FontSize>(),
+		Err(SystemError::ResourceNotFound(_))
+	));
+}
+
+#[test]
+fn reentrant_resource_access_fails() {
+	let system = System::default();
+
+	system.insert_resource(FontSize(12.0));
+
+	let font_size = system.resource::<FontSize>();
+	let again = system.resource::<FontSize>();
+
+	assert!(matches!(
+		system.try_resource_mut::<FontSize>(),
+		Err(SystemError::ReentrantResource(_))
+	));
+	assert!(matches!(
+		system.try_insert_resource(FontSize(0.0)),
+		Err(SystemError::ReentrantLock)
+	));
+	assert!(matches!(
+		system.try_remove_resource::<FontSize>(),
+		Err(SystemError::ReentrantLock)
+	));
+
+	drop((font_size, again));
+
+	system.resource_mut::<FontSize>().0 = 0.0;
+}
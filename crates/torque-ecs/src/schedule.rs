@@ -0,0 +1,246 @@
+#[cfg(test)]
+mod tests;
+
+use std::{cmp::Reverse, collections::BinaryHeap, thread};
+
+use fnv::FnvHashMap;
+
+use crate::{ScheduleError, System, Task};
+
+struct Stage {
+	name: &'static str,
+	tasks: Vec<Task>,
+	/// The lengths of the runs of `tasks` that may run in parallel, `None` until the tasks have
+	/// been ordered.
+	batches: Option<Vec<usize>>,
+}
+
+impl Stage {
+	/// Sorts the tasks so that every task comes after the ones it is ordered after, and after
+	/// every earlier registered task it conflicts with, then groups them into batches of tasks
+	/// that may run at the same time.
+	fn order(&mut self) -> Result<(), ScheduleError> {
+		let count = self.tasks.len();
+		let indices = self
+			.tasks
+			.iter()
+			.enumerate()
+			.map(|(index, task)| (task.name(), index))
+			.collect::<FnvHashMap<_, _>>();
+		let mut successors = vec![Vec::new(); count];
+		let mut predecessors = vec![0; count];
+
+		for (index, task) in self.tasks.iter().enumerate() {
+			let (before, after) = task.ordering();
+			let find = |name| {
+				indices
+					.get(name)
+					.copied()
+					.ok_or(ScheduleError::TaskNotFound(self.name, task.name(), name))
+			};
+
+			for name in before {
+				let other = find(name)?;
+
+				successors[index].push(other);
+				predecessors[other] += 1;
+			}
+
+			for name in after {
+				let other = find(name)?;
+
+				successors[other].push(index);
+				predecessors[index] += 1;
+			}
+		}
+
+		// Kahn's algorithm, preferring registration order among tasks that are ready
+		let mut ready = (0..count)
+			.filter(|&index| predecessors[index] == 0)
+			.map(Reverse)
+			.collect::<BinaryHeap<_>>();
+		let mut sorted = Vec::with_capacity(count);
+
+		while let Some(Reverse(index)) = ready.pop() {
+			sorted.push(index);
+
+			for &other in &successors[index] {
+				predecessors[other] -= 1;
+
+				if predecessors[other] == 0 {
+					ready.push(Reverse(other));
+				}
+			}
+		}
+
+		if sorted.len() < count {
+			let index = (0..count)
+				.find(|&index| predecessors[index] > 0)
+				.expect("a task should be left in the cycle");
+
+			return Err(ScheduleError::Cycle(self.name, self.tasks[index].name()));
+		}
+
+		let mut batch_of = vec![0; count];
+
+		for (position, &index) in sorted.iter().enumerate() {
+			let task = &self.tasks[index];
+
+			batch_of[index] = sorted[..position]
+				.iter()
+				.filter(|&&other| {
+					successors[other].contains(&index)
+						|| !self.tasks[other]
+							.access_of()
+							.is_compatible(task.access_of())
+				})
+				.map(|&other| batch_of[other] + 1)
+				.max()
+				.unwrap_or(0);
+		}
+
+		// a stable sort keeps every batch in topological order
+		sorted.sort_by_key(|&index| batch_of[index]);
+
+		let mut tasks = self.tasks.drain(..).map(Some).collect::<Vec<_>>();
+		let mut batches = Vec::new();
+
+		for index in sorted {
+			let batch = batch_of[index];
+
+			if batches.len() == batch {
+				batches.push(0);
+			}
+
+			batches[batch] += 1;
+			self
+				.tasks
+				.push(tasks[index].take().expect("task should be taken once"));
+		}
+
+		self.batches = Some(batches);
+
+		Ok(())
+	}
+
+	fn run(&mut self, system: &System, parallel: bool) -> Result<(), ScheduleError> {
+		if self.batches.is_none() {
+			self.order()?;
+		}
+
+		let batches = self.batches.as_ref().expect("tasks should be ordered");
+		let mut rest = &mut self.tasks[..];
+
+		for &len in batches {
+			let (batch, tail) = rest.split_at_mut(len);
+
+			rest = tail;
+
+			if parallel && len > 1 {
+				thread::scope(|scope| {
+					for task in batch {
+						scope.spawn(|| task.run(system));
+					}
+				});
+			} else {
+				for task in batch {
+					task.run(system);
+				}
+			}
+		}
+
+		Ok(())
+	}
+}
+
+/// Named stages of [`Task`]s, run one after the other against a [`System`]. Tasks within a stage
+/// run in the order their [`before`](Task::before) and [`after`](Task::after) constraints ask
+/// for, and otherwise in the order they were added if they access the same components.
+///
+/// With [`set_parallel`](Self::set_parallel), tasks of a stage that are not ordered against each
+/// other and do not conflict run on scoped threads. Queries only borrow the columns they access,
+/// so the queries of such tasks run at the same time.
+#[derive(Default)]
+pub struct Schedule {
+	stages: Vec<Stage>,
+	parallel: bool,
+}
+
+impl Schedule {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Adds a stage running after every stage added before it.
+	pub fn add_stage(&mut self, name: &'static str) -> &mut Self {
+		self.try_add_stage(name).unwrap()
+	}
+
+	pub fn try_add_stage(&mut self, name: &'static str) -> Result<&mut Self, ScheduleError> {
+		if self.stages.iter().any(|stage| stage.name == name) {
+			return Err(ScheduleError::DuplicateStage(name));
+		}
+
+		self.stages.push(Stage {
+			name,
+			tasks: Vec::new(),
+			batches: None,
+		});
+
+		Ok(self)
+	}
+
+	pub fn add_task(&mut self, stage: &'static str, task: Task) -> &mut Self {
+		self.try_add_task(stage, task).unwrap()
+	}
+
+	pub fn try_add_task(
+		&mut self,
+		stage: &'static str,
+		task: Task,
+	) -> Result<&mut Self, ScheduleError> {
+		let stage = self
+			.stages
+			.iter_mut()
+			.find(|v| v.name == stage)
+			.ok_or(ScheduleError::StageNotFound(stage))?;
+
+		if stage.tasks.iter().any(|v| v.name() == task.name()) {
+			return Err(ScheduleError::DuplicateTask(stage.name, task.name()));
+		}
+
+		stage.tasks.push(task);
+		stage.batches = None;
+
+		Ok(self)
+	}
+
+	#[inline]
+	pub fn is_parallel(&self) -> bool {
+		self.parallel
+	}
+
+	pub fn set_parallel(&mut self, parallel: bool) -> &mut Self {
+		self.parallel = parallel;
+
+		self
+	}
+
+	/// Updates the [`Events`](crate::Events) of the system, then runs every stage, applying the
+	/// [`Commands`](crate::Commands) deferred by its tasks after each. Tasks are ordered the first
+	/// time a stage runs after tasks were added to it, so ordering errors surface here.
+	pub fn run(&mut self, system: &System) {
+		self.try_run(system).unwrap()
+	}
+
+	pub fn try_run(&mut self, system: &System) -> Result<(), ScheduleError> {
+		system.try_update_events()?;
+
+		for stage in &mut self.stages {
+			stage.run(system, self.parallel)?;
+			system.apply_deferred()?;
+		}
+
+		Ok(())
+	}
+}
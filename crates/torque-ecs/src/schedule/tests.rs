@@ -0,0 +1,188 @@
+use std::sync::{Arc, Barrier, Mutex};
+
+use test_log::test;
+
+use crate::{Changed, Component, Entity, EntityMethods, ScheduleError, System, Task};
+
+use super::Schedule;
+
+#[derive(Clone, Entity)]
+struct ScheduleEntity;
+
+struct Position;
+
+impl Component for Position {
+	const NAME: &str = "Position";
+
+	type Value = f32;
+}
+
+struct Velocity;
+
+impl Component for Velocity {
+	const NAME: &str = "Velocity";
+
+	type Value = f32;
+}
+
+fn record(log: &Arc<Mutex<Vec<&'static str>>>, name: &'static str) -> Task {
+	let log = log.clone();
+
+	Task::new(name, move |_| log.lock().unwrap().push(name))
+}
+
+#[test]
+fn stages_and_ordering() {
+	let system = System::default();
+	let log = Arc::new(Mutex::new(Vec::new()));
+	let mut schedule = Schedule::new();
+
+	schedule
+		.add_stage("Layout")
+		.add_stage("Paint")
+		.add_task("Paint", record(&log, "paint"))
+		.add_task("Layout", record(&log, "layout").after("measure"))
+		.add_task("Layout", record(&log, "measure"))
+		.add_task("Layout", record(&log, "style").before("measure"));
+
+	schedule.run(&system);
+
+	assert_eq!(
+		*log.lock().unwrap(),
+		["style", "measure", "layout", "paint"]
+	);
+}
+
+#[test]
+fn query_task() {
+	let system = System::default();
+	let entity = system.create::<ScheduleEntity>();
+	let mut schedule = Schedule::new();
+
+	entity.set::<Position>(0.0);
+	entity.set::<Velocity>(2.0);
+
+	schedule.add_stage("Update").add_task(
+		"Update",
+		Task::query::<(&mut Position, &Velocity), ()>("integrate", |mut query| {
+			for (_, (position, velocity)) in &mut query {
+				*position += velocity;
+			}
+		}),
+	);
+
+	schedule.run(&system);
+	schedule.run(&system);
+
+	assert_eq!(entity.with::<Position, _>(|v| *v), 4.0);
+}
+
+#[test]
+fn ordering_errors() {
+	let mut schedule = Schedule::new();
+
+	schedule.add_stage("Layout");
+
+	assert!(matches!(
+		schedule.try_add_stage("Layout"),
+		Err(ScheduleError::DuplicateStage("Layout"))
+	));
+	assert!(matches!(
+		schedule.try_add_task("Paint", Task::new("paint", |_| {})),
+		Err(ScheduleError::StageNotFound("Paint"))
+	));
+
+	schedule.add_task("Layout", Task::new("a", |_| {}).after("b"));
+
+	assert!(matches!(
+		schedule.try_run(&System::default()),
+		Err(ScheduleError::TaskNotFound("Layout", "a", "b"))
+	));
+
+	schedule.add_task("Layout", Task::new("b", |_| {}).after("a"));
+
+	assert!(matches!(
+		schedule.try_run(&System::default()),
+		Err(ScheduleError::Cycle("Layout", _))
+	));
+}
+
+#[test]
+fn parallel_batches() {
+	let system = System::default();
+	let log = Arc::new(Mutex::new(Vec::new()));
+	// only released if both readers run at the same time
+	let barrier = Arc::new(Barrier::new(2));
+	let mut schedule = Schedule::new();
+
+	let reader = |name| {
+		let barrier = barrier.clone();
+
+		Task::new(name, move |_| {
+			barrier.wait();
+		})
+		.access::<&Position>()
+	};
+
+	schedule
+		.set_parallel(true)
+		.add_stage("Update")
+		.add_task("Update", reader("read_a"))
+		.add_task("Update", reader("read_b"))
+		.add_task("Update", record(&log, "write").access::<&mut Position>())
+		.add_task("Update", record(&log, "velocity").access::<&mut Velocity>());
+
+	schedule.run(&system);
+
+	assert_eq!(*log.lock().unwrap(), ["velocity", "write"]);
+
+	let stage = &schedule.stages[0];
+	let names = stage.tasks.iter().map(|v| v.name()).collect::<Vec<_>>();
+
+	assert_eq!(names, ["read_a", "read_b", "velocity", "write"]);
+	assert_eq!(stage.batches.as_deref(), Some(&[3, 1][..]));
+}
+
+#[test]
+fn parallel_queries() {
+	let system = System::default();
+	let entity = system.create::<ScheduleEntity>();
+	// only released if every query is held at the same time
+	let barrier = Arc::new(Barrier::new(3));
+	let mut schedule = Schedule::new();
+
+	entity.set::<Position>(1.0).set::<Velocity>(2.0);
+
+	let reader = |name| {
+		let barrier = barrier.clone();
+
+		Task::query::<&Position, ()>(name, move |mut query| {
+			assert_eq!(query.iter().count(), 1);
+
+			barrier.wait();
+		})
+	};
+	let writer = {
+		let barrier = barrier.clone();
+
+		Task::query::<&mut Velocity, Changed<Position>>("write", move |mut query| {
+			for (_, velocity) in query.iter() {
+				*velocity += 1.0;
+			}
+
+			barrier.wait();
+		})
+	};
+
+	schedule
+		.set_parallel(true)
+		.add_stage("Update")
+		.add_task("Update", reader("read_a"))
+		.add_task("Update", reader("read_b"))
+		.add_task("Update", writer);
+
+	schedule.run(&system);
+
+	assert_eq!(entity.get::<Velocity>(), 3.0);
+	assert_eq!(schedule.stages[0].batches.as_deref(), Some(&[3][..]));
+}
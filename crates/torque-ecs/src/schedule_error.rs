@@ -0,0 +1,17 @@
+use crate::SystemError;
+
+#[derive(Debug, thiserror::Error)]
+pub enum ScheduleError {
+	#[error("stage {0} not found")]
+	StageNotFound(&'static str),
+	#[error("stage {0} already exists")]
+	DuplicateStage(&'static str),
+	#[error("task {1} already exists in stage {0}")]
+	DuplicateTask(&'static str, &'static str),
+	#[error("task {1} ordered against {2}, which is not in stage {0}")]
+	TaskNotFound(&'static str, &'static str, &'static str),
+	#[error("tasks of stage {0} are ordered in a cycle involving {1}")]
+	Cycle(&'static str, &'static str),
+	#[error(transparent)]
+	System(#[from] SystemError),
+}
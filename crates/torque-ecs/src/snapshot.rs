@@ -0,0 +1,510 @@
+#[cfg(test)]
+mod tests;
+
+use std::{fmt, sync::Arc};
+
+use fnv::FnvHashMap;
+use serde::{
+	de::{self, DeserializeOwned, DeserializeSeed, IgnoredAny, MapAccess, SeqAccess, Visitor},
+	ser::{self, SerializeMap, SerializeSeq, SerializeStruct},
+	Deserializer, Serialize, Serializer,
+};
+
+use crate::{
+	registry::{self, AmbiguousName, EntityType},
+	system::Inner,
+	Component, Entity, EntityId, EntityRef, System, SystemError, Traversal,
+};
+
+type SerializeFn =
+	fn(&System, EntityId, &mut dyn FnMut(&dyn erased_serde::Serialize)) -> Result<(), SystemError>;
+type DeserializeFn =
+	fn(&System, EntityId, &mut dyn erased_serde::Deserializer) -> Result<(), erased_serde::Error>;
+
+/// How to read and write the value of one component, by [`Component::NAME`].
+#[derive(Clone, Copy)]
+pub(crate) struct ComponentSerde {
+	name: &'static str,
+	has: fn(&System, EntityId) -> bool,
+	serialize: SerializeFn,
+	deserialize: DeserializeFn,
+}
+
+impl ComponentSerde {
+	pub(crate) const fn of<C>() -> Self
+	where
+		C: Component + 'static,
+		C::Value: Serialize + DeserializeOwned,
+	{
+		Self {
+			name: C::NAME,
+			has: |system, entity_id| system.try_entity_has::<C>(entity_id).unwrap_or(false),
+			serialize: |system, entity_id, f| system.try_entity_with::<C, _>(entity_id, |value| f(value)),
+			deserialize: |system, entity_id, deserializer| {
+				let value = erased_serde::deserialize::<C::Value>(deserializer)?;
+
+				system
+					.try_entity_set::<C>(entity_id, value)
+					.map_err(de::Error::custom)
+			},
+		}
+	}
+}
+
+impl fmt::Debug for ComponentSerde {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("ComponentSerde")
+			.field("name", &self.name)
+			.finish_non_exhaustive()
+	}
+}
+
+/// The entity types and components a system can snapshot and restore.
+#[derive(Clone, Default)]
+pub(crate) struct SerdeRegistry {
+	entities: FnvHashMap<&'static str, EntityType>,
+	components: Vec<ComponentSerde>,
+}
+
+impl SerdeRegistry {
+	/// The component registered on the system as `name`, or else in the [`registry`] with
+	/// `#[component(serialize)]`.
+	fn component(&self, name: &str) -> Result<Option<ComponentSerde>, AmbiguousName> {
+		let component = self
+			.components
+			.iter()
+			.find(|component| component.name == name);
+
+		match component {
+			Some(component) => Ok(Some(*component)),
+			None => Ok(registry::try_component_type(name)?.and_then(|v| v.serde())),
+		}
+	}
+
+	/// Every component [`component`](Self::component) can find.
+	fn all_components(&self) -> Vec<ComponentSerde> {
+		let mut components = self.components.clone();
+
+		for component in registry::component_types().filter_map(|v| v.serde()) {
+			if !components.iter().any(|v| v.name == component.name) {
+				components.push(component);
+			}
+		}
+
+		components
+	}
+}
+
+impl Inner {
+	/// Allows entities of type `E` to be restored, see [`restore`](Self::restore). Only needed for
+	/// types not registered in the [`registry`](crate::registry), i.e. not deriving [`Entity`].
+	pub fn register_entity<E>(self: &Arc<Self>) -> &Arc<Self>
+	where
+		E: Entity + 'static,
+	{
+		self
+			.serde
+			.write()
+			.entities
+			.insert(E::NAME, EntityType::of::<E>());
+
+		self
+	}
+
+	/// Includes `C` in snapshots, keyed by [`Component::NAME`]. Registering the same name twice
+	/// replaces the earlier registration. Only needed for components not deriving [`Component`]
+	/// with `#[component(serialize)]`.
+	pub fn register_serde<C>(self: &Arc<Self>) -> &Arc<Self>
+	where
+		C: Component + 'static,
+		C::Value: Serialize + DeserializeOwned,
+	{
+		let mut registry = self.serde.write();
+		let component = ComponentSerde::of::<C>();
+
+		match registry.components.iter_mut().find(|v| v.name == C::NAME) {
+			Some(v) => *v = component,
+			None => registry.components.push(component),
+		}
+
+		self
+	}
+
+	pub fn snapshot(self: &Arc<Self>) -> Snapshot {
+		self.try_snapshot().unwrap()
+	}
+
+	/// Every entity with its type, its children and the components registered with
+	/// [`register_serde`](Self::register_serde) or `#[component(serialize)]`, ready to be written by any serde format. Every
+	/// root is followed by its descendants, depth first.
+	///
+	/// Entities and components are read one by one as the snapshot is written, so changes made
+	/// meanwhile by other threads may or may not be seen.
+	pub fn try_snapshot(self: &Arc<Self>) -> Result<Snapshot, SystemError> {
+		let entity_ids = self.try_entity_ids()?;
+		let mut order = Vec::with_capacity(entity_ids.len());
+
+		for entity_id in entity_ids {
+			if self.contains(entity_id) && self.parent_of(entity_id).is_none() {
+				order.push(entity_id);
+				order.extend(self.descendants(entity_id, Traversal::DepthFirst));
+			}
+		}
+
+		let indices = order
+			.iter()
+			.enumerate()
+			.map(|(index, entity_id)| (*entity_id, index))
+			.collect();
+
+		Ok(Snapshot {
+			system: System(self.clone()),
+			components: self.serde.read().all_components(),
+			entity_ids: order,
+			indices,
+		})
+	}
+
+	/// Creates the entities of a snapshot, with their components and hierarchy, returning them in
+	/// snapshot order. Parents keep their children alive, everything else is disposed once the
+	/// returned references are dropped.
+	pub fn restore<'de, D>(self: &Arc<Self>, deserializer: D) -> Result<Vec<EntityRef<()>>, D::Error>
+	where
+		D: Deserializer<'de>,
+	{
+		SnapshotSeed {
+			system: System(self.clone()),
+			registry: self.serde.read().clone(),
+		}
+		.deserialize(deserializer)
+	}
+
+	pub fn restore_json(self: &Arc<Self>, json: &str) -> serde_json::Result<Vec<EntityRef<()>>> {
+		self.restore(&mut serde_json::Deserializer::from_str(json))
+	}
+
+	pub fn restore_binary(self: &Arc<Self>, bytes: &[u8]) -> bincode::Result<Vec<EntityRef<()>>> {
+		bincode::Options::deserialize_seed(
+			bincode::DefaultOptions::new(),
+			SnapshotSeed {
+				system: System(self.clone()),
+				registry: self.serde.read().clone(),
+			},
+			bytes,
+		)
+	}
+}
+
+/// The state of a system as returned by [`System::snapshot`](crate::System).
+pub struct Snapshot {
+	system: System,
+	components: Vec<ComponentSerde>,
+	entity_ids: Vec<EntityId>,
+	indices: FnvHashMap<EntityId, usize>,
+}
+
+impl Snapshot {
+	/// Pretty-printed JSON, for humans and golden files.
+	pub fn to_json(&self) -> serde_json::Result<String> {
+		serde_json::to_string_pretty(self)
+	}
+
+	/// A compact binary encoding, read back with [`System::restore_binary`](crate::System).
+	pub fn to_binary(&self) -> bincode::Result<Vec<u8>> {
+		bincode::Options::serialize(bincode::DefaultOptions::new(), self)
+	}
+}
+
+impl Serialize for Snapshot {
+	fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+	where
+		S: Serializer,
+	{
+		let mut seq = serializer.serialize_seq(Some(self.entity_ids.len()))?;
+
+		for entity_id in &self.entity_ids {
+			seq.serialize_element(&EntityRecord {
+				snapshot: self,
+				entity_id: *entity_id,
+			})?;
+		}
+
+		seq.end()
+	}
+}
+
+struct EntityRecord<'s> {
+	snapshot: &'s Snapshot,
+	entity_id: EntityId,
+}
+
+impl Serialize for EntityRecord<'_> {
+	fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+	where
+		S: Serializer,
+	{
+		let system = &self.snapshot.system;
+		let name = system
+			.entity_name(self.entity_id)
+			.ok_or_else(|| ser::Error::custom(SystemError::EntityNotFound(self.entity_id)))?;
+		let children = system
+			.children_of(self.entity_id)
+			.iter()
+			.filter_map(|child_id| self.snapshot.indices.get(child_id))
+			.collect::<Vec<_>>();
+
+		let mut record = serializer.serialize_struct("Entity", 3)?;
+
+		record.serialize_field("entity", name)?;
+		record.serialize_field("children", &children)?;
+		record.serialize_field("components", &ComponentsRecord(self))?;
+		record.end()
+	}
+}
+
+struct ComponentsRecord<'r, 's>(&'r EntityRecord<'s>);
+
+impl Serialize for ComponentsRecord<'_, '_> {
+	fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+	where
+		S: Serializer,
+	{
+		let EntityRecord {
+			snapshot,
+			entity_id,
+		} = self.0;
+		let system = &snapshot.system;
+		let components = snapshot
+			.components
+			.iter()
+			.filter(|component| (component.has)(system, *entity_id))
+			.collect::<Vec<_>>();
+
+		let mut map = serializer.serialize_map(Some(components.len()))?;
+
+		for component in components {
+			let mut result = Ok(());
+
+			(component.serialize)(system, *entity_id, &mut |value| {
+				result = map.serialize_entry(component.name, value);
+			})
+			.map_err(ser::Error::custom)?;
+
+			result?;
+		}
+
+		map.end()
+	}
+}
+
+struct SnapshotSeed {
+	system: System,
+	registry: SerdeRegistry,
+}
+
+impl<'de> DeserializeSeed<'de> for SnapshotSeed {
+	type Value = Vec<EntityRef<()>>;
+
+	fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+	where
+		D: Deserializer<'de>,
+	{
+		deserializer.deserialize_seq(self)
+	}
+}
+
+impl<'de> Visitor<'de> for SnapshotSeed {
+	type Value = Vec<EntityRef<()>>;
+
+	fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+		formatter.write_str("a sequence of entities")
+	}
+
+	fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+	where
+		A: SeqAccess<'de>,
+	{
+		let mut entities = Vec::new();
+		let mut children = Vec::new();
+
+		while let Some((entity, entity_children)) = seq.next_element_seed(RecordSeed(&self))? {
+			entities.push(entity);
+			children.push(entity_children);
+		}
+
+		for (parent, children) in entities.iter().zip(children) {
+			for index in children {
+				let child = entities.get(index).ok_or_else(|| {
+					de::Error::invalid_value(de::Unexpected::Unsigned(index as u64), &"an entity index")
+				})?;
+
+				self
+					.system
+					.try_push_child(parent.id, child.id)
+					.map_err(de::Error::custom)?;
+			}
+		}
+
+		Ok(entities)
+	}
+}
+
+const FIELDS: &[&str] = &["entity", "children", "components"];
+
+struct RecordSeed<'s>(&'s SnapshotSeed);
+
+impl RecordSeed<'_> {
+	fn create<E>(&self, name: &str) -> Result<EntityRef<()>, E>
+	where
+		E: de::Error,
+	{
+		let entity_type = self
+			.0
+			.registry
+			.entities
+			.get(name)
+			.map(Ok)
+			.or_else(|| registry::try_entity_type(name).transpose())
+			.ok_or_else(|| E::custom(format!("entity type {name} is not registered")))?
+			.map_err(E::custom)?;
+		let system = &self.0.system;
+		let entity = system
+			.try_create_any(entity_type.name(), entity_type.type_ids())
+			.map_err(E::custom)?;
+
+		entity_type.init(system, entity.id).map_err(E::custom)?;
+
+		Ok(entity)
+	}
+}
+
+impl<'de> DeserializeSeed<'de> for RecordSeed<'_> {
+	type Value = (EntityRef<()>, Vec<usize>);
+
+	fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+	where
+		D: Deserializer<'de>,
+	{
+		deserializer.deserialize_struct("Entity", FIELDS, self)
+	}
+}
+
+impl<'de> Visitor<'de> for RecordSeed<'_> {
+	type Value = (EntityRef<()>, Vec<usize>);
+
+	fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+		formatter.write_str("an entity")
+	}
+
+	fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+	where
+		A: SeqAccess<'de>,
+	{
+		let name = seq
+			.next_element::<String>()?
+			.ok_or_else(|| de::Error::invalid_length(0, &self))?;
+		let entity = self.create(&name)?;
+		let children = seq
+			.next_element()?
+			.ok_or_else(|| de::Error::invalid_length(1, &self))?;
+
+		seq
+			.next_element_seed(ComponentsSeed(self.0, entity.id))?
+			.ok_or_else(|| de::Error::invalid_length(2, &self))?;
+
+		Ok((entity, children))
+	}
+
+	fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+	where
+		A: MapAccess<'de>,
+	{
+		let mut entity = None;
+		let mut children = None;
+
+		while let Some(key) = map.next_key::<String>()? {
+			match key.as_str() {
+				"entity" => entity = Some(self.create(&map.next_value::<String>()?)?),
+				"children" => children = Some(map.next_value()?),
+				"components" => {
+					let entity = entity
+						.as_ref()
+						.ok_or_else(|| de::Error::custom("the entity type must come before its components"))?;
+
+					map.next_value_seed(ComponentsSeed(self.0, entity.id))?;
+				}
+				_ => {
+					map.next_value::<IgnoredAny>()?;
+				}
+			}
+		}
+
+		let entity = entity.ok_or_else(|| de::Error::missing_field("entity"))?;
+
+		Ok((entity, children.unwrap_or_default()))
+	}
+}
+
+struct ComponentsSeed<'s>(&'s SnapshotSeed, EntityId);
+
+impl<'de> DeserializeSeed<'de> for ComponentsSeed<'_> {
+	type Value = ();
+
+	fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+	where
+		D: Deserializer<'de>,
+	{
+		deserializer.deserialize_map(self)
+	}
+}
+
+impl<'de> Visitor<'de> for ComponentsSeed<'_> {
+	type Value = ();
+
+	fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+		formatter.write_str("a map of components by name")
+	}
+
+	fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+	where
+		A: MapAccess<'de>,
+	{
+		let ComponentsSeed(seed, entity_id) = self;
+
+		while let Some(name) = map.next_key::<String>()? {
+			let component = seed
+				.registry
+				.component(&name)
+				.map_err(de::Error::custom)?
+				.ok_or_else(|| de::Error::custom(format!("component {name} is not registered")))?;
+
+			map.next_value_seed(ComponentSeed {
+				system: &seed.system,
+				component,
+				entity_id,
+			})?;
+		}
+
+		Ok(())
+	}
+}
+
+struct ComponentSeed<'s> {
+	system: &'s System,
+	component: ComponentSerde,
+	entity_id: EntityId,
+}
+
+impl<'de> DeserializeSeed<'de> for ComponentSeed<'_> {
+	type Value = ();
+
+	fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+	where
+		D: Deserializer<'de>,
+	{
+		let mut deserializer = <dyn erased_serde::Deserializer>::erase(deserializer);
+
+		(self.component.deserialize)(self.system, self.entity_id, &mut deserializer)
+			.map_err(de::Error::custom)
+	}
+}
@@ -0,0 +1,162 @@
+use test_log::test;
+
+use crate::{Component, Entity, EntityMethods, EntityRef, System};
+
+#[derive(Clone, Entity)]
+struct SnapshotEntity;
+
+#[derive(Clone, Entity)]
+#[extends(SnapshotEntity)]
+struct SnapshotEntity2;
+
+struct Name;
+
+impl Component for Name {
+	const NAME: &str = "Name";
+
+	type Value = String;
+}
+
+struct Position;
+
+impl Component for Position {
+	const NAME: &str = "Position";
+
+	type Value = (i32, i32);
+}
+
+struct Unregistered;
+
+impl Component for Unregistered {
+	const NAME: &str = "Unregistered";
+
+	type Value = usize;
+}
+
+fn registered() -> System {
+	let system = System::default();
+
+	system
+		.register_entity::<SnapshotEntity>()
+		.register_entity::<SnapshotEntity2>()
+		.register_serde::<Name>()
+		.register_serde::<Position>();
+
+	system
+}
+
+/// root
+/// ├─ a
+/// └─ b
+fn scene(system: &System) -> EntityRef<SnapshotEntity> {
+	let root = system.create::<SnapshotEntity>();
+	let a = system.create::<SnapshotEntity2>();
+	let b = system.create::<SnapshotEntity>();
+
+	root.set::<Name>("root".into());
+	a.set::<Name>("a".into());
+	a.set::<Position>((1, -2));
+	a.set::<Unregistered>(7);
+	b.set::<Position>((3, 4));
+
+	system.push_child(root.id, a.id);
+	system.push_child(root.id, b.id);
+
+	root
+}
+
+fn assert_scene(system: &System, entities: &[EntityRef<()>]) {
+	let [root, a, b] = entities else {
+		panic!("expected 3 entities, got {}", entities.len());
+	};
+
+	assert_eq!(system.children_of(root.id), [a.id, b.id]);
+	assert_eq!(root.with::<Name, _>(String::clone), "root");
+	assert!(!root.has::<Position>());
+	assert!(system.get::<SnapshotEntity2>(a.id).is_some());
+	assert_eq!(a.with::<Name, _>(String::clone), "a");
+	assert_eq!(a.with::<Position, _>(|v| *v), (1, -2));
+	assert!(!a.has::<Unregistered>());
+	assert!(!b.has::<Name>());
+	assert_eq!(b.with::<Position, _>(|v| *v), (3, 4));
+}
+
+#[test]
+fn json_round_trip() {
+	let system = registered();
+	let _root = scene(&system);
+	let json = system.snapshot().to_json().unwrap();
+
+	let restored = registered();
+	let entities = restored.restore_json(&json).unwrap();
+
+	assert_scene(&restored, &entities);
+	assert_eq!(restored.snapshot().to_json().unwrap(), json);
+}
+
+#[test]
+fn binary_round_trip() {
+	let system = registered();
+	let _root = scene(&system);
+	let bytes = system.snapshot().to_binary().unwrap();
+
+	let restored = registered();
+	let entities = restored.restore_binary(&bytes).unwrap();
+
+	assert_scene(&restored, &entities);
+}
+
+#[test]
+fn children_outlive_the_restored_references() {
+	let system = registered();
+	let _root = scene(&system);
+	let json = system.snapshot().to_json().unwrap();
+
+	let restored = registered();
+	let root = restored.restore_json(&json).unwrap().swap_remove(0);
+
+	assert_eq!(restored.descendants(root.id, Default::default()).count(), 2);
+}
+
+#[test]
+fn unregistered_names_fail() {
+	let system = registered();
+
+	let error = system
+		.restore_json(r#"[{ "entity": "Missing", "children": [], "components": {} }]"#)
+		.err()
+		.unwrap();
+	assert!(error.to_string().contains("Missing"));
+
+	let error = system
+		.restore_json(
+			r#"[{ "entity": "SnapshotEntity", "children": [], "components": { "Unregistered": 1 } }]"#,
+		)
+		.err()
+		.unwrap();
+	assert!(error.to_string().contains("Unregistered"));
+
+	let error = system
+		.restore_json(r#"[{ "entity": "SnapshotEntity", "children": [4], "components": {} }]"#)
+		.err()
+		.unwrap();
+	assert!(error.to_string().contains("entity index"));
+}
+
+#[derive(Component)]
+#[component(value = Vec<String>, name = "snapshot.Labels", serialize)]
+struct Labels;
+
+#[test]
+fn derived_components_need_no_registration() {
+	let system = System::default();
+	let entity = system.create::<SnapshotEntity>();
+
+	entity.set::<Labels>(vec!["a".into(), "b".into()]);
+
+	let json = system.snapshot().to_json().unwrap();
+	let entities = System::default().restore_json(&json).unwrap();
+
+	assert!(json.contains("snapshot.Labels"));
+	assert_eq!(entities[0].get::<Labels>(), ["a", "b"]);
+}
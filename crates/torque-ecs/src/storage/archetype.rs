@@ -0,0 +1,141 @@
+use fnv::FnvHashMap;
+
+use crate::EntityId;
+
+use super::{Column, ComponentId};
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct ArchetypeId(usize);
+
+impl ArchetypeId {
+	pub const EMPTY: Self = Self(0);
+
+	#[inline]
+	pub fn new(index: usize) -> Self {
+		Self(index)
+	}
+
+	#[inline]
+	pub fn index(self) -> usize {
+		self.0
+	}
+}
+
+/// A set of entities sharing exactly the same components, each component stored in its own
+/// contiguous column. Row `n` of every column belongs to `entities[n]`.
+pub struct Archetype {
+	component_ids: Box<[ComponentId]>,
+	columns: Box<[Column]>,
+	entities: Vec<EntityId>,
+	add_edges: FnvHashMap<ComponentId, ArchetypeId>,
+	remove_edges: FnvHashMap<ComponentId, ArchetypeId>,
+}
+
+impl Archetype {
+	/// `component_ids` must be sorted and match `columns` one to one.
+	pub fn new(component_ids: Box<[ComponentId]>, columns: Box<[Column]>) -> Self {
+		debug_assert!(component_ids.windows(2).all(|v| v[0] < v[1]));
+		debug_assert_eq!(component_ids.len(), columns.len());
+
+		Self {
+			component_ids,
+			columns,
+			entities: Vec::new(),
+			add_edges: Default::default(),
+			remove_edges: Default::default(),
+		}
+	}
+
+	#[inline]
+	pub fn component_ids(&self) -> &[ComponentId] {
+		&self.component_ids
+	}
+
+	#[inline]
+	pub fn entities(&self) -> &[EntityId] {
+		&self.entities
+	}
+
+	#[inline]
+	pub fn contains(&self, component_id: ComponentId) -> bool {
+		self.column_index(component_id).is_some()
+	}
+
+	#[inline]
+	pub fn column_index(&self, component_id: ComponentId) -> Option<usize> {
+		self.component_ids.binary_search(&component_id).ok()
+	}
+
+	#[inline]
+	pub fn column(&self, component_id: ComponentId) -> Option<&Column> {
+		self
+			.column_index(component_id)
+			.map(|index| &self.columns[index])
+	}
+
+	#[inline]
+	pub fn column_mut(&mut self, component_id: ComponentId) -> Option<&mut Column> {
+		self
+			.column_index(component_id)
+			.map(|index| &mut self.columns[index])
+	}
+
+	pub fn add_edge(&self, component_id: ComponentId) -> Option<ArchetypeId> {
+		self.add_edges.get(&component_id).copied()
+	}
+
+	pub fn set_add_edge(&mut self, component_id: ComponentId, archetype_id: ArchetypeId) {
+		self.add_edges.insert(component_id, archetype_id);
+	}
+
+	pub fn remove_edge(&self, component_id: ComponentId) -> Option<ArchetypeId> {
+		self.remove_edges.get(&component_id).copied()
+	}
+
+	pub fn set_remove_edge(&mut self, component_id: ComponentId, archetype_id: ArchetypeId) {
+		self.remove_edges.insert(component_id, archetype_id);
+	}
+
+	/// Pushes an entity whose component values the caller is about to push into every column.
+	pub(super) fn push_entity(&mut self, entity_id: EntityId) -> usize {
+		self.entities.push(entity_id);
+
+		self.entities.len() - 1
+	}
+
+	/// Drops the row and returns the entity that was swapped into its place, if any.
+	pub(super) fn swap_remove(&mut self, row: usize) -> Option<EntityId> {
+		for column in self.columns.iter_mut() {
+			column.swap_remove(row);
+		}
+
+		self.swap_remove_entity(row)
+	}
+
+	/// Moves the row into `target`, handing the columns `target` does not store to `left_behind`,
+	/// which must swap remove the row from them. Returns the row in `target` and the entity that
+	/// was swapped into the vacated row, if any.
+	pub(super) fn move_row(
+		&mut self,
+		row: usize,
+		target: &mut Archetype,
+		mut left_behind: impl FnMut(&mut Column),
+	) -> (usize, Option<EntityId>) {
+		for (component_id, column) in self.component_ids.iter().zip(self.columns.iter_mut()) {
+			match target.column_mut(*component_id) {
+				Some(target_column) => column.swap_remove_into(row, target_column),
+				None => left_behind(column),
+			}
+		}
+
+		let target_row = target.push_entity(self.entities[row]);
+
+		(target_row, self.swap_remove_entity(row))
+	}
+
+	fn swap_remove_entity(&mut self, row: usize) -> Option<EntityId> {
+		self.entities.swap_remove(row);
+
+		self.entities.get(row).copied()
+	}
+}
@@ -0,0 +1,169 @@
+use std::{any::Any, cell::UnsafeCell};
+
+use crate::{
+	cell_borrow::{BorrowKind, Borrows, CellBorrow},
+	tick::{ComponentTicks, Tick},
+};
+
+pub(crate) trait ErasedColumn: Any + Send {
+	fn swap_remove(&mut self, row: usize);
+
+	fn swap_remove_into(&mut self, row: usize, other: &mut dyn ErasedColumn);
+
+	fn as_any(&self) -> &dyn Any;
+
+	fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+pub(crate) struct TypedColumn<T>(Vec<UnsafeCell<T>>);
+
+impl<T> Default for TypedColumn<T> {
+	fn default() -> Self {
+		Self(Vec::new())
+	}
+}
+
+impl<T> ErasedColumn for TypedColumn<T>
+where
+	T: Send + Sync + 'static,
+{
+	fn swap_remove(&mut self, row: usize) {
+		self.0.swap_remove(row);
+	}
+
+	fn swap_remove_into(&mut self, row: usize, other: &mut dyn ErasedColumn) {
+		let other = other
+			.as_any_mut()
+			.downcast_mut::<Self>()
+			.expect("columns should be of the same type");
+
+		other.0.push(self.0.swap_remove(row));
+	}
+
+	fn as_any(&self) -> &dyn Any {
+		self
+	}
+
+	fn as_any_mut(&mut self) -> &mut dyn Any {
+		self
+	}
+}
+
+/// The values of one component in an archetype, along with when each one was added and last
+/// changed.
+///
+/// Values and ticks can be reached through a shared reference so entities can be accessed from
+/// several threads at once. Whoever does so must hold a [`borrow`](Self::borrow) of the row or
+/// the column, or exclusive access to the storage.
+pub struct Column {
+	values: Box<dyn ErasedColumn>,
+	ticks: Vec<UnsafeCell<ComponentTicks>>,
+	borrows: Borrows,
+}
+
+// SAFETY: rows are only accessed through a shared reference while they are borrowed, which hands
+// a row to any number of readers or a single writer at a time, and values are `Sync` so readers
+// may be on several threads
+unsafe impl Sync for Column {}
+
+impl Column {
+	pub fn new<T>() -> Self
+	where
+		T: Send + Sync + 'static,
+	{
+		Self {
+			values: Box::new(TypedColumn::<T>::default()),
+			ticks: Vec::new(),
+			borrows: Borrows::default(),
+		}
+	}
+
+	/// Borrows a row or the whole column, waiting for conflicting borrows held by other threads.
+	/// Returns `None` if this thread holds a conflicting borrow.
+	#[inline]
+	pub(crate) fn borrow(&self, kind: BorrowKind) -> Option<CellBorrow<'_>> {
+		self.borrows.borrow(kind)
+	}
+
+	/// A pointer to the first value. Rows may be read or written through it as described on
+	/// [`Column`].
+	#[inline]
+	pub fn values_ptr<T>(&self) -> *mut T
+	where
+		T: 'static,
+	{
+		UnsafeCell::raw_get(
+			self
+				.values
+				.as_any()
+				.downcast_ref::<TypedColumn<T>>()
+				.expect("column should be of the requested type")
+				.0
+				.as_ptr(),
+		)
+	}
+
+	/// A pointer to the first ticks. Rows may be read or written through it as described on
+	/// [`Column`].
+	#[inline]
+	pub fn ticks_ptr(&self) -> *mut ComponentTicks {
+		UnsafeCell::raw_get(self.ticks.as_ptr())
+	}
+
+	#[inline]
+	pub fn get_mut<T>(&mut self, row: usize) -> &mut T
+	where
+		T: Send + Sync + 'static,
+	{
+		self.typed_vec_mut()[row].get_mut()
+	}
+
+	#[inline]
+	pub fn ticks_mut(&mut self, row: usize) -> &mut ComponentTicks {
+		self.ticks[row].get_mut()
+	}
+
+	pub fn push<T>(&mut self, value: T, tick: Tick)
+	where
+		T: Send + Sync + 'static,
+	{
+		self.typed_vec_mut().push(UnsafeCell::new(value));
+		self.ticks.push(UnsafeCell::new(ComponentTicks::new(tick)));
+		self.borrows.push();
+	}
+
+	pub fn swap_remove_value<T>(&mut self, row: usize) -> T
+	where
+		T: Send + Sync + 'static,
+	{
+		self.ticks.swap_remove(row);
+		self.borrows.swap_remove(row);
+		self.typed_vec_mut().swap_remove(row).into_inner()
+	}
+
+	pub fn swap_remove(&mut self, row: usize) {
+		self.values.swap_remove(row);
+		self.ticks.swap_remove(row);
+		self.borrows.swap_remove(row);
+	}
+
+	pub fn swap_remove_into(&mut self, row: usize, other: &mut Column) {
+		self.values.swap_remove_into(row, &mut *other.values);
+		other.ticks.push(self.ticks.swap_remove(row));
+		self.borrows.swap_remove(row);
+		other.borrows.push();
+	}
+
+	#[inline]
+	fn typed_vec_mut<T>(&mut self) -> &mut Vec<UnsafeCell<T>>
+	where
+		T: Send + Sync + 'static,
+	{
+		&mut self
+			.values
+			.as_any_mut()
+			.downcast_mut::<TypedColumn<T>>()
+			.expect("column should be of the requested type")
+			.0
+	}
+}
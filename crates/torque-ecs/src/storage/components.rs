@@ -0,0 +1,108 @@
+use std::any::{type_name, TypeId};
+
+use fnv::FnvHashMap;
+
+use crate::{component_hooks::ComponentHooks, Component, EntityId};
+
+use super::Column;
+
+/// A component registered with a system, see [`System::component_id`](crate::System). Accessing a
+/// component by id skips looking it up by type.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct ComponentId(usize);
+
+pub(crate) struct ComponentInfo {
+	pub type_id: TypeId,
+	/// [`Component::NAME`], or the type name of a resource.
+	pub name: &'static str,
+	pub new_column: fn() -> Column,
+	pub hooks: ComponentHooks,
+	/// Entities the component was removed from since the last drain, `None` until tracking is
+	/// enabled so untracked components do not pile up ids nobody reads.
+	pub removed: Option<Vec<EntityId>>,
+}
+
+#[derive(Default)]
+pub struct Components {
+	ids: FnvHashMap<TypeId, ComponentId>,
+	infos: Vec<ComponentInfo>,
+}
+
+impl Components {
+	#[inline]
+	pub fn id<C>(&self) -> Option<ComponentId>
+	where
+		C: Component + 'static,
+	{
+		self.ids.get(&TypeId::of::<C>()).copied()
+	}
+
+	/// Whether `id` was registered for `C`, which callers passing ids of their own need to check
+	/// before accessing a column as holding `C::Value`.
+	#[inline]
+	pub fn is<C>(&self, id: ComponentId) -> bool
+	where
+		C: Component + 'static,
+	{
+		self
+			.infos
+			.get(id.0)
+			.is_some_and(|info| info.type_id == TypeId::of::<C>())
+	}
+
+	pub fn init<C>(&mut self) -> ComponentId
+	where
+		C: Component + 'static,
+	{
+		self.init_with(TypeId::of::<C>(), C::NAME, Column::new::<C::Value>)
+	}
+
+	#[inline]
+	pub fn resource_id<R>(&self) -> Option<ComponentId>
+	where
+		R: 'static,
+	{
+		self.ids.get(&TypeId::of::<R>()).copied()
+	}
+
+	/// Registers a resource, which is stored like a component of no entity in particular.
+	pub fn init_resource<R>(&mut self) -> ComponentId
+	where
+		R: Send + Sync + 'static,
+	{
+		self.init_with(TypeId::of::<R>(), type_name::<R>(), Column::new::<R>)
+	}
+
+	fn init_with(
+		&mut self,
+		type_id: TypeId,
+		name: &'static str,
+		new_column: fn() -> Column,
+	) -> ComponentId {
+		let Self { ids, infos } = self;
+
+		*ids.entry(type_id).or_insert_with(|| {
+			let id = ComponentId(infos.len());
+
+			infos.push(ComponentInfo {
+				type_id,
+				name,
+				new_column,
+				hooks: Default::default(),
+				removed: None,
+			});
+
+			id
+		})
+	}
+
+	#[inline]
+	pub(crate) fn info(&self, id: ComponentId) -> &ComponentInfo {
+		&self.infos[id.0]
+	}
+
+	#[inline]
+	pub(crate) fn info_mut(&mut self, id: ComponentId) -> &mut ComponentInfo {
+		&mut self.infos[id.0]
+	}
+}
@@ -0,0 +1,543 @@
+mod archetype;
+mod column;
+mod components;
+
+use std::{
+	any::TypeId,
+	sync::{
+		atomic::{AtomicU64, Ordering},
+		Arc,
+	},
+};
+
+use fnv::FnvHashMap;
+use slotmap::SecondaryMap;
+
+use crate::{
+	component_hooks::{ComponentHook, ComponentHooks},
+	hierarchy::{Children, Parent},
+	tick::Tick,
+	Component, EntityId, SystemError,
+};
+
+pub use self::components::ComponentId;
+pub(crate) use self::{
+	archetype::{Archetype, ArchetypeId},
+	column::Column,
+	components::Components,
+};
+
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Location {
+	pub archetype_id: ArchetypeId,
+	pub row: usize,
+}
+
+/// Where the value of a component lives, see [`Storage::cell`].
+pub struct CellLocation<'s> {
+	pub column: &'s Column,
+	pub row: usize,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct EntityMeta {
+	pub location: Location,
+	/// [`Entity::NAME`](crate::Entity::NAME) of the type the entity was created as.
+	pub name: &'static str,
+	pub type_ids: &'static [TypeId],
+}
+
+/// Columnar component storage. Entities are grouped into archetypes by the exact set of
+/// components they carry, so walking one component over many entities touches contiguous memory
+/// instead of one hash map per entity.
+pub(crate) struct Storage {
+	components: Components,
+	archetypes: Vec<Archetype>,
+	archetype_ids: FnvHashMap<Box<[ComponentId]>, ArchetypeId>,
+	entities: SecondaryMap<EntityId, EntityMeta>,
+	/// Resources, each in a column of a single row.
+	resources: FnvHashMap<ComponentId, Column>,
+	change_tick: AtomicU64,
+}
+
+impl Default for Storage {
+	fn default() -> Self {
+		let mut archetype_ids = FnvHashMap::default();
+
+		archetype_ids.insert(Box::default(), ArchetypeId::EMPTY);
+
+		let mut storage = Self {
+			components: Default::default(),
+			archetypes: vec![Archetype::new(Box::default(), Box::default())],
+			archetype_ids,
+			entities: Default::default(),
+			resources: Default::default(),
+			change_tick: AtomicU64::new(Tick::ZERO.get()),
+		};
+
+		// keeps both directions of the hierarchy consistent however a link goes away
+		storage
+			.hooks_mut::<Parent>()
+			.on_remove
+			.push(Arc::new(Parent::on_remove));
+		storage
+			.hooks_mut::<Children>()
+			.on_remove
+			.push(Arc::new(Children::on_remove));
+
+		storage
+	}
+}
+
+impl Storage {
+	#[inline]
+	pub fn components(&self) -> &Components {
+		&self.components
+	}
+
+	/// The most recent tick handed out.
+	#[inline]
+	pub fn change_tick(&self) -> Tick {
+		Tick::new(self.change_tick.load(Ordering::Relaxed))
+	}
+
+	/// Advances the change tick, returning a tick newer than any handed out before.
+	#[inline]
+	pub fn next_tick(&self) -> Tick {
+		Tick::new(self.change_tick.fetch_add(1, Ordering::Relaxed) + 1)
+	}
+
+	/// Registers `C`, unless it is already.
+	#[inline]
+	pub fn init_component<C>(&mut self) -> ComponentId
+	where
+		C: Component + 'static,
+	{
+		self.components.init::<C>()
+	}
+
+	/// Starts recording the entities `C` is removed from.
+	pub fn track_removed<C>(&mut self)
+	where
+		C: Component + 'static,
+	{
+		let component_id = self.components.init::<C>();
+
+		self
+			.components
+			.info_mut(component_id)
+			.removed
+			.get_or_insert_with(Vec::new);
+	}
+
+	/// Takes the entities `C` was removed from since the last call.
+	pub fn drain_removed<C>(&mut self) -> Vec<EntityId>
+	where
+		C: Component + 'static,
+	{
+		self
+			.components
+			.id::<C>()
+			.and_then(|component_id| self.components.info_mut(component_id).removed.as_mut())
+			.map(std::mem::take)
+			.unwrap_or_default()
+	}
+
+	/// Hooks of `C`, registering the component if needed.
+	pub fn hooks_mut<C>(&mut self) -> &mut ComponentHooks
+	where
+		C: Component + 'static,
+	{
+		let component_id = self.components.init::<C>();
+
+		&mut self.components.info_mut(component_id).hooks
+	}
+
+	pub fn hooks<C>(&self) -> Option<&ComponentHooks>
+	where
+		C: Component + 'static,
+	{
+		self
+			.components
+			.id::<C>()
+			.map(|component_id| &self.components.info(component_id).hooks)
+	}
+
+	/// The remove hooks of every component the entity carries.
+	pub fn remove_hooks(&self, entity_id: EntityId) -> Vec<ComponentHook> {
+		self
+			.location(entity_id)
+			.map(|location| {
+				self.archetypes[location.archetype_id.index()]
+					.component_ids()
+					.iter()
+					.flat_map(|component_id| {
+						self
+							.components
+							.info(*component_id)
+							.hooks
+							.on_remove
+							.iter()
+							.cloned()
+					})
+					.collect()
+			})
+			.unwrap_or_default()
+	}
+
+	#[inline]
+	pub fn location(&self, entity_id: EntityId) -> Option<Location> {
+		self.entities.get(entity_id).map(|meta| meta.location)
+	}
+
+	#[inline]
+	pub fn entity_name(&self, entity_id: EntityId) -> Option<&'static str> {
+		self.entities.get(entity_id).map(|meta| meta.name)
+	}
+
+	/// Every spawned entity, in no particular order.
+	pub fn entity_ids(&self) -> impl Iterator<Item = EntityId> + '_ {
+		self.entities.keys()
+	}
+
+	#[inline]
+	pub fn type_ids(&self, entity_id: EntityId) -> Option<&'static [TypeId]> {
+		self.entities.get(entity_id).map(|meta| meta.type_ids)
+	}
+
+	/// Splits the storage into its archetypes and the per-entity metadata so both can be borrowed at
+	/// the same time.
+	#[inline]
+	pub fn archetypes(&self) -> (&[Archetype], &SecondaryMap<EntityId, EntityMeta>) {
+		(&self.archetypes, &self.entities)
+	}
+
+	pub fn spawn(&mut self, entity_id: EntityId, name: &'static str, type_ids: &'static [TypeId]) {
+		let row = self.archetypes[ArchetypeId::EMPTY.index()].push_entity(entity_id);
+
+		self.entities.insert(
+			entity_id,
+			EntityMeta {
+				location: Location {
+					archetype_id: ArchetypeId::EMPTY,
+					row,
+				},
+				name,
+				type_ids,
+			},
+		);
+	}
+
+	/// Removes the entity and drops all of its components.
+	pub fn despawn(&mut self, entity_id: EntityId) -> bool {
+		let Some(EntityMeta { location, .. }) = self.entities.remove(entity_id) else {
+			return false;
+		};
+
+		let archetype = &mut self.archetypes[location.archetype_id.index()];
+
+		for component_id in archetype.component_ids() {
+			if let Some(removed) = &mut self.components.info_mut(*component_id).removed {
+				removed.push(entity_id);
+			}
+		}
+
+		if let Some(moved) = archetype.swap_remove(location.row) {
+			self.entities[moved].location.row = location.row;
+		}
+
+		true
+	}
+
+	pub fn has<C>(&self, entity_id: EntityId) -> Result<bool, SystemError>
+	where
+		C: Component + 'static,
+	{
+		let location = self
+			.location(entity_id)
+			.ok_or(SystemError::EntityNotFound(entity_id))?;
+
+		Ok(self.components.id::<C>().is_some_and(|component_id| {
+			self.archetypes[location.archetype_id.index()].contains(component_id)
+		}))
+	}
+
+	/// The names of the components the entity carries, ordered by when each component was first
+	/// stored or had a hook registered in this system.
+	pub fn component_names(&self, entity_id: EntityId) -> Result<Vec<&'static str>, SystemError> {
+		let location = self
+			.location(entity_id)
+			.ok_or(SystemError::EntityNotFound(entity_id))?;
+
+		Ok(
+			self.archetypes[location.archetype_id.index()]
+				.component_ids()
+				.iter()
+				.map(|component_id| self.components.info(*component_id).name)
+				.collect(),
+		)
+	}
+
+	/// Locates the component for access through a cell borrow.
+	pub fn cell<C>(&self, entity_id: EntityId) -> Result<CellLocation<'_>, SystemError>
+	where
+		C: Component + 'static,
+	{
+		match self.components.id::<C>() {
+			Some(component_id) => self.cell_by_id::<C>(entity_id, component_id),
+			None => {
+				self
+					.location(entity_id)
+					.ok_or(SystemError::EntityNotFound(entity_id))?;
+
+				Err(SystemError::ComponentNotFound(entity_id, C::NAME))
+			}
+		}
+	}
+
+	/// Like [`cell`](Self::cell), with the component resolved beforehand. Fails with
+	/// [`SystemError::InvalidComponentId`] unless `component_id` was registered for `C` in this
+	/// storage.
+	pub fn cell_by_id<C>(
+		&self,
+		entity_id: EntityId,
+		component_id: ComponentId,
+	) -> Result<CellLocation<'_>, SystemError>
+	where
+		C: Component + 'static,
+	{
+		if !self.components.is::<C>(component_id) {
+			return Err(SystemError::InvalidComponentId(C::NAME));
+		}
+
+		let location = self
+			.location(entity_id)
+			.ok_or(SystemError::EntityNotFound(entity_id))?;
+
+		self.archetypes[location.archetype_id.index()]
+			.column(component_id)
+			.map(|column| CellLocation {
+				column,
+				row: location.row,
+			})
+			.ok_or(SystemError::ComponentNotFound(entity_id, C::NAME))
+	}
+
+	/// Inserts or replaces the component, moving the entity to another archetype if it did not
+	/// carry the component yet. Returns whether the component was added.
+	pub fn insert<C>(&mut self, entity_id: EntityId, value: C::Value) -> Result<bool, SystemError>
+	where
+		C: Component + 'static,
+	{
+		let location = self
+			.location(entity_id)
+			.ok_or(SystemError::EntityNotFound(entity_id))?;
+		let component_id = self.components.init::<C>();
+		let tick = self.next_tick();
+
+		if let Some(column) = self.archetypes[location.archetype_id.index()].column_mut(component_id) {
+			*column.get_mut::<C::Value>(location.row) = value;
+			column.ticks_mut(location.row).changed = tick;
+
+			return Ok(false);
+		}
+
+		let target_id = self.archetype_with(location.archetype_id, component_id);
+
+		self.move_entity(location, target_id, |column| {
+			column.swap_remove(location.row)
+		});
+		self.archetypes[target_id.index()]
+			.column_mut(component_id)
+			.unwrap()
+			.push(value, tick);
+
+		Ok(true)
+	}
+
+	/// Removes the component, moving the entity to another archetype. Returns `None` if the entity
+	/// did not carry it.
+	pub fn remove<C>(&mut self, entity_id: EntityId) -> Result<Option<C::Value>, SystemError>
+	where
+		C: Component + 'static,
+	{
+		let location = self
+			.location(entity_id)
+			.ok_or(SystemError::EntityNotFound(entity_id))?;
+		let Some(component_id) = self.components.id::<C>() else {
+			return Ok(None);
+		};
+
+		if !self.archetypes[location.archetype_id.index()].contains(component_id) {
+			return Ok(None);
+		}
+
+		let target_id = self.archetype_without(location.archetype_id, component_id);
+		let mut value = None;
+
+		self.move_entity(location, target_id, |column| {
+			value = Some(column.swap_remove_value::<C::Value>(location.row));
+		});
+
+		if let Some(removed) = &mut self.components.info_mut(component_id).removed {
+			removed.push(entity_id);
+		}
+
+		Ok(value)
+	}
+
+	/// Inserts or replaces the resource, returning the previous value.
+	pub fn insert_resource<R>(&mut self, value: R) -> Option<R>
+	where
+		R: Send + Sync + 'static,
+	{
+		let component_id = self.components.init_resource::<R>();
+		let tick = self.next_tick();
+
+		let Some(column) = self.resources.get_mut(&component_id) else {
+			let mut column = Column::new::<R>();
+
+			column.push(value, tick);
+			self.resources.insert(component_id, column);
+
+			return None;
+		};
+
+		column.ticks_mut(0).changed = tick;
+
+		Some(std::mem::replace(column.get_mut::<R>(0), value))
+	}
+
+	pub fn remove_resource<R>(&mut self) -> Option<R>
+	where
+		R: Send + Sync + 'static,
+	{
+		let component_id = self.components.resource_id::<R>()?;
+
+		self
+			.resources
+			.remove(&component_id)
+			.map(|mut column| column.swap_remove_value::<R>(0))
+	}
+
+	/// Locates the resource for access through a cell borrow.
+	pub fn resource<R>(&self) -> Option<&Column>
+	where
+		R: 'static,
+	{
+		let component_id = self.components.resource_id::<R>()?;
+
+		self.resources.get(&component_id)
+	}
+
+	/// Moves the entity at `location` into `target_id`. Columns the target does not have are
+	/// handed to `left_behind`, see [`Archetype::move_row`], columns only the target has are left
+	/// for the caller to push into.
+	fn move_entity(
+		&mut self,
+		location: Location,
+		target_id: ArchetypeId,
+		left_behind: impl FnMut(&mut Column),
+	) -> Location {
+		let (source, target) = pair_mut(
+			&mut self.archetypes,
+			location.archetype_id.index(),
+			target_id.index(),
+		);
+
+		let (row, moved) = source.move_row(location.row, target, left_behind);
+		let entity_id = target.entities()[row];
+
+		if let Some(moved) = moved {
+			self.entities[moved].location.row = location.row;
+		}
+
+		let location = Location {
+			archetype_id: target_id,
+			row,
+		};
+
+		self.entities[entity_id].location = location;
+
+		location
+	}
+
+	fn archetype_with(
+		&mut self,
+		archetype_id: ArchetypeId,
+		component_id: ComponentId,
+	) -> ArchetypeId {
+		if let Some(target_id) = self.archetypes[archetype_id.index()].add_edge(component_id) {
+			return target_id;
+		}
+
+		let mut component_ids = self.archetypes[archetype_id.index()]
+			.component_ids()
+			.to_vec();
+		let index = component_ids.binary_search(&component_id).unwrap_err();
+
+		component_ids.insert(index, component_id);
+
+		let target_id = self.get_or_create_archetype(component_ids.into_boxed_slice());
+
+		self.archetypes[archetype_id.index()].set_add_edge(component_id, target_id);
+
+		target_id
+	}
+
+	fn archetype_without(
+		&mut self,
+		archetype_id: ArchetypeId,
+		component_id: ComponentId,
+	) -> ArchetypeId {
+		if let Some(target_id) = self.archetypes[archetype_id.index()].remove_edge(component_id) {
+			return target_id;
+		}
+
+		let component_ids = self.archetypes[archetype_id.index()]
+			.component_ids()
+			.iter()
+			.copied()
+			.filter(|v| *v != component_id)
+			.collect();
+
+		let target_id = self.get_or_create_archetype(component_ids);
+
+		self.archetypes[archetype_id.index()].set_remove_edge(component_id, target_id);
+
+		target_id
+	}
+
+	fn get_or_create_archetype(&mut self, component_ids: Box<[ComponentId]>) -> ArchetypeId {
+		if let Some(archetype_id) = self.archetype_ids.get(&component_ids) {
+			return *archetype_id;
+		}
+
+		let columns = component_ids
+			.iter()
+			.map(|component_id| (self.components.info(*component_id).new_column)())
+			.collect();
+		let archetype_id = ArchetypeId::new(self.archetypes.len());
+
+		self
+			.archetypes
+			.push(Archetype::new(component_ids.clone(), columns));
+		self.archetype_ids.insert(component_ids, archetype_id);
+
+		archetype_id
+	}
+}
+
+fn pair_mut<T>(slice: &mut [T], a: usize, b: usize) -> (&mut T, &mut T) {
+	assert_ne!(a, b);
+
+	if a < b {
+		let (left, right) = slice.split_at_mut(b);
+
+		(&mut left[a], &mut right[0])
+	} else {
+		let (left, right) = slice.split_at_mut(a);
+
+		(&mut right[0], &mut left[b])
+	}
+}
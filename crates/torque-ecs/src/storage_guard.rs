@@ -0,0 +1,96 @@
+use std::{
+	cell::RefCell,
+	mem::ManuallyDrop,
+	ops::{Deref, DerefMut},
+	sync::Arc,
+};
+
+use parking_lot::{RwLockReadGuard, RwLockWriteGuard};
+
+use crate::{storage::Storage, system::Inner};
+
+thread_local! {
+	/// The storages this thread has locked, by address of the system and whether exclusively.
+	static HELD: RefCell<Vec<(usize, bool)>> = const { RefCell::new(Vec::new()) };
+}
+
+/// How the current thread holds the storage of `system`, if at all: `Some(true)` if exclusively.
+pub(crate) fn held(system: &Arc<Inner>) -> Option<bool> {
+	let address = Arc::as_ptr(system) as usize;
+
+	HELD.with_borrow(|held| {
+		held
+			.iter()
+			.filter(|(v, _)| *v == address)
+			.map(|(_, exclusive)| *exclusive)
+			.reduce(|a, b| a || b)
+	})
+}
+
+pub(crate) type StorageRead<'s> = StorageGuard<'s, RwLockReadGuard<'s, Storage>>;
+
+pub(crate) type StorageWrite<'s> = StorageGuard<'s, RwLockWriteGuard<'s, Storage>>;
+
+/// Access to the storage of a system, shared or exclusive depending on `G`. Entities whose last
+/// reference is dropped while this thread holds the storage, e.g. the children held by a
+/// component being replaced, can not be disposed on the spot, so they are disposed once the
+/// thread lets go of it.
+pub(crate) struct StorageGuard<'s, G> {
+	system: &'s Arc<Inner>,
+	guard: ManuallyDrop<G>,
+	exclusive: bool,
+}
+
+impl<'s, G> StorageGuard<'s, G> {
+	pub(crate) fn new(system: &'s Arc<Inner>, guard: G, exclusive: bool) -> Self {
+		let address = Arc::as_ptr(system) as usize;
+
+		HELD.with_borrow_mut(|held| held.push((address, exclusive)));
+
+		Self {
+			system,
+			guard: ManuallyDrop::new(guard),
+			exclusive,
+		}
+	}
+}
+
+impl<G> Deref for StorageGuard<'_, G>
+where
+	G: Deref<Target = Storage>,
+{
+	type Target = Storage;
+
+	fn deref(&self) -> &Self::Target {
+		&self.guard
+	}
+}
+
+impl DerefMut for StorageWrite<'_> {
+	fn deref_mut(&mut self) -> &mut Self::Target {
+		&mut self.guard
+	}
+}
+
+impl<G> Drop for StorageGuard<'_, G> {
+	fn drop(&mut self) {
+		// SAFETY: the guard is not used after this
+		unsafe { ManuallyDrop::drop(&mut self.guard) };
+
+		let address = Arc::as_ptr(self.system) as usize;
+		let entry = (address, self.exclusive);
+
+		HELD.with_borrow_mut(|held| {
+			let index = held
+				.iter()
+				.rposition(|v| *v == entry)
+				.expect("guard should be registered");
+
+			held.remove(index);
+		});
+
+		if held(self.system).is_none() {
+			self.system.dispose_pending();
+		}
+	}
+}
@@ -2,23 +2,32 @@
 mod tests;
 
 use std::{
-	any::{Any, TypeId},
-	collections::HashMap,
+	any::{type_name, TypeId},
 	fmt::Debug,
 	ops::Deref,
-	sync::{
-		atomic::{fence, AtomicUsize, Ordering},
-		Arc, Mutex,
-	},
+	sync::Arc,
 };
 
-use fnv::FnvHashMap;
-use slotmap::{SecondaryMap, SlotMap};
+use parking_lot::{Mutex, ReentrantMutex, RwLock};
+use slotmap::SlotMap;
 
-use crate::{Component, Entity, EntityId, EntityRef, SystemError};
+use crate::{
+	cell_borrow::BorrowKind,
+	commands::{Command, Commands},
+	component_hooks::{call_hooks, ComponentHook, ComponentHooks},
+	component_ref::{Ref, RefMut},
+	event::Observers,
+	events::EventUpdate,
+	resource::{Res, ResMut},
+	snapshot::SerdeRegistry,
+	storage::{ComponentId, Storage},
+	storage_guard::{self, StorageGuard, StorageRead, StorageWrite},
+	tick::Tick,
+	Component, Entity, EntityId, EntityRef, Query, QueryData, QueryFilter, SystemError,
+};
 
 #[derive(Clone, Debug, Default)]
-pub struct System(Arc<Inner>);
+pub struct System(pub(crate) Arc<Inner>);
 
 impl Deref for System {
 	type Target = Arc<Inner>;
@@ -28,13 +37,34 @@ impl Deref for System {
 	}
 }
 
-type ComponentMap = Mutex<SecondaryMap<EntityId, FnvHashMap<TypeId, Box<dyn Any>>>>;
-
+/// Component values are accessed under a shared lock of the storage plus a borrow of the single
+/// value, so closures may access other entities, or other components of the same entity, from
+/// any thread. Queries borrow the columns they access as a whole. Creating and disposing
+/// entities, adding components and registering hooks lock the whole storage exclusively.
+///
+/// Doing something from within a closure that conflicts with what the closure itself holds, e.g.
+/// borrowing the same value mutably again or adding a component while reading another, returns a
+/// [`SystemError::Reentrant`] or [`SystemError::ReentrantLock`] error instead of deadlocking.
+/// Threads waiting on each other's values in a cycle still deadlock, so nested access should go
+/// in a consistent order, e.g. from parents to children.
 #[derive(Default)]
 pub struct Inner {
-	ref_counts: Mutex<SlotMap<EntityId, AtomicUsize>>,
-	type_ids: Mutex<SecondaryMap<EntityId, &'static [TypeId]>>,
-	components: ComponentMap,
+	/// Strong reference count of every entity, `None` once the entity is being disposed so it can
+	/// no longer be revived by an upgrade. The slot is only freed after the entity is despawned, so
+	/// its id is not handed out again while hooks may still see it.
+	ref_counts: Mutex<SlotMap<EntityId, Option<usize>>>,
+	storage: RwLock<Storage>,
+	/// Entities whose last reference was dropped while their thread held the storage.
+	pending_disposal: Mutex<Vec<EntityId>>,
+	/// Commands left to [`apply_deferred`](Self::apply_deferred).
+	deferred: Mutex<Vec<Command>>,
+	pub(crate) serde: RwLock<SerdeRegistry>,
+	pub(crate) observers: RwLock<Observers>,
+	/// Updates the resources added by [`add_events`](Self::add_events).
+	pub(crate) event_updates: Mutex<Vec<EventUpdate>>,
+	/// Held while checking for and linking entities in the hierarchy, so concurrent inserts can
+	/// not form a cycle. Taken before the storage, and reentrant for hooks inserting children.
+	pub(crate) hierarchy: ReentrantMutex<()>,
 }
 
 impl Inner {
@@ -42,33 +72,474 @@ impl Inner {
 	where
 		E: Entity + 'static,
 	{
-		let entity_id = self.ref_counts.lock().unwrap().insert(AtomicUsize::new(0));
+		self.try_create::<E>().unwrap()
+	}
+
+	pub fn try_create<E>(self: &Arc<Self>) -> Result<EntityRef<E>, SystemError>
+	where
+		E: Entity + 'static,
+	{
+		let system = System(self.clone());
+		let entity_id = self.spawn_counted(E::NAME, E::type_ids())?;
+		let entity = EntityRef::from_counted(system.clone(), entity_id);
+
+		E::init(&system, entity_id)?;
+
+		Ok(entity)
+	}
+
+	/// Creates an entity from the [`Entity::NAME`] and [`Entity::type_ids`] of its type.
+	pub(crate) fn try_create_any(
+		self: &Arc<Self>,
+		name: &'static str,
+		type_ids: &'static [TypeId],
+	) -> Result<EntityRef<()>, SystemError> {
+		let entity_id = self.spawn_counted(name, type_ids)?;
+
+		Ok(EntityRef::from_counted(System(self.clone()), entity_id))
+	}
+
+	/// Spawns an entity with a single strong reference, to be owned by the caller.
+	fn spawn_counted(
+		self: &Arc<Self>,
+		name: &'static str,
+		type_ids: &'static [TypeId],
+	) -> Result<EntityId, SystemError> {
+		let mut storage = self.write_storage()?;
+		let entity_id = self.ref_counts.lock().insert(Some(1));
+
+		storage.spawn(entity_id, name, type_ids);
+
+		Ok(entity_id)
+	}
+
+	/// A buffer of structural changes to apply later, see [`Commands`].
+	pub fn commands(self: &Arc<Self>) -> Commands {
+		Commands::new(System(self.clone()))
+	}
+
+	/// Applies the commands deferred by dropping [`Commands`] without applying them, in the order
+	/// they were recorded. Stops at the first command that fails, dropping the rest.
+	pub fn apply_deferred(self: &Arc<Self>) -> Result<(), SystemError> {
+		let commands = std::mem::take(&mut *self.deferred.lock());
+		let system = System(self.clone());
+
+		commands
+			.into_iter()
+			.try_for_each(|command| command(&system))
+	}
+
+	/// Reserves an entity for [`Commands::spawn`], which only enters the storage once
+	/// [`spawn_reserved`](Self::spawn_reserved) is called.
+	pub(crate) fn reserve<E>(self: &Arc<Self>) -> EntityRef<E>
+	where
+		E: Entity + 'static,
+	{
+		let entity_id = self.ref_counts.lock().insert(Some(1));
+
+		EntityRef::from_counted(System(self.clone()), entity_id)
+	}
+
+	/// Spawns a reserved entity, unless every reference to it was dropped in the meantime. Returns
+	/// whether it was spawned.
+	pub(crate) fn spawn_reserved(
+		self: &Arc<Self>,
+		entity_id: EntityId,
+		name: &'static str,
+		type_ids: &'static [TypeId],
+	) -> Result<bool, SystemError> {
+		let mut storage = self.write_storage()?;
+		let alive = matches!(self.ref_counts.lock().get(entity_id), Some(Some(_)));
+		let spawn = alive && storage.location(entity_id).is_none();
+
+		if spawn {
+			storage.spawn(entity_id, name, type_ids);
+		}
+
+		Ok(spawn)
+	}
+
+	pub(crate) fn defer(&self, commands: impl IntoIterator<Item = Command>) {
+		self.deferred.lock().extend(commands);
+	}
+
+	/// Returns a new reference to the entity, or `None` if it is not an `E` or is already gone or
+	/// being disposed. Also `None` while this thread holds the system exclusively, see
+	/// [`try_get`](Self::try_get).
+	#[inline]
+	pub fn get<E>(self: &Arc<Self>, entity_id: EntityId) -> Option<EntityRef<E>>
+	where
+		E: Entity + 'static,
+	{
+		self.try_get(entity_id).ok().flatten()
+	}
 
+	pub fn try_get<E>(
+		self: &Arc<Self>,
+		entity_id: EntityId,
+	) -> Result<Option<EntityRef<E>>, SystemError>
+	where
+		E: Entity + 'static,
+	{
+		let type_id = TypeId::of::<E>();
+		let is = self
+			.read_storage()?
+			.type_ids(entity_id)
+			.is_some_and(|v| v.contains(&type_id));
+
+		Ok(
+			(is && self.try_increment_ref(entity_id))
+				.then(|| EntityRef::from_counted(System(self.clone()), entity_id)),
+		)
+	}
+
+	/// Like [`get`](Self::get), for an entity of any type.
+	#[inline]
+	pub(crate) fn get_any(self: &Arc<Self>, entity_id: EntityId) -> Option<EntityRef<()>> {
+		self.try_get_any(entity_id).ok().flatten()
+	}
+
+	pub(crate) fn try_get_any(
+		self: &Arc<Self>,
+		entity_id: EntityId,
+	) -> Result<Option<EntityRef<()>>, SystemError> {
+		let exists = self.read_storage()?.location(entity_id).is_some();
+
+		Ok(
+			(exists && self.try_increment_ref(entity_id))
+				.then(|| EntityRef::from_counted(System(self.clone()), entity_id)),
+		)
+	}
+
+	/// Disposes the entity, running the remove hooks of its components. References still held
+	/// become dangling: accessing components through them returns [`SystemError::EntityNotFound`]
+	/// and weak references no longer upgrade. Returns `false` if the entity was already gone.
+	///
+	/// When called from within a closure accessing the system, the entity can not be upgraded
+	/// anymore right away but is only disposed once the closure returns.
+	pub fn despawn(self: &Arc<Self>, entity_id: EntityId) -> bool {
+		let dispose = self
+			.ref_counts
+			.lock()
+			.get_mut(entity_id)
+			.and_then(Option::take)
+			.is_some();
+
+		if dispose {
+			self.dispose_or_defer(entity_id);
+		}
+
+		dispose
+	}
+
+	/// Whether the entity exists and is not being disposed.
+	pub fn contains(&self, entity_id: EntityId) -> bool {
 		self
-			.type_ids
+			.ref_counts
 			.lock()
-			.unwrap()
-			.insert(entity_id, E::type_ids());
+			.get(entity_id)
+			.is_some_and(Option::is_some)
+	}
+
+	/// The [`Entity::NAME`] of the entity's type, `None` if it is not spawned.
+	pub fn entity_name(self: &Arc<Self>, entity_id: EntityId) -> Option<&'static str> {
+		self.read_storage().ok()?.entity_name(entity_id)
+	}
+
+	/// Every spawned entity, in no particular order.
+	pub(crate) fn try_entity_ids(self: &Arc<Self>) -> Result<Vec<EntityId>, SystemError> {
+		Ok(self.read_storage()?.entity_ids().collect())
+	}
 
+	/// The number of [`EntityRef`]s to the entity, `0` once it is gone or being disposed.
+	pub fn strong_count(&self, entity_id: EntityId) -> usize {
 		self
-			.components
+			.ref_counts
 			.lock()
-			.unwrap()
-			.insert(entity_id, HashMap::default());
+			.get(entity_id)
+			.copied()
+			.flatten()
+			.unwrap_or(0)
+	}
 
-		EntityRef::new(System(self.clone()), entity_id)
+	/// Registers a hook called after `C` is added to an entity.
+	pub fn on_add<C>(
+		self: &Arc<Self>,
+		hook: impl Fn(&System, EntityId) + Send + Sync + 'static,
+	) -> &Arc<Self>
+	where
+		C: Component + 'static,
+	{
+		self.try_on_add::<C>(hook).unwrap()
 	}
 
-	pub fn get<E>(self: &Arc<Self>, entity_id: EntityId) -> Option<EntityRef<E>>
+	pub fn try_on_add<C>(
+		self: &Arc<Self>,
+		hook: impl Fn(&System, EntityId) + Send + Sync + 'static,
+	) -> Result<&Arc<Self>, SystemError>
 	where
-		E: Entity + 'static,
+		C: Component + 'static,
 	{
-		let type_id = TypeId::of::<E>();
+		self
+			.write_storage()?
+			.hooks_mut::<C>()
+			.on_add
+			.push(Arc::new(hook));
+
+		Ok(self)
+	}
+
+	/// Registers a hook called after the value of `C` is overwritten by a `set`, so it reads the new
+	/// value. It is not called if the value could not be replaced.
+	pub fn on_replace<C>(
+		self: &Arc<Self>,
+		hook: impl Fn(&System, EntityId) + Send + Sync + 'static,
+	) -> &Arc<Self>
+	where
+		C: Component + 'static,
+	{
+		self.try_on_replace::<C>(hook).unwrap()
+	}
+
+	pub fn try_on_replace<C>(
+		self: &Arc<Self>,
+		hook: impl Fn(&System, EntityId) + Send + Sync + 'static,
+	) -> Result<&Arc<Self>, SystemError>
+	where
+		C: Component + 'static,
+	{
+		self
+			.write_storage()?
+			.hooks_mut::<C>()
+			.on_replace
+			.push(Arc::new(hook));
 
-		self.type_ids.lock().unwrap().get(entity_id).and_then(|v| {
-			v.contains(&type_id)
-				.then(|| EntityRef::new(System(self.clone()), entity_id))
-		})
+		Ok(self)
+	}
+
+	/// Registers a hook called before `C` is removed from an entity, including when the entity is
+	/// disposed after its last [`EntityRef`] is dropped.
+	pub fn on_remove<C>(
+		self: &Arc<Self>,
+		hook: impl Fn(&System, EntityId) + Send + Sync + 'static,
+	) -> &Arc<Self>
+	where
+		C: Component + 'static,
+	{
+		self.try_on_remove::<C>(hook).unwrap()
+	}
+
+	pub fn try_on_remove<C>(
+		self: &Arc<Self>,
+		hook: impl Fn(&System, EntityId) + Send + Sync + 'static,
+	) -> Result<&Arc<Self>, SystemError>
+	where
+		C: Component + 'static,
+	{
+		self
+			.write_storage()?
+			.hooks_mut::<C>()
+			.on_remove
+			.push(Arc::new(hook));
+
+		Ok(self)
+	}
+
+	/// The most recent change tick. Keep it around and pass it to [`Query::since`] next time to
+	/// visit only what changed in between.
+	#[inline]
+	pub fn change_tick(self: &Arc<Self>) -> Tick {
+		self.try_change_tick().unwrap()
+	}
+
+	pub fn try_change_tick(self: &Arc<Self>) -> Result<Tick, SystemError> {
+		Ok(self.read_storage()?.change_tick())
+	}
+
+	/// Starts recording the entities `C` is removed from, to be collected with
+	/// [`drain_removed`](Self::drain_removed).
+	pub fn track_removed<C>(self: &Arc<Self>) -> &Arc<Self>
+	where
+		C: Component + 'static,
+	{
+		self.try_track_removed::<C>().unwrap()
+	}
+
+	pub fn try_track_removed<C>(self: &Arc<Self>) -> Result<&Arc<Self>, SystemError>
+	where
+		C: Component + 'static,
+	{
+		self.write_storage()?.track_removed::<C>();
+
+		Ok(self)
+	}
+
+	/// Takes the entities `C` was removed from since the last call. Always empty unless
+	/// [`track_removed`](Self::track_removed) was called.
+	pub fn drain_removed<C>(self: &Arc<Self>) -> Vec<EntityId>
+	where
+		C: Component + 'static,
+	{
+		self.try_drain_removed::<C>().unwrap()
+	}
+
+	pub fn try_drain_removed<C>(self: &Arc<Self>) -> Result<Vec<EntityId>, SystemError>
+	where
+		C: Component + 'static,
+	{
+		Ok(self.write_storage()?.drain_removed::<C>())
+	}
+
+	/// Inserts or replaces a resource, global state that belongs to no entity in particular.
+	/// Returns the previous value.
+	pub fn insert_resource<R>(self: &Arc<Self>, value: R) -> Option<R>
+	where
+		R: Send + Sync + 'static,
+	{
+		self.try_insert_resource(value).unwrap()
+	}
+
+	pub fn try_insert_resource<R>(self: &Arc<Self>, value: R) -> Result<Option<R>, SystemError>
+	where
+		R: Send + Sync + 'static,
+	{
+		Ok(self.write_storage()?.insert_resource(value))
+	}
+
+	/// Inserts the resource `init` returns unless one exists, returning whether it was inserted.
+	pub(crate) fn try_init_resource<R>(
+		self: &Arc<Self>,
+		init: impl FnOnce() -> R,
+	) -> Result<bool, SystemError>
+	where
+		R: Send + Sync + 'static,
+	{
+		let mut storage = self.write_storage()?;
+
+		if storage.resource::<R>().is_some() {
+			return Ok(false);
+		}
+
+		storage.insert_resource(init());
+
+		Ok(true)
+	}
+
+	pub fn remove_resource<R>(self: &Arc<Self>) -> Option<R>
+	where
+		R: Send + Sync + 'static,
+	{
+		self.try_remove_resource::<R>().unwrap()
+	}
+
+	pub fn try_remove_resource<R>(self: &Arc<Self>) -> Result<Option<R>, SystemError>
+	where
+		R: Send + Sync + 'static,
+	{
+		Ok(self.write_storage()?.remove_resource::<R>())
+	}
+
+	#[inline]
+	pub fn contains_resource<R>(self: &Arc<Self>) -> bool
+	where
+		R: 'static,
+	{
+		self.try_contains_resource::<R>().unwrap()
+	}
+
+	pub fn try_contains_resource<R>(self: &Arc<Self>) -> Result<bool, SystemError>
+	where
+		R: 'static,
+	{
+		Ok(self.read_storage()?.resource::<R>().is_some())
+	}
+
+	#[inline]
+	pub fn resource<R>(self: &Arc<Self>) -> Res<'_, R>
+	where
+		R: 'static,
+	{
+		self.try_resource::<R>().unwrap()
+	}
+
+	pub fn try_resource<R>(self: &Arc<Self>) -> Result<Res<'_, R>, SystemError>
+	where
+		R: 'static,
+	{
+		let storage = self.read_storage()?;
+		let column = storage
+			.resource::<R>()
+			.ok_or(SystemError::ResourceNotFound(type_name::<R>()))?;
+		let value = column.values_ptr::<R>();
+		let borrow = column
+			.borrow(BorrowKind::ColumnRead)
+			.ok_or(SystemError::ReentrantResource(type_name::<R>()))?;
+		// SAFETY: the borrow is dropped before the storage, which keeps the column in place
+		let borrow = unsafe { borrow.detach() };
+
+		// SAFETY: the resource is borrowed shared, and can not move while the storage is locked
+		Ok(unsafe { Res::new(storage, borrow, value) })
+	}
+
+	/// Borrows a resource mutably, marking it changed.
+	#[inline]
+	pub fn resource_mut<R>(self: &Arc<Self>) -> ResMut<'_, R>
+	where
+		R: 'static,
+	{
+		self.try_resource_mut::<R>().unwrap()
+	}
+
+	pub fn try_resource_mut<R>(self: &Arc<Self>) -> Result<ResMut<'_, R>, SystemError>
+	where
+		R: 'static,
+	{
+		let storage = self.read_storage()?;
+		let column = storage
+			.resource::<R>()
+			.ok_or(SystemError::ResourceNotFound(type_name::<R>()))?;
+		let (value, ticks) = (column.values_ptr::<R>(), column.ticks_ptr());
+		let borrow = column
+			.borrow(BorrowKind::ColumnWrite)
+			.ok_or(SystemError::ReentrantResource(type_name::<R>()))?;
+		// SAFETY: the borrow is dropped before the storage, which keeps the column in place
+		let borrow = unsafe { borrow.detach() };
+
+		// SAFETY: the resource and its ticks are borrowed exclusively, and can not move while the
+		// storage is locked
+		unsafe {
+			(*ticks).changed = storage.next_tick();
+
+			Ok(ResMut::new(storage, borrow, value))
+		}
+	}
+
+	/// Visits every entity carrying the components in `Q`, e.g.
+	/// `system.query::<(&Style, &mut Layout)>()`.
+	#[inline]
+	pub fn query<Q>(self: &Arc<Self>) -> Query<'_, Q>
+	where
+		Q: QueryData,
+	{
+		self.query_filtered::<Q, ()>()
+	}
+
+	/// Like [`query`](Self::query), restricted to the entities matching `F`, e.g.
+	/// `system.query_filtered::<&Style, (With<Children>, Is<Element>)>()`.
+	pub fn query_filtered<Q, F>(self: &Arc<Self>) -> Query<'_, Q, F>
+	where
+		Q: QueryData,
+		F: QueryFilter,
+	{
+		self.try_query_filtered::<Q, F>().unwrap()
+	}
+
+	pub fn try_query_filtered<Q, F>(self: &Arc<Self>) -> Result<Query<'_, Q, F>, SystemError>
+	where
+		Q: QueryData,
+		F: QueryFilter,
+	{
+		Query::new(self.read_storage()?)
 	}
 
 	pub fn entity_set<C>(self: &Arc<Self>, entity_id: EntityId, value: C::Value)
@@ -78,6 +549,8 @@ impl Inner {
 		self.try_entity_set::<C>(entity_id, value).unwrap()
 	}
 
+	/// Replaces the value in place if the entity carries `C` already, which may be done from
+	/// within a closure accessing the system, and adds it otherwise.
 	pub fn try_entity_set<C>(
 		self: &Arc<Self>,
 		entity_id: EntityId,
@@ -86,19 +559,110 @@ impl Inner {
 	where
 		C: Component + 'static,
 	{
-		let type_id = TypeId::of::<C>();
+		let mut value = Some(value);
 
-		self
-			.components
-			.lock()
-			.unwrap()
-			.get_mut(entity_id)
-			.ok_or(SystemError::EntityNotFound(entity_id))?
-			.insert(type_id, Box::new(value) as Box<dyn Any>);
+		match self.try_entity_with_mut::<C, _>(entity_id, |component| {
+			*component = value.take().unwrap();
+		}) {
+			Ok(()) => {
+				let replace_hooks = {
+					let storage = self.read_storage()?;
+
+					Self::hooks_of::<C>(&storage, |hooks| &hooks.on_replace)
+				};
+
+				self.call_hooks(replace_hooks, entity_id);
+
+				return Ok(());
+			}
+			Err(SystemError::ComponentNotFound(..)) => {}
+			Err(err) => return Err(err),
+		}
+
+		let add_hooks = {
+			let mut storage = self.write_storage()?;
+
+			if storage.insert::<C>(entity_id, value.take().unwrap())? {
+				Self::hooks_of::<C>(&storage, |hooks| &hooks.on_add)
+			} else {
+				Vec::new()
+			}
+		};
+
+		self.call_hooks(add_hooks, entity_id);
 
 		Ok(())
 	}
 
+	pub fn entity_has<C>(self: &Arc<Self>, entity_id: EntityId) -> bool
+	where
+		C: Component + 'static,
+	{
+		self.try_entity_has::<C>(entity_id).unwrap()
+	}
+
+	pub fn try_entity_has<C>(self: &Arc<Self>, entity_id: EntityId) -> Result<bool, SystemError>
+	where
+		C: Component + 'static,
+	{
+		self.read_storage()?.has::<C>(entity_id)
+	}
+
+	/// The [`Component::NAME`]s of every component the entity carries.
+	pub fn entity_component_names(
+		self: &Arc<Self>,
+		entity_id: EntityId,
+	) -> impl Iterator<Item = &'static str> {
+		self.try_entity_component_names(entity_id).unwrap()
+	}
+
+	pub fn try_entity_component_names(
+		self: &Arc<Self>,
+		entity_id: EntityId,
+	) -> Result<impl Iterator<Item = &'static str>, SystemError> {
+		Ok(self.read_storage()?.component_names(entity_id)?.into_iter())
+	}
+
+	/// Removes the component from the entity after running its remove hooks, returning its value,
+	/// or `None` if the entity did not carry it.
+	pub fn entity_remove<C>(self: &Arc<Self>, entity_id: EntityId) -> Option<C::Value>
+	where
+		C: Component + 'static,
+	{
+		self.try_entity_remove::<C>(entity_id).unwrap()
+	}
+
+	/// Fails with [`SystemError::RemoveReentrant`] from within a closure accessing the system,
+	/// before any hook runs. [`Commands::remove`] defers the removal instead.
+	pub fn try_entity_remove<C>(
+		self: &Arc<Self>,
+		entity_id: EntityId,
+	) -> Result<Option<C::Value>, SystemError>
+	where
+		C: Component + 'static,
+	{
+		if storage_guard::held(self).is_some() {
+			return Err(SystemError::RemoveReentrant(entity_id, C::NAME));
+		}
+
+		let remove_hooks = {
+			let storage = self.read_storage()?;
+
+			if !storage.has::<C>(entity_id)? {
+				return Ok(None);
+			}
+
+			Self::hooks_of::<C>(&storage, |hooks| &hooks.on_remove)
+		};
+
+		self.call_hooks(remove_hooks, entity_id);
+
+		// the guard is released here, so dropping the value may dispose the entities it references
+		let value = self.write_storage()?.remove::<C>(entity_id)?;
+
+		Ok(value)
+	}
+
 	#[inline]
 	pub fn entity_with<C, R>(
 		self: &Arc<Self>,
@@ -147,24 +711,7 @@ impl Inner {
 	where
 		C: Component + 'static,
 	{
-		let type_id = TypeId::of::<C>();
-
-		Ok(f(self
-			.components
-			.lock()
-			.unwrap()
-			.get(entity_id)
-			.ok_or(SystemError::EntityNotFound(entity_id))?
-			.get(&type_id)
-			.ok_or(SystemError::ComponentNotFound(entity_id, C::NAME))?
-			.downcast_ref::<C::Value>()
-			.unwrap_or_else(|| {
-				panic!(
-					"component {} is of invalid type for entity {}",
-					C::NAME,
-					entity_id
-				)
-			})))
+		Ok(f(&*self.try_entity_get_ref::<C>(entity_id)?))
 	}
 
 	pub fn try_entity_with_or<C, R>(
@@ -176,24 +723,8 @@ impl Inner {
 	where
 		C: Component + 'static,
 	{
-		let type_id = TypeId::of::<C>();
-
-		Ok(f(self
-			.components
-			.lock()
-			.unwrap()
-			.get_mut(entity_id)
-			.ok_or(SystemError::EntityNotFound(entity_id))?
-			.entry(type_id)
-			.or_insert_with(|| Box::new(init()) as Box<dyn Any>)
-			.downcast_ref::<C::Value>()
-			.unwrap_or_else(|| {
-				panic!(
-					"component {} is of invalid type for entity {}",
-					C::NAME,
-					entity_id
-				)
-			})))
+		self.insert_missing::<C>(entity_id, init)?;
+		self.try_entity_with::<C, _>(entity_id, f)
 	}
 
 	#[inline]
@@ -251,6 +782,7 @@ impl Inner {
 			.unwrap()
 	}
 
+	/// Runs `f` with the value borrowed mutably, marking it changed.
 	pub fn try_entity_with_mut<C, R>(
 		self: &Arc<Self>,
 		entity_id: EntityId,
@@ -259,24 +791,7 @@ impl Inner {
 	where
 		C: Component + 'static,
 	{
-		let type_id = TypeId::of::<C>();
-
-		Ok(f(self
-			.components
-			.lock()
-			.unwrap()
-			.get_mut(entity_id)
-			.ok_or(SystemError::EntityNotFound(entity_id))?
-			.get_mut(&type_id)
-			.ok_or(SystemError::ComponentNotFound(entity_id, C::NAME))?
-			.downcast_mut::<C::Value>()
-			.unwrap_or_else(|| {
-				panic!(
-					"component {} is of invalid type for entity {}",
-					C::NAME,
-					entity_id
-				)
-			})))
+		Ok(f(&mut *self.try_entity_get_mut::<C>(entity_id)?))
 	}
 
 	pub fn try_entity_with_mut_or<C, R>(
@@ -288,24 +803,8 @@ impl Inner {
 	where
 		C: Component + 'static,
 	{
-		let type_id = TypeId::of::<C>();
-
-		Ok(f(self
-			.components
-			.lock()
-			.unwrap()
-			.get_mut(entity_id)
-			.ok_or(SystemError::EntityNotFound(entity_id))?
-			.entry(type_id)
-			.or_insert_with(|| Box::new(init()) as Box<dyn Any>)
-			.downcast_mut::<C::Value>()
-			.unwrap_or_else(|| {
-				panic!(
-					"component {} is of invalid type for entity {}",
-					C::NAME,
-					entity_id
-				)
-			})))
+		self.insert_missing::<C>(entity_id, init)?;
+		self.try_entity_with_mut::<C, _>(entity_id, f)
 	}
 
 	#[inline]
@@ -321,6 +820,187 @@ impl Inner {
 		self.try_entity_with_mut_or::<C, _>(entity_id, f, <C::Value as Default>::default)
 	}
 
+	#[inline]
+	pub fn entity_get_ref<C>(self: &Arc<Self>, entity_id: EntityId) -> Ref<'_, C::Value>
+	where
+		C: Component + 'static,
+	{
+		self.try_entity_get_ref::<C>(entity_id).unwrap()
+	}
+
+	/// Borrows the value shared until the returned guard is dropped, like the closure of
+	/// [`try_entity_with`](Self::try_entity_with).
+	#[inline]
+	pub fn try_entity_get_ref<C>(
+		self: &Arc<Self>,
+		entity_id: EntityId,
+	) -> Result<Ref<'_, C::Value>, SystemError>
+	where
+		C: Component + 'static,
+	{
+		self.get_ref_at::<C>(entity_id, None)
+	}
+
+	#[inline]
+	pub fn entity_get_mut<C>(self: &Arc<Self>, entity_id: EntityId) -> RefMut<'_, C::Value>
+	where
+		C: Component + 'static,
+	{
+		self.try_entity_get_mut::<C>(entity_id).unwrap()
+	}
+
+	/// Borrows the value mutably until the returned guard is dropped, marking it changed.
+	#[inline]
+	pub fn try_entity_get_mut<C>(
+		self: &Arc<Self>,
+		entity_id: EntityId,
+	) -> Result<RefMut<'_, C::Value>, SystemError>
+	where
+		C: Component + 'static,
+	{
+		self.get_mut_at::<C>(entity_id, None)
+	}
+
+	#[inline]
+	pub fn component_id<C>(self: &Arc<Self>) -> ComponentId
+	where
+		C: Component + 'static,
+	{
+		self.try_component_id::<C>().unwrap()
+	}
+
+	/// The id of `C` in this system, registering the component if needed, for the `_by_id`
+	/// accessors, which skip looking the component up by type. Ids are only valid for the system
+	/// they were resolved with.
+	pub fn try_component_id<C>(self: &Arc<Self>) -> Result<ComponentId, SystemError>
+	where
+		C: Component + 'static,
+	{
+		if let Some(component_id) = self.read_storage()?.components().id::<C>() {
+			return Ok(component_id);
+		}
+
+		Ok(self.write_storage()?.init_component::<C>())
+	}
+
+	#[inline]
+	pub fn entity_with_by_id<C, R>(
+		self: &Arc<Self>,
+		entity_id: EntityId,
+		component_id: ComponentId,
+		f: impl FnOnce(&C::Value) -> R,
+	) -> R
+	where
+		C: Component + 'static,
+	{
+		self
+			.try_entity_with_by_id::<C, _>(entity_id, component_id, f)
+			.unwrap()
+	}
+
+	/// Like [`try_entity_with`](Self::try_entity_with), failing with
+	/// [`SystemError::InvalidComponentId`] unless `component_id` is the id of `C`, see
+	/// [`component_id`](Self::component_id).
+	pub fn try_entity_with_by_id<C, R>(
+		self: &Arc<Self>,
+		entity_id: EntityId,
+		component_id: ComponentId,
+		f: impl FnOnce(&C::Value) -> R,
+	) -> Result<R, SystemError>
+	where
+		C: Component + 'static,
+	{
+		Ok(f(
+			&*self.try_entity_get_ref_by_id::<C>(entity_id, component_id)?
+		))
+	}
+
+	#[inline]
+	pub fn entity_with_mut_by_id<C, R>(
+		self: &Arc<Self>,
+		entity_id: EntityId,
+		component_id: ComponentId,
+		f: impl FnOnce(&mut C::Value) -> R,
+	) -> R
+	where
+		C: Component + 'static,
+	{
+		self
+			.try_entity_with_mut_by_id::<C, _>(entity_id, component_id, f)
+			.unwrap()
+	}
+
+	pub fn try_entity_with_mut_by_id<C, R>(
+		self: &Arc<Self>,
+		entity_id: EntityId,
+		component_id: ComponentId,
+		f: impl FnOnce(&mut C::Value) -> R,
+	) -> Result<R, SystemError>
+	where
+		C: Component + 'static,
+	{
+		Ok(f(
+			&mut *self.try_entity_get_mut_by_id::<C>(entity_id, component_id)?
+		))
+	}
+
+	#[inline]
+	pub fn entity_get_ref_by_id<C>(
+		self: &Arc<Self>,
+		entity_id: EntityId,
+		component_id: ComponentId,
+	) -> Ref<'_, C::Value>
+	where
+		C: Component + 'static,
+	{
+		self
+			.try_entity_get_ref_by_id::<C>(entity_id, component_id)
+			.unwrap()
+	}
+
+	/// Like [`try_entity_get_ref`](Self::try_entity_get_ref), with the component resolved by
+	/// [`component_id`](Self::component_id). The value is borrowed through the flag of its row in
+	/// the column the id selects, so no map is consulted on the way.
+	#[inline]
+	pub fn try_entity_get_ref_by_id<C>(
+		self: &Arc<Self>,
+		entity_id: EntityId,
+		component_id: ComponentId,
+	) -> Result<Ref<'_, C::Value>, SystemError>
+	where
+		C: Component + 'static,
+	{
+		self.get_ref_at::<C>(entity_id, Some(component_id))
+	}
+
+	#[inline]
+	pub fn entity_get_mut_by_id<C>(
+		self: &Arc<Self>,
+		entity_id: EntityId,
+		component_id: ComponentId,
+	) -> RefMut<'_, C::Value>
+	where
+		C: Component + 'static,
+	{
+		self
+			.try_entity_get_mut_by_id::<C>(entity_id, component_id)
+			.unwrap()
+	}
+
+	/// Like [`try_entity_get_mut`](Self::try_entity_get_mut), see
+	/// [`try_entity_get_ref_by_id`](Self::try_entity_get_ref_by_id).
+	#[inline]
+	pub fn try_entity_get_mut_by_id<C>(
+		self: &Arc<Self>,
+		entity_id: EntityId,
+		component_id: ComponentId,
+	) -> Result<RefMut<'_, C::Value>, SystemError>
+	where
+		C: Component + 'static,
+	{
+		self.get_mut_at::<C>(entity_id, Some(component_id))
+	}
+
 	#[inline]
 	pub fn entity_cast<E>(self: &Arc<Self>, entity_id: EntityId) -> EntityRef<E>
 	where
@@ -329,7 +1009,7 @@ impl Inner {
 		self.try_entity_cast::<E>(entity_id).unwrap()
 	}
 
-	pub(crate) fn try_entity_cast<E>(
+	pub fn try_entity_cast<E>(
 		self: &Arc<Self>,
 		entity_id: EntityId,
 	) -> Result<EntityRef<E>, SystemError>
@@ -337,18 +1017,16 @@ impl Inner {
 		E: Entity + 'static,
 	{
 		let type_id = TypeId::of::<E>();
-		let type_ids = {
-			*self
-				.type_ids
-				.lock()
-				.unwrap()
-				.get(entity_id)
-				.ok_or(SystemError::EntityNotFound(entity_id))?
-		};
+		let type_ids = self
+			.read_storage()?
+			.type_ids(entity_id)
+			.ok_or(SystemError::EntityNotFound(entity_id))?;
 
 		for target_type_id in type_ids {
 			if target_type_id == &type_id {
-				return Ok(EntityRef::new(System(self.clone()), entity_id));
+				self.increment_ref(entity_id);
+
+				return Ok(EntityRef::from_counted(System(self.clone()), entity_id));
 			}
 		}
 
@@ -363,7 +1041,7 @@ impl Inner {
 		self.try_entity_is::<E>(entity_id).unwrap()
 	}
 
-	pub(crate) fn try_entity_is<E>(self: &Arc<Self>, entity_id: EntityId) -> Result<bool, SystemError>
+	pub fn try_entity_is<E>(self: &Arc<Self>, entity_id: EntityId) -> Result<bool, SystemError>
 	where
 		E: Entity + 'static,
 	{
@@ -371,51 +1049,224 @@ impl Inner {
 
 		Ok(
 			self
-				.type_ids
-				.lock()
-				.unwrap()
-				.get(entity_id)
+				.read_storage()?
+				.type_ids(entity_id)
 				.ok_or(SystemError::EntityNotFound(entity_id))?
 				.contains(&type_id),
 		)
 	}
 
-	pub(crate) fn increment_ref(self: &Arc<Self>, entity_id: EntityId) {
+	/// Counts another reference to an entity the caller already holds one to. Does nothing if the
+	/// entity was despawned explicitly, since those references are dangling anyway.
+	pub(crate) fn increment_ref(&self, entity_id: EntityId) {
 		log::trace!("increment_ref: {}", entity_id);
-		log::debug!("entity count: {}", self.ref_counts.lock().unwrap().len());
 
-		let ref_count = self
-			.ref_counts
-			.lock()
-			.unwrap()
-			.get(entity_id)
-			.unwrap_or_else(|| panic!("increment_ref called for non existent entity {}", entity_id))
-			.fetch_add(1, Ordering::Relaxed);
+		if let Some(Some(ref_count)) = self.ref_counts.lock().get_mut(entity_id) {
+			*ref_count += 1;
+		}
+	}
+
+	/// Counts another reference to an entity unless it is already gone or being disposed.
+	fn try_increment_ref(&self, entity_id: EntityId) -> bool {
+		match self.ref_counts.lock().get_mut(entity_id) {
+			Some(Some(ref_count)) => {
+				*ref_count += 1;
 
-		log::debug!("ref_count: {}", ref_count + 1);
+				true
+			}
+			_ => false,
+		}
 	}
 
 	pub(crate) fn decrement_ref(self: &Arc<Self>, entity_id: EntityId) {
 		log::trace!("decrement_ref: {}", entity_id);
 
-		let ref_counts = self.ref_counts.lock().unwrap();
-		let ref_count = ref_counts
-			.get(entity_id)
-			.expect("increment_ref should be called before decrement_ref");
+		let dispose = match self.ref_counts.lock().get_mut(entity_id) {
+			Some(slot @ Some(1)) => {
+				*slot = None;
 
-		if ref_count.fetch_sub(1, Ordering::Release) == 0 {
+				true
+			}
+			Some(Some(ref_count)) => {
+				*ref_count -= 1;
+
+				false
+			}
+			_ => false,
+		};
+
+		if dispose {
 			log::debug!("disposing: {entity_id}");
 
-			fence(Ordering::Acquire);
+			self.dispose_or_defer(entity_id);
+		}
+	}
+
+	/// Disposes the entity, or leaves it to be disposed once this thread lets go of the storage
+	/// if it holds it, e.g. when a value holding the last reference is replaced.
+	fn dispose_or_defer(self: &Arc<Self>, entity_id: EntityId) {
+		if storage_guard::held(self).is_some() {
+			self.pending_disposal.lock().push(entity_id);
+		} else {
+			self.dispose(entity_id);
+		}
+	}
+
+	/// Disposes every entity whose disposal was deferred.
+	pub(crate) fn dispose_pending(self: &Arc<Self>) {
+		loop {
+			let Some(entity_id) = self.pending_disposal.lock().pop() else {
+				break;
+			};
+
+			self.dispose(entity_id);
+		}
+	}
+
+	/// Runs the remove hooks of every component of the entity and reclaims its storage. No lock is
+	/// held while the hooks run, so they may touch other entities, or this one's components. Must
+	/// only be called by whoever took the entity's reference count.
+	fn dispose(self: &Arc<Self>, entity_id: EntityId) {
+		let remove_hooks = self.read_storage().unwrap().remove_hooks(entity_id);
+
+		self.call_hooks(remove_hooks, entity_id);
 
-			if let Some(mut components) = self.components.lock().unwrap().remove(entity_id) {
-				for (type_id, boxed) in components.drain() {
-					// TODO: cleanup
-				}
+		self.write_storage().unwrap().despawn(entity_id);
+		self.observers.write().remove_entity(entity_id);
+		self.ref_counts.lock().remove(entity_id);
+	}
+
+	/// Adds the component unless the entity carries it already.
+	fn insert_missing<C>(
+		self: &Arc<Self>,
+		entity_id: EntityId,
+		init: impl FnOnce() -> C::Value,
+	) -> Result<(), SystemError>
+	where
+		C: Component + 'static,
+	{
+		if self.read_storage()?.has::<C>(entity_id)? {
+			return Ok(());
+		}
+
+		let value = init();
+		let add_hooks = {
+			let mut storage = self.write_storage()?;
+
+			// another thread may have added it in the meantime
+			if storage.has::<C>(entity_id)? {
+				return Ok(());
 			}
 
-			self.type_ids.lock().unwrap().remove(entity_id);
-			self.ref_counts.lock().unwrap().remove(entity_id);
+			storage.insert::<C>(entity_id, value)?;
+
+			Self::hooks_of::<C>(&storage, |hooks| &hooks.on_add)
+		};
+
+		self.call_hooks(add_hooks, entity_id);
+
+		Ok(())
+	}
+
+	/// Locks the storage shared, which is reentrant unless this thread holds it exclusively.
+	/// Borrows the value of `C`, by id if given.
+	fn get_ref_at<C>(
+		self: &Arc<Self>,
+		entity_id: EntityId,
+		component_id: Option<ComponentId>,
+	) -> Result<Ref<'_, C::Value>, SystemError>
+	where
+		C: Component + 'static,
+	{
+		let storage = self.read_storage()?;
+		let cell = match component_id {
+			Some(component_id) => storage.cell_by_id::<C>(entity_id, component_id)?,
+			None => storage.cell::<C>(entity_id)?,
+		};
+		// SAFETY: the row is in bounds of the column
+		let value = unsafe { cell.column.values_ptr::<C::Value>().add(cell.row) };
+		let borrow = cell
+			.column
+			.borrow(BorrowKind::RowRead(cell.row))
+			.ok_or(SystemError::Reentrant(entity_id, C::NAME))?;
+		// SAFETY: the borrow is dropped before the storage, which keeps the column in place
+		let borrow = unsafe { borrow.detach() };
+
+		// SAFETY: the value is borrowed shared, and can not move while the storage is locked
+		Ok(unsafe { Ref::new(storage, borrow, value) })
+	}
+
+	/// Borrows the value of `C` mutably, by id if given, marking it changed.
+	fn get_mut_at<C>(
+		self: &Arc<Self>,
+		entity_id: EntityId,
+		component_id: Option<ComponentId>,
+	) -> Result<RefMut<'_, C::Value>, SystemError>
+	where
+		C: Component + 'static,
+	{
+		let storage = self.read_storage()?;
+		let cell = match component_id {
+			Some(component_id) => storage.cell_by_id::<C>(entity_id, component_id)?,
+			None => storage.cell::<C>(entity_id)?,
+		};
+		// SAFETY: the row is in bounds of the columns
+		let (value, ticks) = unsafe {
+			(
+				cell.column.values_ptr::<C::Value>().add(cell.row),
+				cell.column.ticks_ptr().add(cell.row),
+			)
+		};
+		let borrow = cell
+			.column
+			.borrow(BorrowKind::RowWrite(cell.row))
+			.ok_or(SystemError::Reentrant(entity_id, C::NAME))?;
+		// SAFETY: the borrow is dropped before the storage, which keeps the column in place
+		let borrow = unsafe { borrow.detach() };
+
+		// SAFETY: the value and its ticks are borrowed exclusively, and can not move while the
+		// storage is locked
+		unsafe {
+			(*ticks).changed = storage.next_tick();
+
+			Ok(RefMut::new(storage, borrow, value))
+		}
+	}
+
+	fn read_storage(self: &Arc<Self>) -> Result<StorageRead<'_>, SystemError> {
+		let guard = match storage_guard::held(self) {
+			Some(true) => return Err(SystemError::ReentrantLock),
+			Some(false) => self.storage.read_recursive(),
+			None => self.storage.read(),
+		};
+
+		Ok(StorageGuard::new(self, guard, false))
+	}
+
+	fn write_storage(self: &Arc<Self>) -> Result<StorageWrite<'_>, SystemError> {
+		if storage_guard::held(self).is_some() {
+			return Err(SystemError::ReentrantLock);
+		}
+
+		Ok(StorageGuard::new(self, self.storage.write(), true))
+	}
+
+	fn hooks_of<C>(
+		storage: &Storage,
+		f: impl FnOnce(&ComponentHooks) -> &Vec<ComponentHook>,
+	) -> Vec<ComponentHook>
+	where
+		C: Component + 'static,
+	{
+		storage
+			.hooks::<C>()
+			.map(|hooks| f(hooks).clone())
+			.unwrap_or_default()
+	}
+
+	fn call_hooks(self: &Arc<Self>, hooks: Vec<ComponentHook>, entity_id: EntityId) {
+		if !hooks.is_empty() {
+			call_hooks(hooks, &System(self.clone()), entity_id);
 		}
 	}
 }
@@ -1,6 +1,17 @@
+use std::{
+	sync::{
+		atomic::{AtomicBool, Ordering},
+		mpsc, Arc, Mutex,
+	},
+	thread,
+	time::Duration,
+};
+
+use fnv::FnvHashMap;
+use proptest::{prelude::*, sample::Index};
 use test_log::test;
 
-use crate::{Component, Entity, EntityMethods, EntityRef};
+use crate::{Component, Entity, EntityId, EntityMethods, EntityRef, SystemError};
 
 use super::System;
 
@@ -14,6 +25,15 @@ struct TestEntity2;
 #[derive(Clone, Entity)]
 struct TestEntity3;
 
+#[derive(Clone, Entity)]
+#[require(TestCount = 1, TestComponent3)]
+struct RequiringEntity;
+
+#[derive(Clone, Entity)]
+#[extends(RequiringEntity)]
+#[require(TestCount = 2)]
+struct RequiringEntity2;
+
 struct TestComponent(#[allow(dead_code)] usize);
 
 impl Component for TestComponent {
@@ -26,7 +46,19 @@ impl Component for TestComponent {
 fn system_create() {
 	let system = System::default();
 
-	system.create::<TestEntity>();
+	let entity = system.create::<TestEntity>();
+
+	assert!(system.contains(entity.id));
+	assert_eq!(system.strong_count(entity.id), 1);
+	assert_eq!(system.entity_name(entity.id), Some("TestEntity"));
+	assert_eq!(entity.component_names().count(), 0);
+
+	let id = entity.id;
+
+	drop(entity);
+
+	assert!(!system.contains(id));
+	assert_eq!(system.strong_count(id), 0);
 }
 
 #[test]
@@ -35,7 +67,16 @@ fn system_create_and_set() {
 
 	let entity = system.create::<TestEntity>();
 
-	entity.set::<TestComponent>(TestComponent(0));
+	assert!(!entity.has::<TestComponent>());
+
+	entity.set::<TestComponent>(TestComponent(7));
+
+	assert!(entity.has::<TestComponent>());
+	assert_eq!(entity.with::<TestComponent, _>(|v| v.0), 7);
+	assert_eq!(
+		entity.component_names().collect::<Vec<_>>(),
+		["TestComponent"]
+	);
 }
 
 #[test]
@@ -45,5 +86,676 @@ fn entity_cast() {
 	let entity = system.create::<TestEntity2>();
 
 	let entity: EntityRef<TestEntity> = entity.upcast::<TestEntity>();
-	entity.downcast::<TestEntity2>();
+
+	assert_eq!(system.strong_count(entity.id), 2);
+	assert!(entity.is::<TestEntity2>());
+	assert!(!entity.is::<TestEntity3>());
+
+	// shadowed references are only dropped at the end of the test
+	let entity = entity.downcast::<TestEntity2>();
+
+	assert_eq!(system.strong_count(entity.id), 3);
+	assert!(matches!(
+		entity.try_downcast::<TestEntity3>(),
+		Err(SystemError::InvalidCast(id, "TestEntity3")) if id == entity.id
+	));
+
+	let base = system.create::<TestEntity>();
+
+	assert!(base.try_downcast::<TestEntity2>().is_err());
+	assert!(system.get::<TestEntity2>(base.id).is_none());
+	assert!(system.get::<TestEntity>(entity.id).is_some());
+}
+
+struct TestComponent2(usize);
+
+impl Component for TestComponent2 {
+	const NAME: &str = "TestComponent2";
+
+	type Value = Self;
+}
+
+struct TestComponent3;
+
+impl Component for TestComponent3 {
+	const NAME: &str = "TestComponent3";
+
+	type Value = Vec<usize>;
+}
+
+struct TestCount;
+
+impl Component for TestCount {
+	const NAME: &str = "TestCount";
+
+	type Value = usize;
+}
+
+#[test]
+fn set_moves_between_archetypes() {
+	let system = System::default();
+
+	let entities = (0..4)
+		.map(|i| {
+			let entity = system.create::<TestEntity>();
+
+			entity.set::<TestComponent2>(TestComponent2(i));
+
+			if i % 2 == 0 {
+				entity.set::<TestComponent>(TestComponent(i * 10));
+			}
+
+			entity
+		})
+		.collect::<Vec<_>>();
+
+	for (i, entity) in entities.iter().enumerate() {
+		assert_eq!(entity.with::<TestComponent2, _>(|v| v.0), i);
+		assert_eq!(
+			entity.try_with::<TestComponent, _>(|v| v.0).ok(),
+			(i % 2 == 0).then_some(i * 10)
+		);
+	}
+}
+
+#[test]
+fn set_replaces_in_place() {
+	let system = System::default();
+
+	let entity = system.create::<TestEntity>();
+
+	entity.set::<TestComponent2>(TestComponent2(1));
+	entity.set::<TestComponent2>(TestComponent2(2));
+	entity.with_mut::<TestComponent2, _>(|v| v.0 += 1);
+
+	assert_eq!(entity.with::<TestComponent2, _>(|v| v.0), 3);
+	assert_eq!(
+		entity.with_mut_or::<TestComponent2, _>(|v| v.0, || TestComponent2(0)),
+		3
+	);
+}
+
+#[test]
+fn remove_and_introspect() {
+	let system = System::default();
+	let removed = Arc::new(Mutex::new(Vec::new()));
+
+	{
+		let removed = removed.clone();
+
+		system.on_remove::<TestComponent2>(move |system, entity_id| {
+			let value = system.entity_with::<TestComponent2, _>(entity_id, |v| v.0);
+
+			removed.lock().unwrap().push(value);
+		});
+	}
+
+	let entity = system.create::<TestEntity>();
+
+	entity.set::<TestComponent>(TestComponent(0));
+	entity.set::<TestComponent2>(TestComponent2(1));
+
+	assert!(entity.has::<TestComponent2>());
+	assert_eq!(
+		entity.component_names().collect::<Vec<_>>(),
+		// registering the hook registered TestComponent2 first
+		["TestComponent2", "TestComponent"]
+	);
+	assert_eq!(entity.remove::<TestComponent2>().map(|v| v.0), Some(1));
+	assert!(entity.remove::<TestComponent2>().is_none());
+	assert!(!entity.has::<TestComponent2>());
+	assert_eq!(
+		entity.component_names().collect::<Vec<_>>(),
+		["TestComponent"]
+	);
+	assert_eq!(*removed.lock().unwrap(), [1]);
+}
+
+#[test]
+fn take_leaves_default() {
+	let system = System::default();
+	let entity = system.create::<TestEntity>();
+
+	entity.set::<TestComponent3>(vec![1, 2]);
+
+	assert_eq!(entity.take::<TestComponent3>(), [1, 2]);
+	assert!(entity.get::<TestComponent3>().is_empty());
+	assert!(matches!(
+		entity.try_take::<TestCount>(),
+		Err(SystemError::ComponentNotFound(..))
+	));
+}
+
+#[test]
+fn remove_within_closure_fails() {
+	let system = System::default();
+	let entity = system.create::<TestEntity>();
+
+	entity.set::<TestComponent2>(TestComponent2(1));
+	entity.with::<TestComponent2, _>(|_| {
+		assert!(matches!(
+			entity.try_remove::<TestComponent2>(),
+			Err(SystemError::RemoveReentrant(_, "TestComponent2"))
+		));
+	});
+
+	assert!(entity.has::<TestComponent2>());
+}
+
+#[test]
+fn component_hooks() {
+	let system = System::default();
+	let events = Arc::new(Mutex::new(Vec::new()));
+
+	{
+		let events = events.clone();
+
+		system.on_add::<TestComponent2>(move |system, entity_id| {
+			let value = system.entity_with::<TestComponent2, _>(entity_id, |v| v.0);
+
+			events.lock().unwrap().push(("add", value));
+		});
+	}
+
+	{
+		let events = events.clone();
+
+		system.on_replace::<TestComponent2>(move |system, entity_id| {
+			let value = system.entity_with::<TestComponent2, _>(entity_id, |v| v.0);
+
+			events.lock().unwrap().push(("replace", value));
+		});
+	}
+
+	{
+		let events = events.clone();
+
+		system.on_remove::<TestComponent2>(move |system, entity_id| {
+			let value = system.entity_with::<TestComponent2, _>(entity_id, |v| v.0);
+
+			events.lock().unwrap().push(("remove", value));
+		});
+	}
+
+	let entity = system.create::<TestEntity>();
+
+	entity.set::<TestComponent2>(TestComponent2(1));
+	entity.set::<TestComponent2>(TestComponent2(2));
+	entity.with_mut::<TestComponent2, _>(|v| v.0 = 3);
+
+	// a replace that fails does not run the hooks
+	entity.with::<TestComponent2, _>(|_| {
+		assert!(matches!(
+			system.try_entity_set::<TestComponent2>(entity.id, TestComponent2(4)),
+			Err(SystemError::Reentrant(..))
+		));
+	});
+
+	let clone = entity.clone();
+
+	drop(entity);
+
+	assert_eq!(*events.lock().unwrap(), vec![("add", 1), ("replace", 2)]);
+
+	drop(clone);
+
+	assert_eq!(
+		*events.lock().unwrap(),
+		vec![("add", 1), ("replace", 2), ("remove", 3)]
+	);
+}
+
+#[test]
+fn drain_removed() {
+	let system = System::default();
+
+	system.track_removed::<TestComponent2>();
+
+	let entity = system.create::<TestEntity>();
+	let entity_id = entity.id;
+
+	entity.set::<TestComponent2>(TestComponent2(0));
+	drop(entity);
+
+	assert_eq!(system.drain_removed::<TestComponent2>(), vec![entity_id]);
+	assert_eq!(system.drain_removed::<TestComponent2>(), vec![]);
+	assert_eq!(system.drain_removed::<TestComponent>(), vec![]);
+}
+
+#[test]
+fn required_components() {
+	let system = System::default();
+
+	let entity = system.create::<RequiringEntity>();
+	assert_eq!(entity.get::<TestCount>(), 1);
+	assert_eq!(entity.get::<TestComponent3>(), Vec::<usize>::new());
+
+	let entity = system.create::<RequiringEntity2>();
+	assert_eq!(entity.get::<TestCount>(), 2);
+	assert!(entity.has::<TestComponent3>());
+
+	let mut commands = system.commands();
+	let entity = commands.spawn::<RequiringEntity2>();
+	commands.apply();
+	assert_eq!(entity.get::<TestCount>(), 2);
+
+	assert_eq!(RequiringEntity2::DEPTH, 2);
+}
+
+#[test]
+fn nested_access() {
+	let system = System::default();
+
+	let a = system.create::<TestEntity>();
+	let b = system.create::<TestEntity>();
+
+	a.set::<TestComponent2>(TestComponent2(1));
+	b.set::<TestComponent2>(TestComponent2(2));
+
+	a.with_mut::<TestComponent2, _>(|a_value| {
+		b.with_mut::<TestComponent2, _>(|b_value| {
+			a_value.0 += b_value.0;
+			b_value.0 = 0;
+		});
+
+		// a value borrowed shared by this thread may be borrowed shared again
+		b.with::<TestComponent2, _>(|_| b.with::<TestComponent2, _>(|_| ()));
+
+		// and the same entity may be set, as long as it already carries the component
+		b.set::<TestComponent2>(TestComponent2(5));
+	});
+
+	assert_eq!(a.with::<TestComponent2, _>(|v| v.0), 3);
+	assert_eq!(b.with::<TestComponent2, _>(|v| v.0), 5);
+}
+
+#[test]
+fn reentrant_access_fails() {
+	let system = System::default();
+
+	let entity = system.create::<TestEntity>();
+	let other = system.create::<TestEntity>();
+
+	entity.set::<TestComponent2>(TestComponent2(0));
+
+	entity.with::<TestComponent2, _>(|_| {
+		assert!(matches!(
+			entity.try_with_mut::<TestComponent2, _>(|_| ()),
+			Err(SystemError::Reentrant(..))
+		));
+		assert!(matches!(
+			system.try_create::<TestEntity>(),
+			Err(SystemError::ReentrantLock)
+		));
+		assert!(matches!(
+			system.try_entity_set::<TestComponent2>(other.id, TestComponent2(0)),
+			Err(SystemError::ReentrantLock)
+		));
+		assert!(matches!(
+			system.try_on_add::<TestComponent2>(|_, _| ()),
+			Err(SystemError::ReentrantLock)
+		));
+		assert!(matches!(
+			system.try_on_replace::<TestComponent2>(|_, _| ()),
+			Err(SystemError::ReentrantLock)
+		));
+		assert!(matches!(
+			system.try_on_remove::<TestComponent2>(|_, _| ()),
+			Err(SystemError::ReentrantLock)
+		));
+		assert!(matches!(
+			system.try_track_removed::<TestComponent2>(),
+			Err(SystemError::ReentrantLock)
+		));
+		assert!(matches!(
+			system.try_drain_removed::<TestComponent2>(),
+			Err(SystemError::ReentrantLock)
+		));
+	});
+
+	let mut query = system.query::<&mut TestComponent2>();
+
+	for _ in query.iter() {
+		assert!(matches!(
+			entity.try_with::<TestComponent2, _>(|_| ()),
+			Err(SystemError::Reentrant(..))
+		));
+		assert!(matches!(
+			system.try_query_filtered::<&TestComponent2, ()>(),
+			Err(SystemError::ReentrantQuery("TestComponent2"))
+		));
+		assert!(matches!(
+			system.try_create::<TestEntity>(),
+			Err(SystemError::ReentrantLock)
+		));
+	}
+}
+
+#[test]
+fn dropping_last_ref_within_closure() {
+	let system = System::default();
+
+	let entity = system.create::<TestEntity>();
+	let other = system.create::<TestEntity>();
+	let other_id = other.id;
+
+	entity.set::<TestComponent2>(TestComponent2(0));
+	entity.with::<TestComponent2, _>(move |_| drop(other));
+
+	assert!(!system.contains(other_id));
+}
+
+#[test]
+fn stress() {
+	const THREADS: usize = 8;
+	const ITERATIONS: usize = 500;
+
+	let system = System::default();
+	let entities = (0..4)
+		.map(|_| {
+			let entity = system.create::<TestEntity>();
+
+			entity.set::<TestComponent2>(TestComponent2(0));
+			entity
+		})
+		.collect::<Vec<_>>();
+
+	let threads = (0..THREADS)
+		.map(|thread| {
+			let system = system.clone();
+			let entities = entities.clone();
+
+			thread::spawn(move || {
+				for i in 0..ITERATIONS {
+					let index = (thread + i) % entities.len();
+
+					entities[index].with_mut::<TestComponent2, _>(|value| {
+						value.0 += 1;
+
+						// reading another entity while holding this one, which other threads may
+						// hold in turn, always in the same order so threads can not wait on each
+						// other in a cycle
+						if let Some(next) = entities.get(index + 1) {
+							next.with::<TestComponent2, _>(|v| v.0);
+						}
+					});
+
+					let temporary = system.create::<TestEntity>();
+
+					temporary.set::<TestComponent2>(TestComponent2(i));
+					drop(temporary);
+				}
+			})
+		})
+		.collect::<Vec<_>>();
+
+	for thread in threads {
+		thread.join().unwrap();
+	}
+
+	let total = entities
+		.iter()
+		.map(|entity| entity.with::<TestComponent2, _>(|v| v.0))
+		.sum::<usize>();
+
+	assert_eq!(total, THREADS * ITERATIONS);
+	assert_eq!(
+		system.query::<&TestComponent2>().iter().count(),
+		entities.len()
+	);
+}
+
+/// An operation of [`model`], on the reference at an index of the references held so far.
+#[derive(Clone, Debug)]
+enum Op {
+	Create(bool),
+	Clone(Index),
+	Drop(Index),
+	Set(Index, usize),
+	Remove(Index),
+	With(Index),
+	Downcast(Index),
+	WithDisposed(Index),
+}
+
+fn op() -> impl Strategy<Value = Op> {
+	prop_oneof![
+		any::<bool>().prop_map(Op::Create),
+		any::<Index>().prop_map(Op::Clone),
+		any::<Index>().prop_map(Op::Drop),
+		(any::<Index>(), any::<usize>()).prop_map(|(i, v)| Op::Set(i, v)),
+		any::<Index>().prop_map(Op::Remove),
+		any::<Index>().prop_map(Op::With),
+		any::<Index>().prop_map(Op::Downcast),
+		any::<Index>().prop_map(Op::WithDisposed),
+	]
+}
+
+/// What the system should hold for an entity.
+#[derive(Default)]
+struct ModelEntity {
+	extended: bool,
+	ref_count: usize,
+	value: Option<usize>,
+}
+
+proptest! {
+	/// Random operations on a system agree with a map of entities, their reference counts and the
+	/// value of one component.
+	#[test]
+	fn model(ops in prop::collection::vec(op(), 1..64)) {
+		let system = System::default();
+		let mut refs = Vec::<EntityRef<TestEntity>>::new();
+		let mut model = FnvHashMap::<EntityId, ModelEntity>::default();
+		let mut disposed = Vec::<EntityId>::new();
+
+		for op in ops {
+			match op {
+				Op::Create(extended) => {
+					let entity = if extended {
+						system.create::<TestEntity2>().upcast::<TestEntity>()
+					} else {
+						system.create::<TestEntity>()
+					};
+
+					model.insert(entity.id, ModelEntity { extended, ref_count: 1, value: None });
+					refs.push(entity);
+				}
+				Op::Clone(i) if !refs.is_empty() => {
+					let entity = refs[i.index(refs.len())].clone();
+
+					model.get_mut(&entity.id).unwrap().ref_count += 1;
+					refs.push(entity);
+				}
+				Op::Drop(i) if !refs.is_empty() => {
+					let entity = refs.swap_remove(i.index(refs.len()));
+					let model_entity = model.get_mut(&entity.id).unwrap();
+
+					model_entity.ref_count -= 1;
+
+					if model_entity.ref_count == 0 {
+						model.remove(&entity.id);
+						disposed.push(entity.id);
+					}
+				}
+				Op::Set(i, value) if !refs.is_empty() => {
+					let entity = &refs[i.index(refs.len())];
+
+					prop_assert!(system.try_entity_set::<TestCount>(entity.id, value).is_ok());
+					model.get_mut(&entity.id).unwrap().value = Some(value);
+				}
+				Op::Remove(i) if !refs.is_empty() => {
+					let entity = &refs[i.index(refs.len())];
+					let removed = system.try_entity_remove::<TestCount>(entity.id).unwrap();
+
+					prop_assert_eq!(removed, model.get_mut(&entity.id).unwrap().value.take());
+				}
+				Op::With(i) if !refs.is_empty() => {
+					let entity = &refs[i.index(refs.len())];
+					let result = system.try_entity_with::<TestCount, _>(entity.id, |v| *v);
+
+					match model[&entity.id].value {
+						Some(value) => prop_assert_eq!(result.unwrap(), value),
+						None => prop_assert!(matches!(
+							result,
+							Err(SystemError::ComponentNotFound(id, "TestCount")) if id == entity.id
+						)),
+					}
+				}
+				Op::Downcast(i) if !refs.is_empty() => {
+					let entity = &refs[i.index(refs.len())];
+					let result = entity.try_downcast::<TestEntity2>();
+					let model_entity = model.get_mut(&entity.id).unwrap();
+
+					if model_entity.extended {
+						model_entity.ref_count += 1;
+						refs.push(result.unwrap().upcast());
+					} else {
+						prop_assert!(matches!(
+							result,
+							Err(SystemError::InvalidCast(id, "TestEntity2")) if id == entity.id
+						));
+					}
+				}
+				Op::WithDisposed(i) if !disposed.is_empty() => {
+					let id = disposed[i.index(disposed.len())];
+
+					prop_assert!(matches!(
+						system.try_entity_with::<TestCount, _>(id, |v| *v),
+						Err(SystemError::EntityNotFound(v)) if v == id
+					));
+					prop_assert!(system.get::<TestEntity>(id).is_none());
+				}
+				_ => {}
+			}
+
+			for (&id, model_entity) in &model {
+				prop_assert!(system.contains(id));
+				prop_assert_eq!(system.strong_count(id), model_entity.ref_count);
+				prop_assert_eq!(system.entity_is::<TestEntity2>(id), model_entity.extended);
+			}
+
+			for &id in &disposed {
+				prop_assert!(!system.contains(id));
+				prop_assert_eq!(system.strong_count(id), 0);
+			}
+		}
+	}
+}
+
+#[test]
+fn component_id_access() {
+	let system = System::default();
+	let entity = system.create::<TestEntity>();
+	let component_id = system.component_id::<TestCount>();
+
+	assert_eq!(system.component_id::<TestCount>(), component_id);
+	assert!(matches!(
+		system.try_entity_with_by_id::<TestCount, _>(entity.id, component_id, |v| *v),
+		Err(SystemError::ComponentNotFound(_, "TestCount"))
+	));
+
+	entity.set::<TestCount>(1);
+	entity.with_mut_by_id::<TestCount, _>(component_id, |v| *v += 1);
+
+	assert_eq!(entity.with_by_id::<TestCount, _>(component_id, |v| *v), 2);
+
+	let other = system.create::<TestEntity>();
+
+	other.set::<TestCount>(10);
+
+	{
+		// rows of the same column are borrowed independently
+		let mut count = system.entity_get_mut_by_id::<TestCount>(entity.id, component_id);
+		let other_count = system.entity_get_ref_by_id::<TestCount>(other.id, component_id);
+
+		*count += *other_count;
+
+		assert!(matches!(
+			system.try_entity_get_ref_by_id::<TestCount>(entity.id, component_id),
+			Err(SystemError::Reentrant(_, "TestCount"))
+		));
+		assert!(matches!(
+			system.try_entity_get_mut_by_id::<TestCount>(other.id, component_id),
+			Err(SystemError::Reentrant(_, "TestCount"))
+		));
+	}
+
+	assert_eq!(entity.get::<TestCount>(), 12);
+
+	let other_id = system.component_id::<TestComponent2>();
+
+	assert!(matches!(
+		system.try_entity_with_by_id::<TestCount, _>(entity.id, other_id, |v| *v),
+		Err(SystemError::InvalidComponentId("TestCount"))
+	));
+}
+
+#[test]
+fn borrows_across_threads() {
+	let system = System::default();
+	let entity = system.create::<TestEntity>();
+
+	entity.set::<TestCount>(1);
+
+	let count = entity.get_ref::<TestCount>();
+	let (tx, rx) = mpsc::channel();
+	let written = AtomicBool::new(false);
+
+	thread::scope(|scope| {
+		// shared borrows of the same value by other threads do not wait
+		scope.spawn(|| tx.send(*entity.get_ref::<TestCount>()).unwrap());
+
+		assert_eq!(rx.recv_timeout(Duration::from_secs(5)), Ok(1));
+
+		scope.spawn(|| {
+			*entity.get_mut::<TestCount>() += 1;
+			written.store(true, Ordering::SeqCst);
+		});
+
+		thread::sleep(Duration::from_millis(50));
+
+		// a mutable borrow waits for the shared one to be dropped
+		assert!(!written.load(Ordering::SeqCst));
+		assert_eq!(*count, 1);
+
+		drop(count);
+	});
+
+	assert_eq!(*entity.get_ref::<TestCount>(), 2);
+}
+
+#[test]
+fn ref_guards() {
+	let system = System::default();
+	let entity = system.create::<TestEntity>();
+	let other = system.create::<TestEntity>();
+
+	entity.set::<TestCount>(1);
+	other.set::<TestCount>(10);
+
+	{
+		let mut count = entity.get_mut::<TestCount>();
+
+		*count += *other.get_ref::<TestCount>();
+
+		assert!(matches!(
+			entity.try_get_ref::<TestCount>(),
+			Err(SystemError::Reentrant(_, "TestCount"))
+		));
+		assert!(matches!(
+			system.try_create::<TestEntity>(),
+			Err(SystemError::ReentrantLock)
+		));
+	}
+
+	let count = entity.get_ref::<TestCount>();
+
+	assert_eq!(*count, 11);
+	assert_eq!(*entity.get_ref::<TestCount>(), 11);
+	assert!(entity.try_get_mut::<TestCount>().is_err());
+	assert!(matches!(
+		entity.try_get_ref::<TestComponent2>(),
+		Err(SystemError::ComponentNotFound(_, "TestComponent2"))
+	));
 }
@@ -8,4 +8,22 @@ pub enum SystemError {
 	ComponentNotFound(EntityId, &'static str),
 	#[error("invalid cast to {1} for entity {0}")]
 	InvalidCast(EntityId, &'static str),
+	#[error("component id is not the id of {0} in this system")]
+	InvalidComponentId(&'static str),
+	#[error("resource {0} not found")]
+	ResourceNotFound(&'static str),
+	#[error("component {1} of entity {0} is already borrowed by this thread")]
+	Reentrant(EntityId, &'static str),
+	#[error("component {0} can not be queried while this thread borrows it")]
+	ReentrantQuery(&'static str),
+	#[error("resource {0} is already borrowed by this thread")]
+	ReentrantResource(&'static str),
+	#[error("entity {0} can not become a child of itself or of its descendant {1}")]
+	HierarchyCycle(EntityId, EntityId),
+	#[error(
+		"component {1} can not be removed from entity {0} while this thread is accessing the system"
+	)]
+	RemoveReentrant(EntityId, &'static str),
+	#[error("entities can not be created, disposed or change components while this thread is accessing the system")]
+	ReentrantLock,
 }
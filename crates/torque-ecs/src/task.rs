@@ -0,0 +1,99 @@
+use std::fmt;
+
+use crate::{query::Access, Query, QueryData, QueryFilter, System};
+
+/// A function run by a [`Schedule`](crate::Schedule), along with the components it accesses and
+/// the tasks of its stage it has to run before or after.
+pub struct Task {
+	name: &'static str,
+	access: Access,
+	before: Vec<&'static str>,
+	after: Vec<&'static str>,
+	run: Box<dyn FnMut(&System) + Send>,
+}
+
+impl Task {
+	/// A task running `run`. It is assumed to access no components unless declared with
+	/// [`access`](Self::access), so it may run in parallel with any other task.
+	pub fn new(name: &'static str, run: impl FnMut(&System) + Send + 'static) -> Self {
+		Self {
+			name,
+			access: Access::default(),
+			before: Vec::new(),
+			after: Vec::new(),
+			run: Box::new(run),
+		}
+	}
+
+	/// A task running `run` with a query of `Q` filtered by `F`, accessing what the query does.
+	pub fn query<Q, F>(
+		name: &'static str,
+		mut run: impl for<'s> FnMut(Query<'s, Q, F>) + Send + 'static,
+	) -> Self
+	where
+		Q: QueryData + 'static,
+		F: QueryFilter + 'static,
+	{
+		let mut task =
+			Self::new(name, move |system| run(system.query_filtered::<Q, F>())).access::<Q>();
+
+		task.access.extend(&Access::of_filter::<F>());
+
+		task
+	}
+
+	/// Declares the task accesses the components of `Q` as a query of it would.
+	pub fn access<Q>(mut self) -> Self
+	where
+		Q: QueryData,
+	{
+		self.access.extend(&Access::of::<Q>());
+
+		self
+	}
+
+	/// Runs the task before the task named `name` of the same stage.
+	pub fn before(mut self, name: &'static str) -> Self {
+		self.before.push(name);
+
+		self
+	}
+
+	/// Runs the task after the task named `name` of the same stage.
+	pub fn after(mut self, name: &'static str) -> Self {
+		self.after.push(name);
+
+		self
+	}
+
+	#[inline]
+	pub fn name(&self) -> &'static str {
+		self.name
+	}
+
+	#[inline]
+	pub(crate) fn access_of(&self) -> &Access {
+		&self.access
+	}
+
+	#[inline]
+	pub(crate) fn ordering(&self) -> (&[&'static str], &[&'static str]) {
+		(&self.before, &self.after)
+	}
+
+	#[inline]
+	pub(crate) fn run(&mut self, system: &System) {
+		(self.run)(system)
+	}
+}
+
+impl fmt::Debug for Task {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("Task")
+			.field("name", &self.name)
+			.field("access", &self.access)
+			.field("before", &self.before)
+			.field("after", &self.after)
+			.finish_non_exhaustive()
+	}
+}
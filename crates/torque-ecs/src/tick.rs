@@ -0,0 +1,40 @@
+/// A point in the change history of a [`System`](crate::System). Every write to a component is
+/// stamped with a tick greater than any tick handed out before it, so "changed since `tick`" is a
+/// plain comparison.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Tick(u64);
+
+impl Tick {
+	pub const ZERO: Self = Self(0);
+
+	#[inline]
+	pub(crate) fn new(value: u64) -> Self {
+		Self(value)
+	}
+
+	#[inline]
+	pub fn get(self) -> u64 {
+		self.0
+	}
+
+	#[inline]
+	pub fn is_newer_than(self, other: Tick) -> bool {
+		self.0 > other.0
+	}
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct ComponentTicks {
+	pub added: Tick,
+	pub changed: Tick,
+}
+
+impl ComponentTicks {
+	#[inline]
+	pub fn new(tick: Tick) -> Self {
+		Self {
+			added: tick,
+			changed: tick,
+		}
+	}
+}
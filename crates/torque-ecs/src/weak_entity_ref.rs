@@ -1,7 +1,13 @@
-use std::marker::PhantomData;
+#[cfg(test)]
+mod tests;
 
-use crate::{Entity, EntityId, EntityRef, System};
+use std::{marker::PhantomData, sync::Arc};
 
+use crate::{Entity, EntityId, EntityRef, System, SystemError};
+
+/// A reference that does not keep the entity alive. Entity ids are generational, so once the
+/// entity is gone a weak reference never upgrades again, even after its slot is reused by a new
+/// entity.
 pub struct WeakEntityRef<E>
 where
 	E: Entity,
@@ -36,7 +42,53 @@ where
 		}
 	}
 
+	#[inline]
+	pub fn id(&self) -> EntityId {
+		self.id
+	}
+
+	#[cfg(feature = "v8")]
+	#[inline]
+	pub(crate) fn system(&self) -> &System {
+		&self.system
+	}
+
+	/// `None` once the entity is gone, or while this thread holds the system exclusively, see
+	/// [`try_upgrade`](Self::try_upgrade).
+	#[inline]
 	pub fn upgrade(&self) -> Option<EntityRef<E>> {
 		self.system.get(self.id)
 	}
+
+	pub fn try_upgrade(&self) -> Result<Option<EntityRef<E>>, SystemError> {
+		self.system.try_get(self.id)
+	}
+
+	/// Whether the entity exists and is not being disposed, i.e. whether `upgrade` would succeed
+	/// right now.
+	pub fn is_alive(&self) -> bool {
+		self.system.contains(self.id)
+	}
+
+	/// The number of [`EntityRef`]s keeping the entity alive.
+	pub fn strong_count(&self) -> usize {
+		self.system.strong_count(self.id)
+	}
+
+	/// Whether both refer to the same entity of the same system.
+	pub fn ptr_eq(&self, other: &Self) -> bool {
+		Arc::ptr_eq(&self.system, &other.system) && self.id == other.id
+	}
+}
+
+#[cfg(feature = "v8")]
+impl<E> v8::cppgc::GarbageCollected for WeakEntityRef<E>
+where
+	E: Entity + 'static,
+{
+	fn trace(&self, _visitor: &v8::cppgc::Visitor) {}
+
+	fn get_name(&self) -> Option<&'static std::ffi::CStr> {
+		Some(c"WeakEntityRef")
+	}
 }
@@ -0,0 +1,97 @@
+use test_log::test;
+
+use crate::{Component, Entity, EntityMethods, System};
+
+#[derive(Entity)]
+struct WeakRefEntity;
+
+struct Count;
+
+impl Component for Count {
+	const NAME: &str = "Count";
+
+	type Value = usize;
+}
+
+#[test]
+fn upgrade_never_resolves_reused_slot() {
+	let system = System::default();
+
+	let entity = system.create::<WeakRefEntity>();
+	let weak = entity.clone().downgrade();
+
+	drop(entity);
+
+	// the freed slot is handed out again, with a new generation
+	let reused = system.create::<WeakRefEntity>();
+
+	assert_ne!(reused.id, weak.id());
+	assert!(!weak.is_alive());
+	assert!(weak.upgrade().is_none());
+
+	let weak = reused.clone().downgrade();
+
+	system.despawn(reused.id);
+
+	let reused = system.create::<WeakRefEntity>();
+
+	assert_ne!(reused.id, weak.id());
+	assert!(weak.upgrade().is_none());
+}
+
+#[test]
+fn strong_count() {
+	let system = System::default();
+
+	let entity = system.create::<WeakRefEntity>();
+	let weak = entity.clone().downgrade();
+
+	assert_eq!(weak.strong_count(), 1);
+
+	let clone = entity.clone();
+	let upgraded = weak.upgrade().unwrap();
+
+	assert_eq!(weak.strong_count(), 3);
+
+	drop((entity, clone, upgraded));
+
+	assert_eq!(weak.strong_count(), 0);
+	assert!(!weak.is_alive());
+}
+
+#[test]
+fn ptr_eq() {
+	let system = System::default();
+	let other_system = System::default();
+
+	let a = system.create::<WeakRefEntity>();
+	let b = system.create::<WeakRefEntity>();
+	let c = other_system.create::<WeakRefEntity>();
+
+	let weak_a = a.clone().downgrade();
+
+	assert!(weak_a.ptr_eq(&a.clone().downgrade()));
+	assert!(!weak_a.ptr_eq(&b.clone().downgrade()));
+	assert!(!weak_a.ptr_eq(&c.clone().downgrade()));
+}
+
+#[test]
+fn upgrade_within_query() {
+	let system = System::default();
+	let entity = system.create::<WeakRefEntity>();
+	let weak = entity.clone().downgrade();
+
+	entity.set::<Count>(1);
+
+	for (entity_id, count) in system.query::<&mut Count>().iter() {
+		let upgraded = weak.try_upgrade().unwrap().unwrap();
+
+		*count += 1;
+
+		assert_eq!(upgraded.id, entity_id);
+		assert!(system.entity_is::<WeakRefEntity>(entity_id));
+		assert!(system.try_entity_cast::<WeakRefEntity>(entity_id).is_ok());
+	}
+
+	assert_eq!(entity.get::<Count>(), 2);
+}
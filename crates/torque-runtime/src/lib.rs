@@ -1,11 +1,13 @@
 mod console;
 mod runtime;
+mod runtime_builder;
 mod runtime_error;
 mod runtime_event;
 mod runtime_handle;
 mod thread;
 mod thread_context;
 mod thread_handle;
+mod timers;
 mod window;
 
 use std::any::Any;
@@ -17,6 +19,7 @@ pub type BoxSendSyncAny = Box<dyn Any + Send + Sync + 'static>;
 
 pub use self::{
 	runtime::Runtime,
+	runtime_builder::RuntimeBuilder,
 	runtime_error::RuntimeError,
 	runtime_handle::RuntimeHandle,
 	thread::{Thread, ThreadEvent},
@@ -26,11 +29,11 @@ pub use self::{
 };
 
 pub(crate) use self::{
-	runtime::{with_event_loop, with_platform, with_spawner, with_threads},
+	runtime::{with_event_loop, with_platform, with_spawner, with_threads, with_windows},
 	runtime_event::RuntimeEvent,
 };
 
 // this enables short qualified references to all winit types, much like wgpu
 pub(crate) mod winit {
-	pub use ::winit::{application::*, event::*, event_loop::*, window::*};
+	pub use ::winit::{application::*, dpi::*, error::*, event::*, event_loop::*, window::*};
 }
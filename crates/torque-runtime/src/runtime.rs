@@ -1,16 +1,28 @@
-use std::{future::Future, sync::OnceLock, thread::ThreadId};
+use std::{
+	future::Future,
+	sync::{Arc, OnceLock},
+	thread::ThreadId,
+};
 
 use fnv::FnvHashMap;
-use futures::executor::{LocalPool, LocalSpawner};
+use futures::{
+	channel::mpsc,
+	executor::{LocalPool, LocalSpawner},
+	StreamExt,
+};
 use scoped_tls_hkt::scoped_thread_local;
 use tracing::trace;
 
-use crate::{winit, RuntimeError, RuntimeEvent, RuntimeHandle, Thread, ThreadHandle};
+use crate::{
+	runtime_builder::IsolateOptions, winit, RuntimeBuilder, RuntimeError, RuntimeEvent,
+	RuntimeHandle, Thread, ThreadEvent, ThreadHandle,
+};
 
 scoped_thread_local!(static PLATFORM: v8::SharedRef<v8::Platform>);
 scoped_thread_local!(static EVENT_LOOP: winit::ActiveEventLoop);
 scoped_thread_local!(static SPAWNER: LocalSpawner);
 scoped_thread_local!(static mut THREADS: FnvHashMap<ThreadId, Thread>);
+scoped_thread_local!(static mut WINDOWS: FnvHashMap<winit::WindowId, ThreadId>);
 
 pub fn with_platform<R>(f: impl FnOnce(&v8::SharedRef<v8::Platform>) -> R) -> R {
 	PLATFORM.with(f)
@@ -28,19 +40,20 @@ pub fn with_threads<R>(f: impl FnOnce(&mut FnvHashMap<ThreadId, Thread>) -> R) -
 	THREADS.with(f)
 }
 
+/// The thread each window was created by, which its events are sent to.
+pub fn with_windows<R>(f: impl FnOnce(&mut FnvHashMap<winit::WindowId, ThreadId>) -> R) -> R {
+	WINDOWS.with(f)
+}
+
 pub struct Runtime {
 	platform: v8::SharedRef<v8::Platform>,
 	local_pool: LocalPool,
 	threads: FnvHashMap<ThreadId, Thread>,
-	event_loop_proxy: winit::EventLoopProxy<RuntimeEvent>,
+	windows: FnvHashMap<winit::WindowId, ThreadId>,
 }
 
 impl Runtime {
-	fn new(
-		platform: v8::SharedRef<v8::Platform>,
-		event_loop_proxy: winit::EventLoopProxy<RuntimeEvent>,
-		main_thread: Thread,
-	) -> Self {
+	fn new(platform: v8::SharedRef<v8::Platform>, main_thread: Thread) -> Self {
 		let local_pool = LocalPool::new();
 		let mut threads: FnvHashMap<ThreadId, Thread> = Default::default();
 
@@ -50,15 +63,19 @@ impl Runtime {
 			platform,
 			local_pool,
 			threads,
-			event_loop_proxy,
+			windows: Default::default(),
 		}
 	}
 
+	pub fn builder() -> RuntimeBuilder {
+		RuntimeBuilder::default()
+	}
+
 	pub fn run_sync<R>(f: impl FnOnce() -> R + Send + Sync + 'static) -> Result<R, RuntimeError>
 	where
 		R: Send + Sync + 'static,
 	{
-		Self::run(|| async { f() })
+		Self::builder().run_sync(f)
 	}
 
 	pub fn run<Fut, R>(f: impl FnOnce() -> Fut + Send + Sync + 'static) -> Result<R, RuntimeError>
@@ -66,24 +83,23 @@ impl Runtime {
 		Fut: Future<Output = R> + Send + Sync + 'static,
 		R: Send + Sync + 'static,
 	{
-		#[cfg(feature = "tracing-subscriber")]
-		{
-			let subscriber = tracing_subscriber::FmtSubscriber::builder()
-				.with_thread_names(true)
-				.with_max_level(tracing::Level::TRACE)
-				.finish();
-
-			tracing::subscriber::set_global_default(subscriber)
-				.expect("setting default subscriber failed");
-		}
+		Self::builder().run(f)
+	}
 
+	/// Initializes V8 on the first call, returning the same platform on later ones whatever they are
+	/// passed.
+	pub(crate) fn platform(worker_threads: u32, v8_flags: &str) -> v8::SharedRef<v8::Platform> {
 		static PLATFORM: OnceLock<v8::SharedRef<v8::Platform>> = OnceLock::new();
 
-		let platform = PLATFORM
+		PLATFORM
 			.get_or_init(|| {
 				trace!("initializing v8 platform");
 
-				let platform = v8::new_default_platform(0, false).make_shared();
+				if !v8_flags.is_empty() {
+					v8::V8::set_flags_from_string(v8_flags);
+				}
+
+				let platform = v8::new_default_platform(worker_threads, false).make_shared();
 
 				v8::V8::initialize_platform(platform.clone());
 				v8::V8::initialize();
@@ -91,39 +107,89 @@ impl Runtime {
 
 				platform
 			})
-			.clone();
+			.clone()
+	}
 
-		let event_loop = winit::EventLoop::<RuntimeEvent>::with_user_event()
-			.build()
-			.unwrap();
+	/// Runs the event loop of winit until every thread has exited.
+	pub(crate) fn run_winit<Fut, R>(
+		platform: v8::SharedRef<v8::Platform>,
+		isolate: Arc<IsolateOptions>,
+		f: impl FnOnce() -> Fut + Send + Sync + 'static,
+	) -> Result<R, RuntimeError>
+	where
+		Fut: Future<Output = R> + Send + Sync + 'static,
+		R: Send + Sync + 'static,
+	{
+		let event_loop = winit::EventLoop::<RuntimeEvent>::with_user_event().build()?;
 
 		event_loop.set_control_flow(winit::ControlFlow::Poll);
 
-		let event_loop_proxy = event_loop.create_proxy();
-		let runtime_handle = RuntimeHandle::new(event_loop_proxy.clone());
+		let runtime_handle = RuntimeHandle::new(event_loop.create_proxy(), isolate);
 
 		let (main_thread, join_handle) = Thread::new(platform.clone(), runtime_handle, f);
 		let thread_handle = ThreadHandle::<R>::new(main_thread.clone(), join_handle);
 
-		let mut app = Runtime::new(platform, event_loop_proxy, main_thread);
+		let mut app = Runtime::new(platform, main_thread);
 
-		event_loop.run_app(&mut app).unwrap();
+		event_loop.run_app(&mut app)?;
+
+		thread_handle.join()
+	}
+
+	/// Runs a loop receiving [`RuntimeEvent`]s from a channel instead of winit, until every thread
+	/// has exited. There is no event loop to create windows with.
+	pub(crate) fn run_headless<Fut, R>(
+		platform: v8::SharedRef<v8::Platform>,
+		isolate: Arc<IsolateOptions>,
+		f: impl FnOnce() -> Fut + Send + Sync + 'static,
+	) -> Result<R, RuntimeError>
+	where
+		Fut: Future<Output = R> + Send + Sync + 'static,
+		R: Send + Sync + 'static,
+	{
+		let (event_tx, mut event_rx) = mpsc::unbounded();
+		let runtime_handle = RuntimeHandle::headless(event_tx, isolate);
+
+		let (main_thread, join_handle) = Thread::new(platform.clone(), runtime_handle, f);
+		let thread_handle = ThreadHandle::<R>::new(main_thread.clone(), join_handle);
+
+		let Self {
+			platform,
+			local_pool,
+			threads,
+			windows,
+		} = &mut Runtime::new(platform, main_thread);
+
+		while !threads.is_empty() {
+			let spawner = local_pool.spawner();
+			let event = enter(platform, None, &spawner, threads, windows, || {
+				local_pool.run_until(event_rx.next())
+			});
+
+			match event {
+				Some(RuntimeEvent::Invoke(v)) => enter(platform, None, &spawner, threads, windows, v),
+				None => break,
+			}
+		}
 
 		thread_handle.join()
 	}
 }
 
+/// Sets the state of the runtime for `f`, along with the event loop unless headless.
 fn enter<R>(
 	platform: &v8::SharedRef<v8::Platform>,
-	event_loop: &winit::ActiveEventLoop,
+	event_loop: Option<&winit::ActiveEventLoop>,
 	spawner: &LocalSpawner,
 	threads: &mut FnvHashMap<ThreadId, Thread>,
+	windows: &mut FnvHashMap<winit::WindowId, ThreadId>,
 	f: impl FnOnce() -> R,
 ) -> R {
-	PLATFORM.set(platform, || {
-		EVENT_LOOP.set(event_loop, || {
-			SPAWNER.set(spawner, || THREADS.set(threads, f))
-		})
+	let f = || SPAWNER.set(spawner, || THREADS.set(threads, || WINDOWS.set(windows, f)));
+
+	PLATFORM.set(platform, || match event_loop {
+		Some(event_loop) => EVENT_LOOP.set(event_loop, f),
+		None => f(),
 	})
 }
 
@@ -143,6 +209,7 @@ impl winit::ApplicationHandler<RuntimeEvent> for Runtime {
 					platform,
 					local_pool,
 					threads,
+					windows,
 					..
 				} = self;
 
@@ -152,9 +219,14 @@ impl winit::ApplicationHandler<RuntimeEvent> for Runtime {
 					return;
 				}
 
-				enter(platform, event_loop, &local_pool.spawner(), threads, || {
-					local_pool.run_until_stalled()
-				});
+				enter(
+					platform,
+					Some(event_loop),
+					&local_pool.spawner(),
+					threads,
+					windows,
+					|| local_pool.run_until_stalled(),
+				);
 			}
 			winit::StartCause::Init => {}
 		}
@@ -169,10 +241,18 @@ impl winit::ApplicationHandler<RuntimeEvent> for Runtime {
 					platform,
 					local_pool,
 					threads,
+					windows,
 					..
 				} = self;
 
-				enter(platform, event_loop, &local_pool.spawner(), threads, || v());
+				enter(
+					platform,
+					Some(event_loop),
+					&local_pool.spawner(),
+					threads,
+					windows,
+					|| v(),
+				);
 			}
 		}
 	}
@@ -184,6 +264,25 @@ impl winit::ApplicationHandler<RuntimeEvent> for Runtime {
 		event: winit::WindowEvent,
 	) {
 		trace!("window_event: {:?}", event);
-		//todo!()
+
+		let Some(thread) = self
+			.windows
+			.get(&window_id)
+			.and_then(|thread_id| self.threads.get(thread_id))
+		else {
+			trace!("dropping event of window {:?} without a thread", window_id);
+
+			return;
+		};
+
+		let destroyed = matches!(event, winit::WindowEvent::Destroyed);
+
+		if !thread.send(ThreadEvent::Window(window_id, event)) {
+			trace!("thread of window {:?} has exited", window_id);
+		}
+
+		if destroyed {
+			self.windows.remove(&window_id);
+		}
 	}
 }
@@ -0,0 +1,167 @@
+use std::{
+	ffi::c_void,
+	fmt::{self, Debug},
+	future::Future,
+	sync::Arc,
+};
+
+#[cfg(feature = "tracing-subscriber")]
+use tracing::level_filters::LevelFilter;
+
+use crate::{Runtime, RuntimeError};
+
+/// Configures a [`Runtime`] before running it, see [`Runtime::builder`].
+#[derive(Debug)]
+pub struct RuntimeBuilder {
+	headless: bool,
+	v8_flags: Vec<String>,
+	worker_threads: u32,
+	isolate: IsolateOptions,
+	#[cfg(feature = "tracing-subscriber")]
+	subscriber: Option<LevelFilter>,
+}
+
+/// How the isolate of every thread is created.
+#[derive(Default)]
+pub(crate) struct IsolateOptions {
+	heap_limits: Option<(usize, usize)>,
+	near_heap_limit: Option<NearHeapLimit>,
+}
+
+type NearHeapLimit = Arc<dyn Fn(usize, usize) -> usize + Send + Sync>;
+
+impl Default for RuntimeBuilder {
+	fn default() -> Self {
+		Self {
+			headless: false,
+			v8_flags: Vec::new(),
+			worker_threads: 0,
+			isolate: IsolateOptions::default(),
+			#[cfg(feature = "tracing-subscriber")]
+			subscriber: Some(LevelFilter::TRACE),
+		}
+	}
+}
+
+impl RuntimeBuilder {
+	/// Runs without winit, so no display is needed, e.g. for tests in CI. Windows can only be
+	/// created with [`Window::headless`](crate::Window::headless).
+	pub fn headless(mut self) -> Self {
+		self.headless = true;
+		self
+	}
+
+	/// Passes a flag to V8, e.g. `--expose-gc`. V8 is initialized once per process, so like
+	/// [`worker_threads`](Self::worker_threads) this only applies to the first runtime run.
+	pub fn v8_flag(mut self, flag: impl Into<String>) -> Self {
+		self.v8_flags.push(flag.into());
+		self
+	}
+
+	/// The size of the thread pool of V8, `0` to pick one for the number of CPUs.
+	pub fn worker_threads(mut self, count: u32) -> Self {
+		self.worker_threads = count;
+		self
+	}
+
+	/// The initial and maximum size in bytes of the heap of each thread.
+	pub fn heap_limits(mut self, initial: usize, max: usize) -> Self {
+		self.isolate.heap_limits = Some((initial, max));
+		self
+	}
+
+	/// Called with the current and initial heap limit when a thread is about to run out of heap,
+	/// returning the new limit. Returning the current one lets V8 fail with out of memory.
+	pub fn near_heap_limit(
+		mut self,
+		f: impl Fn(usize, usize) -> usize + Send + Sync + 'static,
+	) -> Self {
+		self.isolate.near_heap_limit = Some(Arc::new(f));
+		self
+	}
+
+	/// Installs a global subscriber logging up to `filter`, which is the default with `TRACE`. It is
+	/// not installed if another is already set.
+	#[cfg(feature = "tracing-subscriber")]
+	pub fn subscriber(mut self, filter: impl Into<LevelFilter>) -> Self {
+		self.subscriber = Some(filter.into());
+		self
+	}
+
+	/// Leaves installing a subscriber to the application.
+	#[cfg(feature = "tracing-subscriber")]
+	pub fn without_subscriber(mut self) -> Self {
+		self.subscriber = None;
+		self
+	}
+
+	pub fn run_sync<R>(self, f: impl FnOnce() -> R + Send + Sync + 'static) -> Result<R, RuntimeError>
+	where
+		R: Send + Sync + 'static,
+	{
+		self.run(|| async { f() })
+	}
+
+	pub fn run<Fut, R>(
+		self,
+		f: impl FnOnce() -> Fut + Send + Sync + 'static,
+	) -> Result<R, RuntimeError>
+	where
+		Fut: Future<Output = R> + Send + Sync + 'static,
+		R: Send + Sync + 'static,
+	{
+		#[cfg(feature = "tracing-subscriber")]
+		if let Some(filter) = self.subscriber {
+			let subscriber = tracing_subscriber::FmtSubscriber::builder()
+				.with_thread_names(true)
+				.with_max_level(filter)
+				.finish();
+
+			// the application embedding the runtime may have set its own
+			let _ = tracing::subscriber::set_global_default(subscriber);
+		}
+
+		let platform = Runtime::platform(self.worker_threads, &self.v8_flags.join(" "));
+		let isolate = Arc::new(self.isolate);
+
+		if self.headless {
+			Runtime::run_headless(platform, isolate, f)
+		} else {
+			Runtime::run_winit(platform, isolate, f)
+		}
+	}
+}
+
+impl IsolateOptions {
+	pub(crate) fn create_params(&self) -> v8::CreateParams {
+		let params = v8::CreateParams::default();
+
+		match self.heap_limits {
+			Some((initial, max)) => params.heap_limits(initial, max),
+			None => params,
+		}
+	}
+
+	/// Adds the near heap limit callback to `isolate`, which must be dropped before `self`.
+	pub(crate) fn install(&self, isolate: &mut v8::Isolate) {
+		extern "C" fn near_heap_limit(data: *mut c_void, current: usize, initial: usize) -> usize {
+			// SAFETY: data points to the callback, which outlives the isolate
+			let callback = unsafe { &*(data as *const NearHeapLimit) };
+
+			callback(current, initial)
+		}
+
+		if let Some(callback) = &self.near_heap_limit {
+			isolate.add_near_heap_limit_callback(near_heap_limit, callback as *const _ as *mut c_void);
+		}
+	}
+}
+
+impl Debug for IsolateOptions {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("IsolateOptions")
+			.field("heap_limits", &self.heap_limits)
+			.field("near_heap_limit", &self.near_heap_limit.is_some())
+			.finish()
+	}
+}
@@ -7,6 +7,15 @@ pub enum RuntimeError {
 	#[error("spawn error")]
 	Spawn(#[from] SpawnError),
 
+	#[error("failed to create window")]
+	CreateWindow(#[from] winit::OsError),
+
+	#[error("windows can not be created by a headless runtime, use `Window::headless` instead")]
+	Headless,
+
+	#[error("event loop error")]
+	EventLoop(#[from] winit::EventLoopError),
+
 	#[error("event loop closed")]
 	EventLoopClosed(#[from] winit::EventLoopClosed<RuntimeEvent>),
 
@@ -1,7 +1,11 @@
-use std::future::Future;
+use std::{
+	future::Future,
+	sync::Arc,
+	thread::{current, ThreadId},
+};
 
 use futures::{
-	channel::oneshot::{self},
+	channel::{mpsc, oneshot},
 	executor::block_on,
 	task::LocalSpawnExt,
 };
@@ -9,14 +13,23 @@ use scoped_tls_hkt::scoped_thread_local;
 use tracing::{instrument, trace};
 
 use crate::{
-	winit, with_platform, with_spawner, BoxSendSyncAny, RuntimeError, Thread, ThreadHandle,
+	runtime_builder::IsolateOptions, winit, with_event_loop, with_platform, with_spawner,
+	with_windows, BoxSendSyncAny, RuntimeError, Thread, ThreadHandle, Window,
 };
 
 use super::RuntimeEvent;
 
 #[derive(Clone, Debug)]
 pub struct RuntimeHandle {
-	event_loop_proxy: winit::EventLoopProxy<RuntimeEvent>,
+	event_tx: EventSender,
+	isolate: Arc<IsolateOptions>,
+}
+
+/// Where [`RuntimeEvent`]s go, the event loop of winit or the loop of a headless runtime.
+#[derive(Clone, Debug)]
+enum EventSender {
+	Winit(winit::EventLoopProxy<RuntimeEvent>),
+	Headless(mpsc::UnboundedSender<RuntimeEvent>),
 }
 
 scoped_thread_local! {
@@ -24,8 +37,45 @@ scoped_thread_local! {
 }
 
 impl RuntimeHandle {
-	pub(crate) fn new(event_loop_proxy: winit::EventLoopProxy<RuntimeEvent>) -> Self {
-		Self { event_loop_proxy }
+	pub(crate) fn new(
+		event_loop_proxy: winit::EventLoopProxy<RuntimeEvent>,
+		isolate: Arc<IsolateOptions>,
+	) -> Self {
+		Self {
+			event_tx: EventSender::Winit(event_loop_proxy),
+			isolate,
+		}
+	}
+
+	pub(crate) fn headless(
+		event_tx: mpsc::UnboundedSender<RuntimeEvent>,
+		isolate: Arc<IsolateOptions>,
+	) -> Self {
+		Self {
+			event_tx: EventSender::Headless(event_tx),
+			isolate,
+		}
+	}
+
+	/// How the isolates of threads spawned by the runtime are created.
+	pub(crate) fn isolate_options(&self) -> &Arc<IsolateOptions> {
+		&self.isolate
+	}
+
+	#[inline]
+	pub fn is_headless(&self) -> bool {
+		matches!(self.event_tx, EventSender::Headless(_))
+	}
+
+	fn send(&self, event: RuntimeEvent) -> Result<(), RuntimeError> {
+		match &self.event_tx {
+			EventSender::Winit(event_loop_proxy) => event_loop_proxy.send_event(event)?,
+			EventSender::Headless(event_tx) => event_tx
+				.unbounded_send(event)
+				.map_err(|e| winit::EventLoopClosed(e.into_inner()))?,
+		}
+
+		Ok(())
 	}
 
 	pub fn current() -> RuntimeHandle {
@@ -37,11 +87,47 @@ impl RuntimeHandle {
 	}
 
 	pub fn invoke(&self, f: impl FnOnce() + Send + Sync + 'static) -> Result<(), RuntimeError> {
-		self
-			.event_loop_proxy
-			.send_event(RuntimeEvent::Invoke(Box::new(f)))?;
+		self.send(RuntimeEvent::Invoke(Box::new(f)))
+	}
 
-		Ok(())
+	/// Creates a window on the event loop thread, whose events are sent to the current thread, see
+	/// [`ThreadContext::on_window_event`](crate::ThreadContext). [`Window::headless`] creates one
+	/// without a display, and is the only way to get a window from a headless runtime.
+	pub async fn create_window(
+		&self,
+		attributes: winit::WindowAttributes,
+	) -> Result<Window, RuntimeError> {
+		if self.is_headless() {
+			return Err(RuntimeError::Headless);
+		}
+
+		let (tx, rx) = oneshot::channel();
+		let thread_id = current().id();
+
+		self.invoke(move || {
+			let result =
+				with_event_loop(|event_loop| event_loop.create_window(attributes)).map(|window| {
+					with_windows(|windows| windows.insert(window.id(), thread_id));
+
+					Window::native(window)
+				});
+
+			let _ = tx.send(result);
+		})?;
+
+		Ok(rx.await??)
+	}
+
+	/// Sends the events of the window to the thread from now on, until the window is destroyed or
+	/// the thread exits.
+	pub fn register_window(
+		&self,
+		window_id: winit::WindowId,
+		thread_id: ThreadId,
+	) -> Result<(), RuntimeError> {
+		self.invoke(move || {
+			with_windows(|windows| windows.insert(window_id, thread_id));
+		})
 	}
 
 	pub fn spawn_thread<R>(
@@ -68,15 +154,13 @@ impl RuntimeHandle {
 
 		trace!("sending runtime event requesting spawn of new thread");
 
-		self
-			.event_loop_proxy
-			.send_event(RuntimeEvent::Invoke(Box::new(move || {
-				with_platform(|platform| {
-					let (thread, join_handle) = Thread::new(platform.clone(), runtime_handle.clone(), f);
+		self.send(RuntimeEvent::Invoke(Box::new(move || {
+			with_platform(|platform| {
+				let (thread, join_handle) = Thread::new(platform.clone(), runtime_handle.clone(), f);
 
-					let _ = tx.send(ThreadHandle::new(thread, join_handle));
-				});
-			})))?;
+				let _ = tx.send(ThreadHandle::new(thread, join_handle));
+			});
+		})))?;
 
 		Ok(block_on(rx)?)
 	}
@@ -91,15 +175,13 @@ impl RuntimeHandle {
 	{
 		let (tx, rx) = oneshot::channel();
 
-		self
-			.event_loop_proxy
-			.send_event(RuntimeEvent::Invoke(Box::new(move || {
-				with_spawner(|spawner| {
-					let handle = spawner.spawn_local_with_handle(f());
+		self.send(RuntimeEvent::Invoke(Box::new(move || {
+			with_spawner(|spawner| {
+				let handle = spawner.spawn_local_with_handle(f());
 
-					tx.send(handle).unwrap();
-				})
-			})))?;
+				tx.send(handle).unwrap();
+			})
+		})))?;
 
 		rx.await??
 			.await
@@ -1,6 +1,7 @@
 use std::{
 	future::Future,
-	thread::{current, spawn, JoinHandle, ThreadId},
+	thread::{current, panicking, park, park_timeout, spawn, JoinHandle, ThreadId},
+	time::{Duration, Instant},
 };
 
 use futures::{
@@ -9,14 +10,22 @@ use futures::{
 	task::LocalSpawnExt,
 	FutureExt, StreamExt,
 };
-use m8::enter_scope;
+use m8::{enter_scope, with_scope};
 use scoped_tls_hkt::scoped_thread_local;
 use torque_compiler::Compiler;
 use tracing::trace;
 
-use crate::{console, with_threads, BoxSendSyncAny, RuntimeHandle, ThreadContext};
+use crate::{
+	console, timers, winit, with_threads, with_windows, BoxSendSyncAny, RuntimeHandle, ThreadContext,
+};
 
-pub enum ThreadEvent {}
+/// Sent by the runtime to a thread, see [`ThreadContext::on_window_event`].
+#[derive(Debug)]
+pub enum ThreadEvent {
+	/// An event of a window the thread created, e.g. a resize, close request, redraw request,
+	/// pointer, keyboard or IME input.
+	Window(winit::WindowId, winit::WindowEvent),
+}
 
 #[derive(Clone, Debug)]
 pub struct Thread {
@@ -27,6 +36,10 @@ pub struct Thread {
 
 scoped_thread_local! (static CONTEXT: ThreadContext);
 
+/// How often a thread checks for tasks while V8 runs background tasks, which post their results
+/// without waking the thread.
+const BACKGROUND_TASK_POLL: Duration = Duration::from_millis(1);
+
 impl Thread {
 	pub(crate) fn new<Fut, R>(
 		platform: v8::SharedRef<v8::Platform>,
@@ -70,6 +83,11 @@ impl Thread {
 		R: Send + Sync + 'static,
 	{
 		let thread_id = current().id();
+		// dropped last, also when unwinding from a panic
+		let _exit = Exit {
+			thread_id,
+			runtime_handle: runtime_handle.clone(),
+		};
 		let mut local_pool = LocalPool::new();
 
 		let compiler = Compiler::new();
@@ -83,8 +101,12 @@ impl Thread {
 
 		trace!("setting up v8 isolate and context");
 
+		// declared before the isolate to outlive its near heap limit callback
+		let isolate_options = runtime_handle.isolate_options().clone();
 		let heap = v8::cppgc::Heap::create(platform.clone(), v8::cppgc::HeapCreateParams::default());
-		let isolate = &mut v8::Isolate::new(v8::CreateParams::default().cpp_heap(heap));
+		let isolate = &mut v8::Isolate::new(isolate_options.create_params().cpp_heap(heap));
+
+		isolate_options.install(isolate);
 		let context = {
 			let scope = &mut v8::HandleScope::new(isolate);
 			let context = v8::Context::new(scope, v8::ContextOptions::default());
@@ -95,15 +117,23 @@ impl Thread {
 			v8::Global::new(scope, context)
 		};
 
-		{
+		let timers = {
 			let scope = &mut v8::HandleScope::with_context(isolate, context.clone());
 
 			console::init(scope);
 
+			let timers = timers::init(scope);
+
+			let global = scope.get_current_context().global(scope);
+
+			torque_ecs::js::init(scope, global);
+
 			m8::init(scope, |scope, specifier, module| {
 				compiler.add_module(specifier.to_string(), v8::Global::new(scope, module));
 			});
-		}
+
+			timers
+		};
 
 		trace!("setting up local pool");
 
@@ -111,13 +141,17 @@ impl Thread {
 
 		let main_future = &mut spawner.spawn_local_with_handle(f()).unwrap();
 
+		let event_context = thread_context.clone();
+
 		// TODO: handle error gracefully
 		local_pool
 			.spawner()
 			.spawn_local(async move {
-				loop {
-					if let Some(event) = event_rx.next().await {
-						match event {}
+				while let Some(event) = event_rx.next().await {
+					match event {
+						ThreadEvent::Window(window_id, event) => {
+							event_context.dispatch_window_event(window_id, &event)
+						}
 					}
 				}
 			})
@@ -126,28 +160,47 @@ impl Thread {
 		trace!("entering loop");
 
 		loop {
-			if let Some(result) = main_future.now_or_never() {
-				trace!("exiting thread gracefully");
-
-				let _ = runtime_handle.invoke(move || {
-					with_threads(|threads| threads.remove(&thread_id));
-				});
-
-				break Box::new(result);
-			}
-
-			v8::Platform::pump_message_loop(&platform, isolate, false);
+			while v8::Platform::pump_message_loop(&platform, isolate, false) {}
 			v8::Platform::run_idle_tasks(&platform, isolate, 0.0);
 
 			let scope = &mut v8::HandleScope::with_context(isolate, context.clone());
 
-			scope.perform_microtask_checkpoint();
-
 			enter_scope(scope, || {
 				CONTEXT.set(&thread_context, || {
+					with_scope(|scope| {
+						timers.run_due(scope);
+						scope.perform_microtask_checkpoint();
+					});
+
 					local_pool.run_until_stalled();
+
+					// promises resolved by the futures
+					with_scope(|scope| scope.perform_microtask_checkpoint());
 				})
 			});
+
+			let background_tasks = scope.has_pending_background_tasks();
+
+			if let Some(result) = main_future.now_or_never() {
+				trace!("exiting thread gracefully");
+
+				break Box::new(result);
+			}
+
+			// futures woken from any thread and those spawned with the context unpark this one, and
+			// timers are only set by this thread, before the sleep is computed
+			let mut due = timers.next_due();
+
+			if background_tasks {
+				let poll = Instant::now() + BACKGROUND_TASK_POLL;
+
+				due = Some(due.map_or(poll, |v| v.min(poll)));
+			}
+
+			match due {
+				Some(due) => park_timeout(due.saturating_duration_since(Instant::now())),
+				None => park(),
+			}
 		}
 	}
 
@@ -155,7 +208,34 @@ impl Thread {
 		self.id
 	}
 
+	/// Returns `false` if the thread has exited.
+	pub(crate) fn send(&self, event: ThreadEvent) -> bool {
+		self.event_tx.unbounded_send(event).is_ok()
+	}
+
 	pub fn context() -> ThreadContext {
 		CONTEXT.with(|context| context.clone())
 	}
 }
+
+/// Removes an exiting thread from the runtime, which otherwise waits for it forever, whether it
+/// returns or panics.
+struct Exit {
+	thread_id: ThreadId,
+	runtime_handle: RuntimeHandle,
+}
+
+impl Drop for Exit {
+	fn drop(&mut self) {
+		let thread_id = self.thread_id;
+
+		if panicking() {
+			trace!("exiting thread after a panic");
+		}
+
+		let _ = self.runtime_handle.invoke(move || {
+			with_threads(|threads| threads.remove(&thread_id));
+			with_windows(|windows| windows.retain(|_, v| *v != thread_id));
+		});
+	}
+}
@@ -1,20 +1,36 @@
-use std::thread::ThreadId;
+use std::{
+	cell::RefCell,
+	future::Future,
+	rc::Rc,
+	thread::{self, ThreadId},
+};
 
-use futures::{channel::mpsc::UnboundedSender, executor::LocalSpawner};
+use futures::{
+	channel::mpsc::UnboundedSender,
+	executor::LocalSpawner,
+	task::{LocalSpawnExt, SpawnError},
+};
 use torque_compiler::Compiler;
 
-use crate::{RuntimeHandle, ThreadEvent};
+use crate::{winit, RuntimeHandle, ThreadEvent};
+
+type WindowEventHandler = Rc<dyn Fn(winit::WindowId, &winit::WindowEvent)>;
 
 #[derive(Clone)]
 pub struct ThreadContext {
 	pub id: ThreadId,
+	/// Futures spawned with it while the thread sleeps wait for it to wake up, see
+	/// [`spawn_local`](Self::spawn_local).
 	pub spawner: LocalSpawner,
 	pub compiler: Compiler,
 	pub runtime_handle: RuntimeHandle,
 	pub event_tx: UnboundedSender<ThreadEvent>,
+	window_event_handlers: Rc<RefCell<Vec<WindowEventHandler>>>,
+	thread: thread::Thread,
 }
 
 impl ThreadContext {
+	/// Called by the thread the context is for.
 	pub fn new(
 		id: ThreadId,
 		spawner: LocalSpawner,
@@ -28,6 +44,35 @@ impl ThreadContext {
 			compiler,
 			runtime_handle,
 			event_tx,
+			window_event_handlers: Default::default(),
+			thread: thread::current(),
+		}
+	}
+
+	/// Spawns `future` on the thread, waking it so the future is polled even if spawned while the
+	/// thread is not polling its futures, e.g. by a promise reaction.
+	pub fn spawn_local(&self, future: impl Future<Output = ()> + 'static) -> Result<(), SpawnError> {
+		self.spawner.spawn_local(future)?;
+		self.thread.unpark();
+
+		Ok(())
+	}
+
+	/// Calls `f` with every event of the windows this thread created, in the order they arrive.
+	pub fn on_window_event(&self, f: impl Fn(winit::WindowId, &winit::WindowEvent) + 'static) {
+		self.window_event_handlers.borrow_mut().push(Rc::new(f));
+	}
+
+	pub(crate) fn dispatch_window_event(
+		&self,
+		window_id: winit::WindowId,
+		event: &winit::WindowEvent,
+	) {
+		// cloned so handlers may add handlers
+		let handlers = self.window_event_handlers.borrow().clone();
+
+		for handler in handlers {
+			handler(window_id, event);
 		}
 	}
 }
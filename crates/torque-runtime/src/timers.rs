@@ -0,0 +1,392 @@
+use std::{
+	cell::{Cell, RefCell},
+	collections::{BTreeSet, VecDeque},
+	rc::Rc,
+	time::{Duration, Instant},
+};
+
+use fnv::FnvHashMap;
+use tracing::error;
+
+/// How often animation frames run, about 60 times a second.
+const FRAME_INTERVAL: Duration = Duration::from_micros(16_667);
+
+/// The shortest delay of timers nested deeper than [`MAX_NESTING`], as in HTML, so timers setting
+/// timers and intervals without a delay do not keep the thread busy.
+const MIN_NESTED_DELAY: Duration = Duration::from_millis(4);
+const MAX_NESTING: u32 = 5;
+
+/// The timers and animation frame callbacks of a context, run by the loop of its thread.
+pub(crate) struct Timers(RefCell<Inner>);
+
+struct Inner {
+	origin: Instant,
+	next_id: u32,
+	/// The nesting level of the timer running, `0` outside of timers.
+	nesting: u32,
+	timers: FnvHashMap<u32, (Instant, Rc<Timer>)>,
+	queue: BTreeSet<(Instant, u32)>,
+	next_frame_id: u32,
+	frames: VecDeque<(u32, v8::Global<v8::Function>)>,
+	last_frame: Instant,
+}
+
+struct Timer {
+	callback: v8::Global<v8::Function>,
+	args: Vec<v8::Global<v8::Value>>,
+	/// Set for intervals.
+	interval: Option<Duration>,
+	/// One more than the nesting level of the timer that set it, incremented by every repeat.
+	nesting: Cell<u32>,
+}
+
+impl Timers {
+	fn new() -> Self {
+		let now = Instant::now();
+
+		Self(RefCell::new(Inner {
+			origin: now,
+			next_id: 1,
+			nesting: 0,
+			timers: Default::default(),
+			queue: Default::default(),
+			next_frame_id: 1,
+			frames: Default::default(),
+			last_frame: now,
+		}))
+	}
+
+	/// When the next timer or animation frame is due, `None` if there are none.
+	pub(crate) fn next_due(&self) -> Option<Instant> {
+		let inner = self.0.borrow();
+		let timer = inner.queue.first().map(|(due, _)| *due);
+		let frame = (!inner.frames.is_empty()).then(|| inner.last_frame + FRAME_INTERVAL);
+
+		timer.into_iter().chain(frame).min()
+	}
+
+	/// Runs the timers and the animation frame due by now, logging what they throw. Timers set by
+	/// callbacks run on a later call.
+	pub(crate) fn run_due(&self, scope: &mut v8::HandleScope) {
+		let now = Instant::now();
+
+		loop {
+			// not borrowed while calling, as callbacks set and clear timers
+			let due = self.0.borrow_mut().pop_timer(now);
+			let Some((id, timer)) = due else {
+				break;
+			};
+
+			let scope = &mut v8::HandleScope::new(scope);
+			let args = timer
+				.args
+				.iter()
+				.map(|v| v8::Local::new(scope, v))
+				.collect::<Vec<_>>();
+
+			self.0.borrow_mut().nesting = timer.nesting.get();
+
+			call(scope, &timer.callback, &args);
+
+			let mut inner = self.0.borrow_mut();
+
+			inner.nesting = 0;
+
+			if let Some(interval) = timer.interval {
+				let nesting = timer.nesting.get();
+
+				timer.nesting.set(nesting.saturating_add(1));
+				inner.reschedule(id, Instant::now() + clamp(nesting, interval));
+			}
+		}
+
+		let (time, last_id) = {
+			let mut inner = self.0.borrow_mut();
+
+			if inner.frames.is_empty() || now < inner.last_frame + FRAME_INTERVAL {
+				return;
+			}
+
+			inner.last_frame = now;
+
+			let time = now.duration_since(inner.origin).as_secs_f64() * 1000.0;
+
+			(time, inner.next_frame_id)
+		};
+
+		loop {
+			// frames requested by callbacks run on the next one
+			let frame = {
+				let mut inner = self.0.borrow_mut();
+
+				match inner.frames.front() {
+					Some((id, _)) if *id < last_id => inner.frames.pop_front().map(|(_, v)| v),
+					_ => None,
+				}
+			};
+			let Some(callback) = frame else {
+				break;
+			};
+
+			let scope = &mut v8::HandleScope::new(scope);
+			let time = v8::Number::new(scope, time);
+
+			call(scope, &callback, &[time.into()]);
+		}
+	}
+}
+
+impl Inner {
+	fn set(&mut self, delay: Duration, timer: Timer) -> u32 {
+		let id = self.next_id;
+		let due = Instant::now() + clamp(self.nesting, delay);
+
+		timer.nesting.set(self.nesting.saturating_add(1));
+		self.next_id += 1;
+		self.timers.insert(id, (due, Rc::new(timer)));
+		self.queue.insert((due, id));
+
+		id
+	}
+
+	fn clear(&mut self, id: u32) {
+		if let Some((due, _)) = self.timers.remove(&id) {
+			self.queue.remove(&(due, id));
+		}
+	}
+
+	/// The first timer due by `now`, which is kept if it is an interval.
+	fn pop_timer(&mut self, now: Instant) -> Option<(u32, Rc<Timer>)> {
+		let (due, id) = *self.queue.first()?;
+
+		if due > now {
+			return None;
+		}
+
+		self.queue.remove(&(due, id));
+
+		let (_, timer) = self.timers.get(&id)?;
+		let timer = timer.clone();
+
+		if timer.interval.is_none() {
+			self.timers.remove(&id);
+		}
+
+		Some((id, timer))
+	}
+
+	/// Queues an interval again, unless it was cleared by its callback.
+	fn reschedule(&mut self, id: u32, due: Instant) {
+		if let Some((v, _)) = self.timers.get_mut(&id) {
+			*v = due;
+			self.queue.insert((due, id));
+		}
+	}
+}
+
+/// The delay of a timer set or repeated at `nesting`.
+fn clamp(nesting: u32, delay: Duration) -> Duration {
+	if nesting > MAX_NESTING {
+		delay.max(MIN_NESTED_DELAY)
+	} else {
+		delay
+	}
+}
+
+fn call(
+	scope: &mut v8::HandleScope,
+	callback: &v8::Global<v8::Function>,
+	args: &[v8::Local<v8::Value>],
+) {
+	let scope = &mut v8::TryCatch::new(scope);
+	let callback = v8::Local::new(scope, callback);
+	let recv = v8::undefined(scope).into();
+
+	if callback.call(scope, recv, args).is_none() {
+		if let Some(exception) = scope.exception() {
+			error!(
+				"uncaught exception in timer: {}",
+				exception.to_rust_string_lossy(scope)
+			);
+		}
+	}
+}
+
+fn timers(scope: &mut v8::HandleScope) -> Rc<Timers> {
+	scope
+		.get_current_context()
+		.get_slot::<Rc<Timers>>()
+		.cloned()
+		.expect("timers::init should be called for the context")
+}
+
+/// The callback argument at `index`, throwing if it is not a function.
+fn callback_arg<'s>(
+	scope: &mut v8::HandleScope<'s>,
+	args: &v8::FunctionCallbackArguments<'s>,
+	index: i32,
+) -> Option<v8::Local<'s, v8::Function>> {
+	let callback = v8::Local::<v8::Function>::try_from(args.get(index)).ok();
+
+	if callback.is_none() {
+		m8::throw_error!(scope, "callback is not a function");
+	}
+
+	callback
+}
+
+/// The delay argument at `index` in milliseconds, `0` if missing or negative.
+fn delay_arg(
+	scope: &mut v8::HandleScope,
+	args: &v8::FunctionCallbackArguments,
+	index: i32,
+) -> Duration {
+	let delay = args.get(index).number_value(scope).unwrap_or_default();
+
+	if delay.is_finite() && delay > 0.0 {
+		Duration::from_secs_f64(delay / 1000.0)
+	} else {
+		Duration::ZERO
+	}
+}
+
+fn set_timer(
+	scope: &mut v8::HandleScope,
+	args: v8::FunctionCallbackArguments,
+	mut rv: v8::ReturnValue,
+	repeat: bool,
+) {
+	let Some(callback) = callback_arg(scope, &args, 0) else {
+		return;
+	};
+	let delay = delay_arg(scope, &args, 1);
+	let timer = Timer {
+		callback: v8::Global::new(scope, callback),
+		args: (2..args.length())
+			.map(|i| v8::Global::new(scope, args.get(i)))
+			.collect(),
+		interval: repeat.then_some(delay),
+		nesting: Cell::new(0),
+	};
+
+	let id = timers(scope).0.borrow_mut().set(delay, timer);
+
+	rv.set_uint32(id);
+}
+
+fn clear_timer(scope: &mut v8::HandleScope, args: v8::FunctionCallbackArguments) {
+	if let Some(id) = args.get(0).uint32_value(scope) {
+		timers(scope).0.borrow_mut().clear(id);
+	}
+}
+
+fn set_timeout(
+	scope: &mut v8::HandleScope,
+	args: v8::FunctionCallbackArguments,
+	rv: v8::ReturnValue,
+) {
+	set_timer(scope, args, rv, false);
+}
+
+fn set_interval(
+	scope: &mut v8::HandleScope,
+	args: v8::FunctionCallbackArguments,
+	rv: v8::ReturnValue,
+) {
+	set_timer(scope, args, rv, true);
+}
+
+fn clear_timeout(
+	scope: &mut v8::HandleScope,
+	args: v8::FunctionCallbackArguments,
+	_rv: v8::ReturnValue,
+) {
+	clear_timer(scope, args);
+}
+
+fn queue_microtask(
+	scope: &mut v8::HandleScope,
+	args: v8::FunctionCallbackArguments,
+	_rv: v8::ReturnValue,
+) {
+	if let Some(callback) = callback_arg(scope, &args, 0) {
+		scope.enqueue_microtask(callback);
+	}
+}
+
+fn request_animation_frame(
+	scope: &mut v8::HandleScope,
+	args: v8::FunctionCallbackArguments,
+	mut rv: v8::ReturnValue,
+) {
+	let Some(callback) = callback_arg(scope, &args, 0) else {
+		return;
+	};
+	let callback = v8::Global::new(scope, callback);
+	let timers = timers(scope);
+	let mut inner = timers.0.borrow_mut();
+	let id = inner.next_frame_id;
+
+	inner.next_frame_id += 1;
+	inner.frames.push_back((id, callback));
+
+	rv.set_uint32(id);
+}
+
+fn cancel_animation_frame(
+	scope: &mut v8::HandleScope,
+	args: v8::FunctionCallbackArguments,
+	_rv: v8::ReturnValue,
+) {
+	if let Some(id) = args.get(0).uint32_value(scope) {
+		timers(scope)
+			.0
+			.borrow_mut()
+			.frames
+			.retain(|(v, _)| *v != id);
+	}
+}
+
+fn set_function(
+	scope: &mut v8::HandleScope,
+	global: v8::Local<v8::Object>,
+	name: &str,
+	callback: impl v8::MapFnTo<v8::FunctionCallback>,
+) {
+	let key = v8::String::new(scope, name).unwrap();
+	let function = v8::Function::new(scope, callback).unwrap();
+
+	global.set(scope, key.into(), function.into());
+}
+
+/// Sets `setTimeout`, `clearTimeout`, `setInterval`, `clearInterval`, `queueMicrotask`,
+/// `requestAnimationFrame` and `cancelAnimationFrame` as globals, returning the timers for the loop
+/// of the thread to run.
+pub(crate) fn init(scope: &mut v8::HandleScope) -> Rc<Timers> {
+	let context = scope.get_current_context();
+	let global = context.global(scope);
+	let timers = Rc::new(Timers::new());
+
+	context.set_slot(timers.clone());
+
+	set_function(scope, global, "setTimeout", set_timeout);
+	set_function(scope, global, "clearTimeout", clear_timeout);
+	set_function(scope, global, "setInterval", set_interval);
+	// timeouts and intervals share ids, as in browsers
+	set_function(scope, global, "clearInterval", clear_timeout);
+	set_function(scope, global, "queueMicrotask", queue_microtask);
+	set_function(
+		scope,
+		global,
+		"requestAnimationFrame",
+		request_animation_frame,
+	);
+	set_function(
+		scope,
+		global,
+		"cancelAnimationFrame",
+		cancel_animation_frame,
+	);
+
+	timers
+}
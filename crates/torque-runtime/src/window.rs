@@ -1,7 +1,234 @@
-use std::sync::Arc;
+use std::sync::{
+	atomic::{AtomicU64, Ordering},
+	Arc,
+};
 
-use crate::winit;
+use futures::channel::mpsc::UnboundedSender;
+use parking_lot::Mutex;
 
+use crate::{winit, Thread, ThreadEvent};
+
+/// A window created by [`RuntimeHandle::create_window`](crate::RuntimeHandle) or
+/// [`Window::headless`]. Handles can be sent to and used from any thread, the events of the window
+/// go to the thread that created it, see
+/// [`ThreadContext::on_window_event`](crate::ThreadContext). The window is closed once every handle
+/// is dropped.
+#[derive(Clone, Debug)]
 pub struct Window {
-	inner: Arc<winit::Window>,
+	inner: Inner,
+}
+
+#[derive(Clone, Debug)]
+enum Inner {
+	Native(Arc<winit::Window>),
+	Headless(Arc<Headless>),
+}
+
+/// A window without a display, which keeps what it is set to and sends the events a native window
+/// would send in response to its own methods.
+#[derive(Debug)]
+struct Headless {
+	id: winit::WindowId,
+	event_tx: UnboundedSender<ThreadEvent>,
+	state: Mutex<HeadlessState>,
+}
+
+#[derive(Debug)]
+struct HeadlessState {
+	title: String,
+	visible: bool,
+	size: winit::PhysicalSize<u32>,
+	position: winit::PhysicalPosition<i32>,
+	decorated: bool,
+	cursor: winit::Cursor,
+	cursor_visible: bool,
+}
+
+impl Headless {
+	fn send(&self, event: winit::WindowEvent) {
+		// the thread is gone, so is anyone interested in the event
+		let _ = self
+			.event_tx
+			.unbounded_send(ThreadEvent::Window(self.id, event));
+	}
+}
+
+impl Drop for Headless {
+	fn drop(&mut self) {
+		self.send(winit::WindowEvent::Destroyed);
+	}
+}
+
+impl Window {
+	/// The size of headless windows created without one.
+	pub const DEFAULT_HEADLESS_SIZE: winit::PhysicalSize<u32> = winit::PhysicalSize::new(800, 600);
+
+	pub(crate) fn native(window: winit::Window) -> Self {
+		Self {
+			inner: Inner::Native(Arc::new(window)),
+		}
+	}
+
+	/// Creates a window that is never shown, for running without a display, e.g. in tests. Its
+	/// scale factor is `1.0` and its events go to the current thread, which must be a [`Thread`].
+	pub fn headless(attributes: winit::WindowAttributes) -> Self {
+		// counted down so ids are unlikely to collide with those of native windows
+		static NEXT_ID: AtomicU64 = AtomicU64::new(u64::MAX);
+
+		let id = winit::WindowId::from(NEXT_ID.fetch_sub(1, Ordering::Relaxed));
+		let state = HeadlessState {
+			title: attributes.title,
+			visible: attributes.visible,
+			size: attributes
+				.inner_size
+				.map(|v| v.to_physical(1.0))
+				.unwrap_or(Self::DEFAULT_HEADLESS_SIZE),
+			position: attributes
+				.position
+				.map(|v| v.to_physical(1.0))
+				.unwrap_or_default(),
+			decorated: attributes.decorations,
+			cursor: attributes.cursor,
+			cursor_visible: true,
+		};
+
+		Self {
+			inner: Inner::Headless(Arc::new(Headless {
+				id,
+				event_tx: Thread::context().event_tx,
+				state: Mutex::new(state),
+			})),
+		}
+	}
+
+	#[inline]
+	pub fn is_headless(&self) -> bool {
+		matches!(self.inner, Inner::Headless(_))
+	}
+
+	pub fn id(&self) -> winit::WindowId {
+		match &self.inner {
+			Inner::Native(window) => window.id(),
+			Inner::Headless(headless) => headless.id,
+		}
+	}
+
+	pub fn title(&self) -> String {
+		match &self.inner {
+			Inner::Native(window) => window.title(),
+			Inner::Headless(headless) => headless.state.lock().title.clone(),
+		}
+	}
+
+	pub fn set_title(&self, title: &str) {
+		match &self.inner {
+			Inner::Native(window) => window.set_title(title),
+			Inner::Headless(headless) => headless.state.lock().title = title.to_owned(),
+		}
+	}
+
+	/// `None` if the platform can not tell.
+	pub fn is_visible(&self) -> Option<bool> {
+		match &self.inner {
+			Inner::Native(window) => window.is_visible(),
+			Inner::Headless(headless) => Some(headless.state.lock().visible),
+		}
+	}
+
+	pub fn set_visible(&self, visible: bool) {
+		match &self.inner {
+			Inner::Native(window) => window.set_visible(visible),
+			Inner::Headless(headless) => headless.state.lock().visible = visible,
+		}
+	}
+
+	/// The size of the client area.
+	pub fn inner_size(&self) -> winit::PhysicalSize<u32> {
+		match &self.inner {
+			Inner::Native(window) => window.inner_size(),
+			Inner::Headless(headless) => headless.state.lock().size,
+		}
+	}
+
+	/// Asks for the client area to be resized, returning the new size if it was applied right away.
+	/// A [`Resized`](winit::WindowEvent::Resized) event follows either way once it is.
+	pub fn request_inner_size(
+		&self,
+		size: impl Into<winit::Size>,
+	) -> Option<winit::PhysicalSize<u32>> {
+		match &self.inner {
+			Inner::Native(window) => window.request_inner_size(size),
+			Inner::Headless(headless) => {
+				let size = size.into().to_physical(1.0);
+
+				headless.state.lock().size = size;
+				headless.send(winit::WindowEvent::Resized(size));
+
+				Some(size)
+			}
+		}
+	}
+
+	/// The position of the top left corner of the window, `None` if the platform can not tell.
+	pub fn outer_position(&self) -> Option<winit::PhysicalPosition<i32>> {
+		match &self.inner {
+			Inner::Native(window) => window.outer_position().ok(),
+			Inner::Headless(headless) => Some(headless.state.lock().position),
+		}
+	}
+
+	pub fn set_outer_position(&self, position: impl Into<winit::Position>) {
+		match &self.inner {
+			Inner::Native(window) => window.set_outer_position(position),
+			Inner::Headless(headless) => {
+				let position = position.into().to_physical(1.0);
+
+				headless.state.lock().position = position;
+				headless.send(winit::WindowEvent::Moved(position));
+			}
+		}
+	}
+
+	pub fn scale_factor(&self) -> f64 {
+		match &self.inner {
+			Inner::Native(window) => window.scale_factor(),
+			Inner::Headless(_) => 1.0,
+		}
+	}
+
+	pub fn is_decorated(&self) -> bool {
+		match &self.inner {
+			Inner::Native(window) => window.is_decorated(),
+			Inner::Headless(headless) => headless.state.lock().decorated,
+		}
+	}
+
+	pub fn set_decorations(&self, decorations: bool) {
+		match &self.inner {
+			Inner::Native(window) => window.set_decorations(decorations),
+			Inner::Headless(headless) => headless.state.lock().decorated = decorations,
+		}
+	}
+
+	pub fn set_cursor(&self, cursor: impl Into<winit::Cursor>) {
+		match &self.inner {
+			Inner::Native(window) => window.set_cursor(cursor),
+			Inner::Headless(headless) => headless.state.lock().cursor = cursor.into(),
+		}
+	}
+
+	pub fn set_cursor_visible(&self, visible: bool) {
+		match &self.inner {
+			Inner::Native(window) => window.set_cursor_visible(visible),
+			Inner::Headless(headless) => headless.state.lock().cursor_visible = visible,
+		}
+	}
+
+	/// Asks for a [`RedrawRequested`](winit::WindowEvent::RedrawRequested) event.
+	pub fn request_redraw(&self) {
+		match &self.inner {
+			Inner::Native(window) => window.request_redraw(),
+			Inner::Headless(headless) => headless.send(winit::WindowEvent::RedrawRequested),
+		}
+	}
 }
@@ -0,0 +1,162 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use futures::{channel::mpsc, FutureExt, StreamExt};
+use m8::with_scope;
+use torque_runtime::{Runtime, RuntimeError, Thread, Window};
+use winit::{
+	dpi::{PhysicalPosition, PhysicalSize},
+	event::WindowEvent,
+	window::WindowAttributes,
+};
+
+/// Runs `source` as a script in the context of the current thread, returning its result as a
+/// string.
+fn eval(source: &str) -> String {
+	with_scope(|scope| {
+		let scope = &mut v8::HandleScope::new(scope);
+		let source = v8::String::new(scope, source).unwrap();
+		let script = v8::Script::compile(scope, source, None).expect("script should compile");
+		let value = script.run(scope).expect("script should not throw");
+
+		value.to_rust_string_lossy(scope)
+	})
+}
+
+#[test]
+fn runs_scripts() {
+	let result = Runtime::builder().headless().run_sync(|| {
+		assert!(Thread::context().runtime_handle.is_headless());
+
+		eval("[1, 2, 3].map((v) => v * 2).join()")
+	});
+
+	assert_eq!(result.unwrap(), "2,4,6");
+}
+
+#[test]
+fn raises_heap_limit() {
+	static RAISED: AtomicBool = AtomicBool::new(false);
+
+	let result = Runtime::builder()
+		.headless()
+		.heap_limits(0, 16 << 20)
+		.near_heap_limit(|current, _| {
+			RAISED.store(true, Ordering::Relaxed);
+
+			current * 2
+		})
+		.run_sync(|| {
+			eval(
+				"const arrays = [];
+				for (let i = 0; i < 64; i++) arrays.push(new Array(1 << 18).fill(i));
+				arrays.length",
+			)
+		});
+
+	assert_eq!(result.unwrap(), "64");
+	assert!(RAISED.load(Ordering::Relaxed));
+}
+
+#[test]
+fn headless_window() {
+	let size = Runtime::builder().headless().run_sync(|| {
+		let window = Window::headless(WindowAttributes::default().with_title("test"));
+
+		assert!(window.is_headless());
+		assert_eq!(window.title(), "test");
+
+		window.set_title("renamed");
+
+		assert_eq!(window.title(), "renamed");
+
+		window.inner_size()
+	});
+
+	assert_eq!(size.unwrap(), Window::DEFAULT_HEADLESS_SIZE);
+}
+
+#[test]
+fn create_window_fails() {
+	let result = Runtime::builder().headless().run_sync(|| {
+		// fails before waiting for the event loop
+		let result = Thread::context()
+			.runtime_handle
+			.create_window(WindowAttributes::default())
+			.now_or_never();
+
+		matches!(result, Some(Err(RuntimeError::Headless)))
+	});
+
+	assert!(result.unwrap());
+}
+
+#[test]
+fn routes_window_events() {
+	let result = Runtime::builder().headless().run(|| async {
+		let (event_tx, event_rx) = mpsc::unbounded();
+		let window = Window::headless(WindowAttributes::default());
+		let window_id = window.id();
+
+		Thread::context().on_window_event(move |id, event| {
+			assert_eq!(id, window_id);
+
+			let _ = event_tx.unbounded_send(event.clone());
+		});
+
+		window.request_inner_size(PhysicalSize::new(320, 240));
+		window.set_outer_position(PhysicalPosition::new(10, 20));
+		window.request_redraw();
+		drop(window);
+
+		let events = event_rx.take(4).collect::<Vec<_>>().await;
+
+		assert_eq!(
+			events,
+			[
+				WindowEvent::Resized(PhysicalSize::new(320, 240)),
+				WindowEvent::Moved(PhysicalPosition::new(10, 20)),
+				WindowEvent::RedrawRequested,
+				WindowEvent::Destroyed,
+			]
+		);
+	});
+
+	result.unwrap();
+}
+
+#[test]
+fn shuts_down_after_spawned_threads() {
+	let result = Runtime::builder().headless().run_sync(|| {
+		let thread = Thread::context()
+			.runtime_handle
+			.spawn_thread(|| eval("'spawned'"))
+			.unwrap();
+
+		thread.join().unwrap()
+	});
+
+	assert_eq!(result.unwrap(), "spawned");
+}
+
+#[test]
+fn returns_thread_panics() {
+	let result = Runtime::builder()
+		.headless()
+		.run_sync(|| eval("throw new Error('thrown')"));
+
+	assert!(
+		matches!(result, Err(RuntimeError::ThreadPanic(Some(message))) if message.contains("throw"))
+	);
+
+	// a panic of a spawned thread only fails its own handle
+	let result = Runtime::builder().headless().run_sync(|| {
+		let thread = Thread::context()
+			.runtime_handle
+			.spawn_thread::<()>(|| panic!("spawned"))
+			.unwrap();
+
+		matches!(thread.join(), Err(RuntimeError::ThreadPanic(Some(message))) if message == "spawned")
+	});
+
+	assert!(result.unwrap());
+}
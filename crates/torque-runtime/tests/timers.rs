@@ -0,0 +1,110 @@
+use std::cell::RefCell;
+
+use futures::channel::oneshot;
+use m8::with_scope;
+use torque_runtime::Runtime;
+
+thread_local! {
+	static DONE: RefCell<Option<oneshot::Sender<String>>> = const { RefCell::new(None) };
+}
+
+fn done(scope: &mut v8::HandleScope, args: v8::FunctionCallbackArguments, _rv: v8::ReturnValue) {
+	let value = args.get(0).to_rust_string_lossy(scope);
+
+	if let Some(tx) = DONE.take() {
+		let _ = tx.send(value);
+	}
+}
+
+/// Runs `source` on a headless runtime, returning what it passes to the global `done` function.
+fn run_until_done(source: &'static str) -> String {
+	Runtime::builder()
+		.headless()
+		.run(move || async move {
+			let (tx, rx) = oneshot::channel();
+
+			DONE.set(Some(tx));
+
+			with_scope(|scope| {
+				let scope = &mut v8::HandleScope::new(scope);
+				let global = scope.get_current_context().global(scope);
+				let key = v8::String::new(scope, "done").unwrap();
+				let function = v8::Function::new(scope, done).unwrap();
+
+				global.set(scope, key.into(), function.into());
+
+				let source = v8::String::new(scope, source).unwrap();
+				let script = v8::Script::compile(scope, source, None).expect("script should compile");
+
+				script.run(scope).expect("script should not throw");
+			});
+
+			rx.await.expect("done should be called")
+		})
+		.unwrap()
+}
+
+#[test]
+fn ordering() {
+	let order = run_until_done(
+		"const order = [];
+		setTimeout(() => order.push('c'), 20);
+		setTimeout(() => order.push('a'), 0);
+		setTimeout(() => order.push('b'), 10);
+		setTimeout((v) => order.push(v), 0, 'a2');
+		Promise.resolve().then(() => order.push('microtask'));
+		setTimeout(() => done(order.join()), 40);",
+	);
+
+	assert_eq!(order, "microtask,a,a2,b,c");
+}
+
+#[test]
+fn clearing() {
+	let calls = run_until_done(
+		"const calls = [];
+		const cleared = setTimeout(() => calls.push('cleared'), 0);
+		let count = 0;
+		const interval = setInterval(() => {
+			calls.push(`interval ${++count}`);
+
+			if (count === 3) {
+				clearInterval(interval);
+			}
+		}, 1);
+
+		clearTimeout(cleared);
+		setTimeout(() => done(calls.join()), 100);",
+	);
+
+	assert_eq!(calls, "interval 1,interval 2,interval 3");
+}
+
+#[test]
+fn nested_timers_are_clamped() {
+	// timers set by timers nested deeper than 5 wait at least 4ms, that is 14 of these
+	let elapsed = run_until_done(
+		"const start = Date.now();
+		const tick = (n) => n === 0 ? done(Date.now() - start) : setTimeout(() => tick(n - 1), 0);
+
+		tick(20);",
+	);
+
+	assert!(elapsed.parse::<u64>().unwrap() >= 14 * 4);
+}
+
+#[test]
+fn zero_intervals_are_clamped() {
+	let count = run_until_done(
+		"let count = 0;
+		const interval = setInterval(() => count++, 0);
+
+		setTimeout(() => {
+			clearInterval(interval);
+			done(count);
+		}, 100);",
+	);
+
+	// the first run and 5 repeats without a delay, then one every 4ms at most
+	assert!(count.parse::<u64>().unwrap() <= 6 + 100 / 4);
+}
@@ -0,0 +1,23 @@
+//! V8 takes its flags from the first runtime run in a process, so this is the only test here.
+
+use m8::with_scope;
+use torque_runtime::Runtime;
+
+#[test]
+fn passes_flags_to_v8() {
+	let result = Runtime::builder()
+		.headless()
+		.v8_flag("--expose-gc")
+		.worker_threads(1)
+		.run_sync(|| {
+			with_scope(|scope| {
+				let scope = &mut v8::HandleScope::new(scope);
+				let source = v8::String::new(scope, "typeof gc").unwrap();
+				let script = v8::Script::compile(scope, source, None).unwrap();
+
+				script.run(scope).unwrap().to_rust_string_lossy(scope)
+			})
+		});
+
+	assert_eq!(result.unwrap(), "function");
+}
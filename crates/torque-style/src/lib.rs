@@ -18,9 +18,10 @@ pub use self::{
 	resolve::{Resolve, ResolveOrZero},
 };
 
-#[derive(Debug, Default)]
+#[derive(Component, Debug, Default)]
+#[component(default)]
 pub struct Style {
-	values: FnvHashMap<TypeId, Box<dyn Any + Send>>,
+	values: FnvHashMap<TypeId, Box<dyn Any + Send + Sync>>,
 }
 
 impl Style {
@@ -63,9 +64,3 @@ impl Style {
 		self
 	}
 }
-
-impl Component for Style {
-	type Value = Self;
-
-	const NAME: &str = "Style";
-}
@@ -1,5 +1,5 @@
 use std::{any::Any, fmt::Debug};
 
 pub trait Property {
-	type Value: Any + Clone + Send + Debug;
+	type Value: Any + Clone + Send + Sync + Debug;
 }
@@ -1,11 +1,9 @@
 #[cfg(test)]
 mod tests;
 
-use std::collections::VecDeque;
+use torque_ecs::{Children, Entity, EntityMethods, EntityRef};
 
-use torque_ecs::{Entity, EntityMethods, EntityRef};
-
-use crate::{Children, Node, NodeMethods, Parent};
+use crate::{Node, NodeMethods};
 
 pub trait ElementMethods<E>: NodeMethods<E>
 where
@@ -13,47 +11,32 @@ where
 {
 	fn element_self(&self) -> EntityRef<Element>;
 
-	fn with_children<R>(&self, f: impl FnOnce(&VecDeque<EntityRef<Node>>) -> R) -> R {
-		self.with_or_default::<Children, _>(f)
-	}
+	fn children(&self) -> Vec<EntityRef<Node>> {
+		let system = self.system();
 
-	fn with_children_mut<R>(&self, f: impl FnOnce(&mut VecDeque<EntityRef<Node>>) -> R) -> R {
-		self.with_mut_or_default::<Children, _>(f)
+		system
+			.children_of(self.id())
+			.into_iter()
+			.filter_map(|child_id| system.get::<Node>(child_id))
+			.collect()
 	}
 
 	fn prepend_child(&self, child: EntityRef<Node>) {
-		if let Some(parent) = child.parent().as_ref() {
-			if let Some(parent) = parent.upgrade() {
-				parent.remove_child(child.clone())
-			}
-		}
-
-		self.with_children_mut(|children| children.push_front(child.clone()));
-
-		child.set::<Parent>(self.element_self().downgrade().into());
+		self.system().insert_child_at(self.id(), 0, child.id());
 	}
 
 	fn append_child(&self, child: EntityRef<Node>) {
-		if let Some(parent) = child.parent().as_ref() {
-			if let Some(parent) = parent.upgrade() {
-				parent.remove_child(child.clone())
-			}
-		}
-
-		self.with_children_mut(|children| children.push_back(child.clone()));
-
-		child.set::<Parent>(self.element_self().downgrade().into());
+		self.system().push_child(self.id(), child.id());
 	}
 
 	fn remove_child(&self, child: EntityRef<Node>) {
-		child.set::<Parent>(None);
-
-		self.with_children_mut(|children| children.retain(|node| child.id == node.id));
+		self.system().remove_child(self.id(), child.id());
 	}
 }
 
 #[derive(Entity)]
 #[extends(Node)]
+#[require(Children)]
 pub struct Element;
 
 impl NodeMethods<Element> for EntityRef<Element> {}
@@ -1,13 +1,11 @@
-use std::collections::VecDeque;
-
 use test_log::test;
 use torque_ecs::{EntityMethods, EntityRef, System};
 
-use crate::Node;
+use crate::{Node, NodeMethods};
 
 use super::{Element, ElementMethods};
 
-fn print_children(children: &VecDeque<EntityRef<Node>>) {
+fn print_children(children: Vec<EntityRef<Node>>) {
 	for child in children.iter() {
 		log::debug!("{}", child.id);
 	}
@@ -21,7 +19,7 @@ pub fn append_child() {
 
 	parent.append_child(child.upcast());
 
-	parent.with_children(print_children);
+	print_children(parent.children());
 }
 
 #[test]
@@ -34,5 +32,39 @@ pub fn append_child2() {
 	parent.append_child(child1.upcast());
 	parent.append_child(child2.upcast());
 
-	parent.with_children(print_children);
+	print_children(parent.children());
+}
+
+#[test]
+pub fn drop_parent_detaches_children() {
+	let system = System::default();
+
+	let parent = system.create::<Element>();
+	let child = system.create::<Element>();
+
+	parent.append_child(child.upcast());
+
+	assert!(child.parent().is_some());
+
+	drop(parent);
+
+	assert!(child.parent().is_none());
+}
+
+#[test]
+pub fn remove_child_keeps_siblings() {
+	let system = System::default();
+	let parent = system.create::<Element>();
+	let child1 = system.create::<Element>();
+	let child2 = system.create::<Element>();
+
+	parent.append_child(child1.upcast());
+	parent.append_child(child2.upcast());
+	parent.remove_child(child1.upcast());
+
+	let children = parent.children();
+
+	assert_eq!(children.len(), 1);
+	assert_eq!(children[0].id, child2.id);
+	assert!(child1.parent().is_none());
 }
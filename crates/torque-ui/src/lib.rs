@@ -1,20 +1,31 @@
-mod children;
 mod element;
 pub mod layout;
 mod node;
-mod parent;
+pub mod stage;
 mod tree;
 mod window;
 
+use torque_ecs::Schedule;
+
 pub use self::{
-	children::Children,
 	element::{Element, ElementMethods},
 	node::{Node, NodeMethods},
-	parent::Parent,
 	tree::Tree,
 	window::Window,
 };
 
+/// A schedule with the frame [`stage`]s and no tasks, for a window to add its tasks to.
+pub fn schedule() -> Schedule {
+	let mut schedule = Schedule::new();
+
+	schedule
+		.add_stage(stage::PRE_LAYOUT)
+		.add_stage(stage::LAYOUT)
+		.add_stage(stage::PAINT);
+
+	schedule
+}
+
 m8::module! {
 	name: "@torque-rs/ui",
 	exports: [
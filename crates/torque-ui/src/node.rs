@@ -1,14 +1,19 @@
-use torque_ecs::{Entity, EntityMethods, EntityRef, WeakEntityRef};
+use torque_ecs::{Entity, EntityMethods, EntityRef, Ref, RefMut, WeakEntityRef};
 use torque_style::{Layout, MaxSize, MinSize, Resolve, Size, Style};
 
-use crate::{layout, Element, Parent};
+use crate::{layout, Element};
 
 pub trait NodeMethods<E>: EntityMethods<E>
 where
 	E: Entity + 'static,
 {
 	fn parent(&self) -> Option<WeakEntityRef<Element>> {
-		self.get_or_default::<Parent>()
+		let system = self.system();
+
+		system
+			.parent_of(self.id())
+			.and_then(|parent_id| system.get::<Element>(parent_id))
+			.map(EntityMethods::downgrade)
 	}
 
 	fn with_style<R>(&self, f: impl FnOnce(&Style) -> R) -> R {
@@ -19,19 +24,23 @@ where
 		self.with_mut_or_default::<Style, _>(f)
 	}
 
+	/// The style of the node, which every [`Node`] carries.
+	fn style(&self) -> Ref<'_, Style> {
+		self.get_ref::<Style>()
+	}
+
+	fn style_mut(&self) -> RefMut<'_, Style> {
+		self.get_mut::<Style>()
+	}
+
 	fn compute_layout(&self, input: layout::Input) -> layout::Output {
-		let (layout, size, min_size, max_size) = self.with_style(|style| {
-			(
-				style.get_or_default::<Layout>(),
-				style.get_or_default::<Size>(),
-				style.get_or_default::<MinSize>(),
-				style.get_or_default::<MaxSize>(),
-			)
-		});
-
-		let size = size.resolve(input.parent_size);
-		let min_size = min_size.resolve(input.parent_size);
-		let max_size = max_size.resolve(input.parent_size);
+		let style = self.style();
+		let layout = style.get_or_default::<Layout>();
+		let size = style.get_or_default::<Size>().resolve(input.parent_size);
+		let min_size = style.get_or_default::<MinSize>().resolve(input.parent_size);
+		let max_size = style.get_or_default::<MaxSize>().resolve(input.parent_size);
+
+		drop(style);
 
 		match layout {
 			Layout::Row => todo!(),
@@ -43,6 +52,7 @@ where
 }
 
 #[derive(Entity)]
+#[require(Style)]
 pub struct Node;
 
 impl Node {
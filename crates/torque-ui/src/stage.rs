@@ -0,0 +1,10 @@
+//! The stages of the [`Schedule`](torque_ecs::Schedule) a window runs every frame, in order.
+
+/// Resolves styles and anything else layout depends on.
+pub const PRE_LAYOUT: &str = "PreLayout";
+
+/// Measures and places nodes.
+pub const LAYOUT: &str = "Layout";
+
+/// Records draw commands and presents the frame.
+pub const PAINT: &str = "Paint";
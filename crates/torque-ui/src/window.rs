@@ -1,6 +1,10 @@
-use std::{fmt, ops::Deref, sync::Arc};
+use std::{
+	fmt,
+	ops::Deref,
+	sync::{Arc, Mutex},
+};
 
-use torque_ecs::{EntityRef, System};
+use torque_ecs::{EntityRef, Schedule, System};
 use winit::{error::OsError, window::WindowId};
 
 #[derive(Debug, thiserror::Error, serde::Serialize)]
@@ -97,6 +101,7 @@ impl Window {
 					device,
 					queue,
 					system: System::default(),
+					schedule: Mutex::new(crate::schedule()),
 				},
 			)
 		});
@@ -125,6 +130,7 @@ pub struct Inner {
 	device: wgpu::Device,
 	queue: wgpu::Queue,
 	system: System,
+	schedule: Mutex<Schedule>,
 	//root: EntityRef<Element>,
 }
 
@@ -149,6 +155,11 @@ impl Inner {
 		self.window.set_visible(visible);
 	}
 
+	/// Runs the frame schedule against the system of the window.
+	pub fn redraw(&self) {
+		self.schedule.lock().unwrap().run(&self.system);
+	}
+
 	/*pub fn create_element(&self) -> EntityRef<Element> {
 		let element = self.system.create::<Element>();
 